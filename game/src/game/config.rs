@@ -2,13 +2,16 @@ use game_macros::UIEditable;
 
 use crate::game::{ui::FONT_SIZE_MEDIUM, UIEdit};
 use crate::math::{v2, Vector2};
-use crate::physics::rigidbody::SharedPropertySelection;
-use crate::rendering::Color;
+use crate::physics::rigidbody::{CorrectionMode, SharedPropertySelection};
+use crate::physics::sph::KernelKind;
+use crate::physics::units::EARTH_GRAVITY_CM;
+use crate::rendering::{Color, ColorMixMode, FluidColorMode, RenderStyle};
 use crate::utility::AsMq;
 
 use macroquad::text::draw_text;
+use serde_derive::Serialize;
 
-use super::Selection;
+use super::{Ranged, Selection};
 
 const SELECTION_VALUES: [SharedPropertySelection; 4] = [
     SharedPropertySelection::Average,
@@ -20,7 +23,67 @@ const SELECTION_NAMES: [&str; 4] = ["Average", "Min", "Max", "Multiply"];
 const SELECTION_BOX: Selection<SharedPropertySelection, 4> =
     Selection::new(SELECTION_VALUES, SELECTION_NAMES);
 
-#[derive(Clone, UIEditable)]
+const CORRECTION_VALUES: [CorrectionMode; 3] = [
+    CorrectionMode::Positional,
+    CorrectionMode::Baumgarte,
+    CorrectionMode::Blend,
+];
+const CORRECTION_NAMES: [&str; 3] = ["Positional", "Baumgarte", "Blend"];
+const CORRECTION_BOX: Selection<CorrectionMode, 3> =
+    Selection::new(CORRECTION_VALUES, CORRECTION_NAMES);
+
+const BOUNDARY_VALUES: [BoundaryMode; 3] = [
+    BoundaryMode::Walls,
+    BoundaryMode::Reflect,
+    BoundaryMode::Wrap,
+];
+const BOUNDARY_NAMES: [&str; 3] = ["Walls", "Reflect", "Wrap"];
+const BOUNDARY_BOX: Selection<BoundaryMode, 3> = Selection::new(BOUNDARY_VALUES, BOUNDARY_NAMES);
+
+const TAG_FILTER_VALUES: [TagFilterMode; 3] = [
+    TagFilterMode::Off,
+    TagFilterMode::Hide,
+    TagFilterMode::Highlight,
+];
+const TAG_FILTER_NAMES: [&str; 3] = ["Off", "Hide", "Highlight"];
+const TAG_FILTER_BOX: Selection<TagFilterMode, 3> =
+    Selection::new(TAG_FILTER_VALUES, TAG_FILTER_NAMES);
+
+const FLUID_ORDER_VALUES: [FluidDrawOrder; 2] =
+    [FluidDrawOrder::BehindBodies, FluidDrawOrder::OverBodies];
+const FLUID_ORDER_NAMES: [&str; 2] = ["Behind bodies", "Over bodies"];
+const FLUID_ORDER_BOX: Selection<FluidDrawOrder, 2> =
+    Selection::new(FLUID_ORDER_VALUES, FLUID_ORDER_NAMES);
+
+const RENDER_STYLE_VALUES: [RenderStyle; 3] = [
+    RenderStyle::FilledWithOutline,
+    RenderStyle::Filled,
+    RenderStyle::Wireframe,
+];
+const RENDER_STYLE_NAMES: [&str; 3] = ["Filled with outline", "Filled", "Wireframe"];
+const RENDER_STYLE_BOX: Selection<RenderStyle, 3> =
+    Selection::new(RENDER_STYLE_VALUES, RENDER_STYLE_NAMES);
+
+const FLUID_COLOR_VALUES: [FluidColorMode; 2] =
+    [FluidColorMode::Particle, FluidColorMode::Pressure];
+const FLUID_COLOR_NAMES: [&str; 2] = ["Particle", "Pressure"];
+const FLUID_COLOR_BOX: Selection<FluidColorMode, 2> =
+    Selection::new(FLUID_COLOR_VALUES, FLUID_COLOR_NAMES);
+
+const COLOR_MIX_VALUES: [ColorMixMode; 3] = [
+    ColorMixMode::Average,
+    ColorMixMode::Dominant,
+    ColorMixMode::Additive,
+];
+const COLOR_MIX_NAMES: [&str; 3] = ["Average", "Dominant", "Additive"];
+const COLOR_MIX_BOX: Selection<ColorMixMode, 3> = Selection::new(COLOR_MIX_VALUES, COLOR_MIX_NAMES);
+
+const KERNEL_VALUES: [KernelKind; 3] =
+    [KernelKind::Quadratic, KernelKind::Poly6, KernelKind::Spiky];
+const KERNEL_NAMES: [&str; 3] = ["Quadratic", "Poly6", "Spiky"];
+const KERNEL_BOX: Selection<KernelKind, 3> = Selection::new(KERNEL_VALUES, KERNEL_NAMES);
+
+#[derive(Clone, Serialize, UIEditable)]
 pub struct GameConfig {
     pub description: &'static str,
     #[display_as("Time Step [s]")]
@@ -28,14 +91,52 @@ pub struct GameConfig {
     /// This will divide the `time_step` into **n** parts and perform **n** steps of the physical simulation
     /// with those time steps. Leads to better accuracy at cost of performance.
     pub sub_steps: u8,
+    /// If true, the simulation advances by the real time that passed since the last frame, in
+    /// steps of `time_step`. The bodies are drawn between their last 2 steps, so they move
+    /// smoothly even when a frame does not take exactly one step. Otherwise a single step is
+    /// done every frame.
+    #[display_as("Real-time stepping")]
+    pub real_time_stepping: bool,
+    /// Number of threads the simulations run on. Lower values leave more of the CPU to other
+    /// programs. Zero uses all cores.
+    #[display_as("Worker threads")]
+    pub worker_threads: u8,
+    /// If false, the walls take the elasticity of the body hitting them.
+    #[display_as("Custom wall elasticity")]
+    pub custom_wall_elasticity: bool,
+    /// Elasticity of the 4 walls around the world. Used only with `custom_wall_elasticity`.
+    #[display_as("Wall elasticity")]
+    pub wall_elasticity: Ranged,
+    /// What happens to objects at the edges of the world.
+    #[display_as("World edges")]
+    pub boundary_mode: Selection<BoundaryMode, 3>,
+    /// The walls can be removed one by one, for example to get an open container without a
+    /// ceiling. Used only with the `BoundaryMode::Walls` mode.
+    #[display_as("Floor")]
+    pub floor_enabled: bool,
+    #[display_as("Ceiling")]
+    pub ceiling_enabled: bool,
+    #[display_as("Left wall")]
+    pub left_wall_enabled: bool,
+    #[display_as("Right wall")]
+    pub right_wall_enabled: bool,
+    /// Bodies this close to the cursor can be selected even when the cursor is not inside them.
+    /// Makes thin bodies easier to pick.
+    #[display_as("Pick tolerance [cm]")]
+    pub pick_tolerance: Ranged,
+    /// Turns gravity off for both fluid and bodies without changing the `gravity` vector.
+    #[display_as("Gravity enabled")]
+    pub gravity_enabled: bool,
     /// The force of gravity acting on the fluid.
-    #[display_as("Gravity [cm/s]")]
+    #[display_as("Gravity [cm/s^2]")]
     #[gap_after(v2!(0.0, 30.0))]
     pub gravity: Vector2<f32>,
     #[display_as("Fluids")]
     pub sph_config: SphConfig,
     #[display_as("Rigidbodies")]
     pub rb_config: RigidBodiesConfig,
+    #[display_as("Rendering")]
+    pub render_config: RenderConfig,
 }
 
 impl Default for GameConfig {
@@ -44,21 +145,61 @@ impl Default for GameConfig {
             description: "These are the values to configure the underlaying physics simulation.",
             time_step: 0.01,
             sub_steps: 2,
-            gravity: Vector2::new(0.0, 981.0),
+            real_time_stepping: false,
+            worker_threads: 0,
+            custom_wall_elasticity: false,
+            wall_elasticity: Ranged::new(0.5, 0.0, 1.0),
+            boundary_mode: BOUNDARY_BOX,
+            floor_enabled: true,
+            ceiling_enabled: true,
+            left_wall_enabled: true,
+            right_wall_enabled: true,
+            pick_tolerance: Ranged::new(5.0, 0.0, 20.0),
+            gravity_enabled: true,
+            gravity: Vector2::new(0.0, EARTH_GRAVITY_CM),
             sph_config: SphConfig::default(),
             rb_config: RigidBodiesConfig::default(),
+            render_config: RenderConfig::default(),
         }
     }
 }
 
 /// Values for configuring the SPH fluid simulation.
-#[derive(Clone, UIEditable)]
+#[derive(Clone, Serialize, UIEditable)]
 pub struct SphConfig {
     /// Base pressure multiplier for each particle. Individual values are computed using this and
     /// the particles mass.
     pub base_pressure: f32,
     /// Similiar to `base_pressure` but only affects the particles effect on rigidbodies.
     pub base_body_force: f32,
+    /// The smoothing kernel used for density and pressure calculations.
+    pub kernel: Selection<KernelKind, 3>,
+    /// Sums the forces from neighbors in the same order every step so that the results are
+    /// reproducible. Slightly slower.
+    pub deterministic: bool,
+    /// Periodically merges particles of the same fluid that are very close to each other.
+    /// Lowers the particle count without changing the mass or momentum of the fluid.
+    #[display_as("Merge close particles")]
+    pub coalesce: bool,
+    /// Particles closer than this are merged. In centimeters.
+    #[display_as("Merge distance")]
+    pub coalesce_distance: f32,
+    /// How many times per step are the particles moved apart to keep the fluid from compressing.
+    /// Makes the fluid less bouncy at cost of performance. Zero turns it off.
+    #[display_as("Incompressible iterations")]
+    pub incompressible_iterations: u8,
+    /// Particles within this distance are searched for when computing densities and pressures.
+    /// The search covers whole cells of the lookup grid, which are twice the smoothing radius
+    /// wide. In centimeters.
+    #[display_as("Neighbor search radius")]
+    pub neighbor_search_radius: Ranged,
+    /// How much does the fluid bounce off bodies.
+    #[display_as("Body collision elasticity")]
+    pub particle_elasticity: Ranged,
+    /// How much is the fluid slowed down when sliding along bodies. High values make it stick to
+    /// them.
+    #[display_as("Body collision friction")]
+    pub particle_friction: Ranged,
 }
 
 impl Default for SphConfig {
@@ -66,15 +207,60 @@ impl Default for SphConfig {
         SphConfig {
             base_pressure: 100_000.0,
             base_body_force: 10_000.0,
+            kernel: KERNEL_BOX,
+            deterministic: false,
+            coalesce: false,
+            coalesce_distance: 1.0,
+            incompressible_iterations: 0,
+            neighbor_search_radius: Ranged::new(24.0, 0.0, 72.0),
+            particle_elasticity: Ranged::new(0.3, 0.0, 1.0),
+            particle_friction: Ranged::new(0.0, 0.0, 1.0),
         }
     }
 }
 
-#[derive(Clone, UIEditable)]
+/// Values for configuring the rigidbody simulation.
+#[derive(Clone, Serialize, UIEditable)]
 pub struct RigidBodiesConfig {
+    /// How are the elasticities of 2 colliding bodies combined into one.
+    #[display_as("Elasticity mode")]
     pub elasticity_selection: Selection<SharedPropertySelection, 4>,
+    /// How are the frictions of 2 colliding bodies combined into one.
+    #[display_as("Friction mode")]
     pub friction_selection: Selection<SharedPropertySelection, 4>,
-    pub iterations: u32,
+    /// How many times are the collision impulses resolved in a step.
+    #[display_as("Velocity iterations")]
+    pub velocity_iterations: u32,
+    /// How many times are penetrating bodies pushed apart in a step.
+    #[display_as("Position iterations")]
+    pub position_iterations: u32,
+    /// Whether the penetrations are removed by pushing the bodies apart through their velocity,
+    /// by moving them directly, or both.
+    #[display_as("Correction mode")]
+    pub correction_mode: Selection<CorrectionMode, 3>,
+    /// Bodies moving further than this in a step are moved in smaller parts so that they do not
+    /// pass through thin bodies. Zero disables it.
+    #[display_as("Max move per step [cm]")]
+    pub max_move_step: f32,
+    /// Bodies collide as if they were larger by this in every direction, so resting bodies keep a
+    /// small gap between them. The gap is visible once it is larger than the allowed penetration.
+    #[display_as("Contact offset [cm]")]
+    pub contact_offset: f32,
+    /// Bodies penetrating deeper than this are moved apart at once instead of gradually, so that
+    /// they do not stay stuck in each other for many frames. Zero disables it.
+    #[display_as("Snap penetration [cm]")]
+    pub snap_penetration: f32,
+    /// Shuffles the order in which collisions are resolved to reduce directional bias.
+    #[display_as("Randomize contact order")]
+    pub randomize_contact_order: bool,
+    /// Resolves each collision in a single point in the middle of its contact points. Faster, but
+    /// bodies lying on each other rotate less accurately.
+    #[display_as("Single contact point")]
+    pub single_contact_point: bool,
+    /// Contacts approaching slower than this do not bounce. Stops bouncy bodies from jittering
+    /// when they should be resting.
+    #[display_as("Restitution threshold [cm/s]")]
+    pub restitution_velocity_threshold: f32,
 }
 
 impl Default for RigidBodiesConfig {
@@ -82,7 +268,215 @@ impl Default for RigidBodiesConfig {
         RigidBodiesConfig {
             elasticity_selection: SELECTION_BOX,
             friction_selection: SELECTION_BOX,
-            iterations: 6,
+            velocity_iterations: 6,
+            position_iterations: 2,
+            correction_mode: CORRECTION_BOX,
+            max_move_step: 10.0,
+            contact_offset: 1.0,
+            snap_penetration: 20.0,
+            randomize_contact_order: false,
+            single_contact_point: false,
+            restitution_velocity_threshold: 50.0,
+        }
+    }
+}
+
+/// Values for configuring visualizations drawn on top of the simulation.
+#[derive(Clone, Serialize, UIEditable)]
+pub struct RenderConfig {
+    /// Draws the recently travelled path of each dynamic body.
+    #[display_as("Draw trails")]
+    pub draw_trails: bool,
+    /// Bodies briefly flash white after a hard impact.
+    #[display_as("Flash on impacts")]
+    pub impact_flash: bool,
+    /// How many recent positions are kept for each trail.
+    pub trail_length: u32,
+    /// Draws coordinate labels along the edges of the gameview and marks the origin.
+    #[display_as("Draw coordinates")]
+    pub draw_coordinates: bool,
+    /// Colors bodies from green to red based on how much contact force they are under.
+    #[display_as("Stress heatmap")]
+    pub draw_stress_heatmap: bool,
+    /// Scales the gameview to fill the window while keeping its aspect ratio. If false, it is drawn
+    /// with 1 pixel per centimeter in the top-left corner.
+    #[display_as("Fit gameview to window")]
+    pub fit_gameview: bool,
+    /// If false, the walls are not drawn but still collide.
+    #[display_as("Draw walls")]
+    pub draw_walls: bool,
+    /// Draws the edges of the triangles each polygon is split into.
+    #[display_as("Draw triangulation")]
+    pub draw_triangulation: bool,
+    /// Draws the axes used for collision checks of the polygon under the cursor.
+    #[display_as("Draw projection axes")]
+    pub draw_projection_axes: bool,
+    /// Draws the speed and angular velocity next to each moving dynamic body.
+    #[display_as("Draw body velocities")]
+    pub draw_body_velocities: bool,
+    /// Draws arrows showing the velocity of the fluid on a grid.
+    #[display_as("Draw flow field")]
+    pub draw_flow_field: bool,
+    /// Distance between 2 neighboring arrows of the flow field.
+    #[display_as("Flow field spacing")]
+    pub flow_field_spacing: Ranged,
+    /// How are rigidbodies drawn.
+    #[display_as("Body style")]
+    pub body_style: Selection<RenderStyle, 3>,
+    /// Thickness of the darker outline drawn around rigidbodies. Zero disables the outline.
+    #[display_as("Outline thickness")]
+    pub outline_thickness: f32,
+    /// Number of straight segments circular bodies are drawn with. More segments keep large
+    /// circles smooth.
+    #[display_as("Circle segments")]
+    pub circle_segments: u8,
+    /// Size of a cell of the fluid rendering grid. Larger cells trade fluid detail for performance.
+    #[display_as("Fluid cell size")]
+    pub fluid_cell_size: Ranged,
+    /// What is the fluid colored by. Pressure makes compression waves visible.
+    #[display_as("Fluid color")]
+    pub fluid_color_mode: Selection<FluidColorMode, 2>,
+    /// How are the colors of different fluids mixed where they meet. Dominant keeps the
+    /// boundaries sharp, average blends them.
+    #[display_as("Fluid color mixing")]
+    pub fluid_color_mix: Selection<ColorMixMode, 3>,
+    /// Color channels weaker than this are left out when blending the colors of neighboring
+    /// fluid. Higher values keep the colors of different fluids more separated.
+    #[display_as("Color blend threshold")]
+    pub fluid_blend_threshold: Ranged,
+    /// Higher values make the thin edges of the fluid more transparent.
+    #[display_as("Fluid alpha exponent")]
+    pub fluid_alpha_exponent: Ranged,
+    /// Draws deeper fluid darker and more opaque for a look of water seen from above. The depth is
+    /// estimated from the density of the fluid.
+    #[display_as("Depth shading")]
+    pub fluid_depth_shading: bool,
+    /// Smears fast flowing fluid along its flow. The value is the exposure time in seconds, zero
+    /// turns the blur off.
+    #[display_as("Fluid motion blur [s]")]
+    pub fluid_motion_blur: Ranged,
+    /// Drawing the fluid over bodies makes partially submerged bodies look like they are in it.
+    /// Bodies marked to be drawn over fluid stay on top either way.
+    #[display_as("Fluid drawn")]
+    pub fluid_draw_order: Selection<FluidDrawOrder, 2>,
+    /// What happens to bodies with tag equal to `tag_filter`.
+    #[display_as("Tag filter mode")]
+    pub tag_filter_mode: Selection<TagFilterMode, 3>,
+    #[display_as("Tag filter")]
+    pub tag_filter: String,
+}
+
+impl Default for RenderConfig {
+    fn default() -> Self {
+        RenderConfig {
+            draw_trails: false,
+            impact_flash: true,
+            trail_length: 100,
+            draw_coordinates: false,
+            draw_stress_heatmap: false,
+            fit_gameview: true,
+            draw_walls: true,
+            draw_triangulation: false,
+            draw_projection_axes: false,
+            draw_body_velocities: false,
+            draw_flow_field: false,
+            flow_field_spacing: Ranged::new(25.0, 10.0, 100.0),
+            body_style: RENDER_STYLE_BOX,
+            outline_thickness: 2.0,
+            circle_segments: 48,
+            fluid_cell_size: Ranged::new(5.0, 2.0, 25.0),
+            fluid_color_mode: FLUID_COLOR_BOX,
+            fluid_color_mix: COLOR_MIX_BOX,
+            fluid_blend_threshold: Ranged::new(0.2, 0.0, 1.0),
+            fluid_alpha_exponent: Ranged::new(1.0, 0.25, 4.0),
+            fluid_depth_shading: false,
+            fluid_motion_blur: Ranged::new(0.0, 0.0, 0.1),
+            fluid_draw_order: FLUID_ORDER_BOX,
+            tag_filter_mode: TAG_FILTER_BOX,
+            tag_filter: String::new(),
+        }
+    }
+}
+
+/// What happens to objects at the edges of the world.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BoundaryMode {
+    /// The 4 wall bodies keep everything inside.
+    Walls,
+    /// The walls are disabled and objects bounce off the edges.
+    Reflect,
+    /// The walls are disabled and objects leaving one edge appear on the opposite one.
+    Wrap,
+}
+
+impl BoundaryMode {
+    /// Returns the position and velocity of an object brought back into the world of the given
+    /// `size`. Objects inside the world are left as they are.
+    pub fn confine(
+        &self,
+        position: Vector2<f32>,
+        velocity: Vector2<f32>,
+        size: Vector2<f32>,
+    ) -> (Vector2<f32>, Vector2<f32>) {
+        let confine_axis = |pos: f32, vel: f32, size: f32| match self {
+            BoundaryMode::Walls => (pos, vel),
+            BoundaryMode::Reflect if pos < 0.0 => ((-pos).min(size), vel.abs()),
+            BoundaryMode::Reflect if pos > size => ((2.0 * size - pos).max(0.0), -vel.abs()),
+            BoundaryMode::Reflect => (pos, vel),
+            BoundaryMode::Wrap => (pos.rem_euclid(size), vel),
+        };
+
+        let (x, vx) = confine_axis(position.x, velocity.x, size.x);
+        let (y, vy) = confine_axis(position.y, velocity.y, size.y);
+        (v2!(x, y), v2!(vx, vy))
+    }
+}
+
+/// Is the fluid drawn before or after the bodies.
+#[derive(Clone, Copy, PartialEq)]
+pub enum FluidDrawOrder {
+    BehindBodies,
+    OverBodies,
+}
+
+/// How are the bodies matching the tag filter drawn.
+#[derive(Clone, Copy, PartialEq)]
+pub enum TagFilterMode {
+    Off,
+    Hide,
+    Highlight,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BoundaryMode;
+    use crate::math::{v2, Vector2};
+
+    #[test]
+    fn boundary_modes_confine_positions() {
+        let size = v2!(100.0, 50.0);
+        let velocity = v2!(-10.0, 5.0);
+        let inside = v2!(30.0, 20.0);
+        let outside = v2!(-5.0, 60.0);
+
+        for mode in [
+            BoundaryMode::Walls,
+            BoundaryMode::Reflect,
+            BoundaryMode::Wrap,
+        ] {
+            assert_eq!(mode.confine(inside, velocity, size), (inside, velocity));
         }
+        assert_eq!(
+            BoundaryMode::Walls.confine(outside, velocity, size),
+            (outside, velocity)
+        );
+        assert_eq!(
+            BoundaryMode::Reflect.confine(outside, velocity, size),
+            (v2!(5.0, 40.0), v2!(10.0, -5.0))
+        );
+        assert_eq!(
+            BoundaryMode::Wrap.confine(outside, velocity, size),
+            (v2!(95.0, 10.0), velocity)
+        );
     }
 }
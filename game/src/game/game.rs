@@ -1,29 +1,180 @@
-use std::{collections::LinkedList, f32::consts::PI};
+use std::{
+    collections::{LinkedList, VecDeque},
+    f32::consts::PI,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
 
 use macroquad::{
+    camera::{set_camera, set_default_camera, Camera2D},
     input::{
-        is_key_pressed, is_mouse_button_down, is_mouse_button_pressed, is_mouse_button_released,
-        mouse_position, KeyCode, MouseButton,
+        is_key_down, is_key_pressed, is_mouse_button_down, is_mouse_button_pressed,
+        is_mouse_button_released, mouse_position, mouse_wheel, KeyCode, MouseButton,
     },
-    shapes::draw_circle,
-    text::draw_text,
-    window::clear_background,
+    shapes::{draw_circle, draw_line, draw_rectangle_lines},
+    text::{draw_text, measure_text},
+    time::get_frame_time,
+    window::{clear_background, screen_height, screen_width},
 };
+use rayon::{ThreadPool, ThreadPoolBuilder};
 
 use crate::{
     math::{v2, Vector2},
-    physics::rigidbody::{BodyBehaviour, RbSimulator, Rectangle, RigidBody, SharedProperty},
-    rendering::{Color, Draw, MarchingSquaresRenderer, Renderer},
-    serialization::{GameSerializedForm, SerializationForm},
-    utility::AsMq,
+    physics::{
+        rigidbody::{
+            presets::{catapult, Catapult},
+            Anchor, BodyBehaviour, DistanceConstraint, RbSimulator, Rectangle, RigidBody,
+            SharedProperty,
+        },
+        sph::FlowProbe,
+        units::{from_cm, to_cm, EARTH_GRAVITY, EARTH_GRAVITY_CM},
+    },
+    rendering::{
+        draw_orientation_indicator, draw_outline_with_color, draw_styled, draw_styled_with_color,
+        draw_trail, draw_triangulation_edges, Color, MarchingSquaresRenderer, Renderer,
+    },
+    serialization::{
+        BodySerializationForm, BodySerializedForm, Bounds, GameSerializedForm, SceneStats,
+        SerializationForm,
+    },
+    shapes::convex_hull,
+    utility::{AsMq, StateHasher},
     Particle, Sph,
 };
 
 use super::{
-    config::GameConfig, save_load, EntityInfo, FluidSelectorAction, InGameUI, QuickAction,
-    SaveLoadAction, Tool, FONT_SIZE_LARGE, FONT_SIZE_SMALL,
+    config::{BoundaryMode, FluidDrawOrder, GameConfig, TagFilterMode},
+    gif_recorder::GifRecorder,
+    rewind::{RewindBuffer, Snapshot},
+    save_load,
+    step_accumulator::StepAccumulator,
+    EntityInfo, FluidSelectorAction, InGameUI, MassMode, QuickAction, SaveLoadAction, Tool,
+    FONT_SIZE_LARGE, FONT_SIZE_SMALL,
 };
 
+/// Number of the wall bodies at the start of the body list. They are always there, but each one
+/// can be disabled in the config.
+const WALL_COUNT: usize = 4;
+
+/// Distance between 2 neighboring labeled ticks of the coordinate overlay.
+const COORDINATE_TICK_SPACING: f32 = 100.0;
+const COORDINATE_TICK_LENGTH: f32 = 8.0;
+
+/// Outline of bodies highlighted by the tag filter.
+const HIGHLIGHT_COLOR: Color = Color::rgb(255, 220, 0);
+const HIGHLIGHT_THICKNESS: f32 = 4.0;
+/// Outline of bodies touching the body selected in the info tool.
+const CONTACT_HIGHLIGHT_COLOR: Color = Color::rgb(0, 160, 255);
+
+/// Color of the edges of the triangulation overlay.
+const TRIANGULATION_COLOR: Color = Color::rgb(255, 255, 255);
+
+/// Color of the line of the flow probe.
+const FLOW_PROBE_COLOR: Color = Color::rgb(0, 220, 220);
+const FLOW_PROBE_THICKNESS: f32 = 3.0;
+/// Length of the arrow showing the positive direction of the flow probe.
+const FLOW_PROBE_ARROW_LENGTH: f32 = 15.0;
+
+/// Color and size of the highlighted traced particle.
+const TRACED_PARTICLE_COLOR: Color = Color::rgb(255, 255, 0);
+const TRACED_PARTICLE_RADIUS: f32 = 4.0;
+
+/// Outline of the box of fluid to be frozen.
+const FREEZE_REGION_COLOR: Color = Color::rgb(150, 220, 255);
+const FREEZE_REGION_THICKNESS: f32 = 2.0;
+
+/// Change of velocity in a single step in cm/s from which a body starts to flash and at which it
+/// flashes fully.
+const IMPACT_FLASH_MIN_SPEED: f32 = 50.0;
+const IMPACT_FLASH_FULL_SPEED: f32 = 300.0;
+/// How long it takes a full flash to fade out, in seconds.
+const IMPACT_FLASH_DURATION: f32 = 0.3;
+const IMPACT_FLASH_COLOR: Color = Color::rgb(255, 255, 255);
+
+/// Density of the circles of a rope in g/cm^2.
+const ROPE_DENSITY: f32 = 2.0;
+const ROPE_COLOR: Color = Color::rgb(140, 90, 40);
+/// Distance between the circles of a rope spawned by dragging and their diameter, in cm.
+const ROPE_SEGMENT_LENGTH: f32 = 15.0;
+const ROPE_THICKNESS: f32 = 10.0;
+/// Color of the lines drawn between the ends of distance constraints.
+const CONSTRAINT_COLOR: Color = Color::rgb(90, 60, 30);
+const CONSTRAINT_THICKNESS: f32 = 2.0;
+
+/// Launch velocity of a spawned body per centimeter of dragging back from the spawn point.
+const LAUNCH_SPEED_PER_DRAG: f32 = 5.0;
+/// Color of the line showing the direction and speed of a body being launched.
+const LAUNCH_AIM_COLOR: Color = Color::rgb(255, 120, 0);
+const LAUNCH_AIM_THICKNESS: f32 = 2.0;
+
+/// Color and length of the rays of the projection axes overlay.
+const PROJECTION_AXIS_COLOR: Color = Color::rgb(255, 0, 255);
+const PROJECTION_AXIS_LENGTH: f32 = 40.0;
+
+/// Bodies slower than this in cm/s do not get their velocity drawn.
+const BODY_VELOCITY_MIN_SPEED: f32 = 1.0;
+/// Maximum number of drawn body velocities. The fastest bodies are preferred.
+const MAX_BODY_VELOCITY_LABELS: usize = 30;
+const BODY_VELOCITY_COLOR: Color = Color::rgb(0, 0, 0);
+
+/// Length of the fastest arrow of the flow field relative to the spacing of the arrows.
+const FLOW_FIELD_ARROW_SCALE: f32 = 0.9;
+/// Length of the head of the flow field arrows relative to the spacing of the arrows.
+const FLOW_FIELD_HEAD_SCALE: f32 = 0.2;
+
+/// Gravity is rotated by this many degrees with the left and right arrow keys and scaled by this
+/// factor with the up and down arrow keys.
+const GRAVITY_ROTATION_STEP: f32 = 15.0;
+const GRAVITY_SCALE_STEP: f32 = 1.25;
+/// The gravity arrow is drawn this far from the top-right corner of the gameview. The arrow of
+/// the Earth's gravity has the given length and stronger gravity is drawn at most twice as long.
+const GRAVITY_ARROW_MARGIN: f32 = 50.0;
+const GRAVITY_ARROW_LENGTH: f32 = 30.0;
+const GRAVITY_ARROW_COLOR: Color = Color::rgb(255, 255, 255);
+
+/// Keys selecting the slots of the body hotbar.
+const HOTBAR_KEYS: [KeyCode; HOTBAR_SLOTS] = [
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+];
+pub(crate) const HOTBAR_SLOTS: usize = 9;
+
+/// A body whose copies are spawned from the hotbar. Its position is replaced on spawn.
+pub type BodyTemplate = RigidBody;
+
+/// Width of the part of the window on the right of the gameview that is reserved for the UI.
+const UI_PANEL_WIDTH: f32 = 450.0;
+/// Height of the part of the window below the gameview that is reserved for the name and
+/// description.
+const DESCRIPTION_HEIGHT: f32 = 300.0;
+
+/// Number of frames recorded into a GIF and the file it is saved to.
+const GIF_FRAMES: usize = 180;
+const GIF_PATH: &str = "recording.gif";
+/// File the statistics of the scene are exported to.
+const STATS_PATH: &str = "stats.json";
+/// File the bodies are exported to.
+const BODIES_PATH: &str = "bodies.json";
+/// File the fluid is imported from, see `Sph::import_csv`.
+const FLUID_CSV_PATH: &str = "fluid.csv";
+/// Elasticities of the boxes dropped when the drop test is run.
+const DROP_TEST_ELASTICITIES: [f32; 3] = [0.0, 0.5, 0.8];
+
+/// Offset of a duplicated body from the original.
+const DUPLICATE_OFFSET: Vector2<f32> = v2!(20.0, 20.0);
+
+/// Number of fragments a body is broken into.
+const FRAGMENT_PIECES: usize = 6;
+/// Speed added to each fragment in the direction away from the center of the broken body. In cm/s.
+const FRAGMENT_SPEED: f32 = 100.0;
+
 struct DraggedBody {
     pub index: usize,
     pub drag_offset: Vector2<f32>,
@@ -38,11 +189,18 @@ pub struct Game {
     pub(crate) fluid_system: Sph,
     /// If the physics are currently being simulated or not
     is_simulating: bool,
+    /// If the fluid is being simulated while `is_simulating` is true
+    simulate_fluid: bool,
+    /// If the rigidbodies are being simulated while `is_simulating` is true
+    simulate_bodies: bool,
 
     pub(crate) rb_simulator: RbSimulator,
 
     // GUI things
+    /// Position of the top-left corner of the gameview on the screen.
     gameview_offset: Vector2<f32>,
+    /// How many screen pixels is 1 unit of the game world.
+    gameview_scale: f32,
     pub(crate) gameview_width: f32,
     pub(crate) gameview_height: f32,
     renderer: Box<dyn Renderer>,
@@ -53,8 +211,40 @@ pub struct Game {
     pub(crate) name: String,
     pub(crate) description: LinkedList<String>,
 
+    /// Splits the real time into steps with `GameConfig::real_time_stepping`.
+    step_accumulator: StepAccumulator,
+    /// How far between the previous and current state of the bodies they are drawn, in [0..1]
+    /// range. Below 1 only with `GameConfig::real_time_stepping`.
+    render_interpolation: f32,
+
     mouse_position_last_frame: Vector2<f32>,
     dragged_body: Option<DraggedBody>,
+    rewind_buffer: RewindBuffer,
+    /// Bodies spawned at the cursor with the number keys.
+    pub(crate) hotbar: [Option<BodyTemplate>; HOTBAR_SLOTS],
+    /// Corner of the box of fluid to be frozen while it is being drawn.
+    freeze_region_start: Option<Vector2<f32>>,
+    /// Where the body being aimed with right mouse button will be spawned.
+    launch_start: Option<Vector2<f32>>,
+    /// Id of the particle whose path is traced.
+    traced_id: Option<u32>,
+    /// Recent positions of the traced particle, oldest first.
+    traced_trail: VecDeque<Vector2<f32>>,
+    pub(crate) flow_probe: Option<FlowProbe>,
+    /// If true, statistics of the rigidbody solver are printed to stderr every simulated frame.
+    pub debug_log: bool,
+    /// Start of the flow probe line while it is being drawn.
+    flow_probe_start: Option<Vector2<f32>>,
+    /// The last spawned catapult if it was not fired yet.
+    loaded_catapult: Option<Catapult>,
+    /// Records the gameview into a GIF while set.
+    gif_recorder: Option<GifRecorder>,
+    /// Receives the results of the drop test while it runs on a worker thread.
+    drop_test: Option<Receiver<Vec<String>>>,
+    /// Pool of threads in which the simulations are stepped. None uses the global pool.
+    thread_pool: Option<ThreadPool>,
+    /// Number of threads with which `thread_pool` was built.
+    thread_pool_size: u8,
 }
 
 impl Game {
@@ -64,7 +254,8 @@ impl Game {
         let (f_width, f_height) = (width as f32, height as f32);
 
         let sph = Sph::new(f_width, f_height);
-        let renderer_step_size = f_width / 100.0;
+        let game_config = GameConfig::default();
+        let renderer_step_size = game_config.render_config.fluid_cell_size.get_value();
 
         // Add rectangles that act as walls
         let wall_thickness = 20.0;
@@ -87,23 +278,29 @@ impl Game {
         }
 
         let mut ingame_ui = InGameUI::default();
-        ingame_ui.body_maker.set_max_size(f_width.min(f_height));
+        ingame_ui
+            .body_maker
+            .set_max_size(to_cm(f_width.min(f_height)));
 
-        let mut rb_simulator = RbSimulator::new(v2!(0.0, 981.0));
+        let mut rb_simulator = RbSimulator::new(v2!(0.0, EARTH_GRAVITY));
         rb_simulator.bodies = bodies;
+        let thread_pool_size = game_config.worker_threads;
 
         let mut game = Game {
-            game_config: GameConfig::default(),
+            game_config,
 
             quit_flag: false,
             save_name: "_Default".to_string(),
 
             fluid_system: sph,
             is_simulating: true,
+            simulate_fluid: true,
+            simulate_bodies: true,
 
             rb_simulator,
 
             gameview_offset: Vector2::zero(),
+            gameview_scale: 1.0,
             gameview_width: f_width,
             gameview_height: f_height,
             renderer: Box::new(
@@ -123,8 +320,25 @@ impl Game {
             name: String::new(),
             description: LinkedList::new(),
 
+            step_accumulator: StepAccumulator::default(),
+            render_interpolation: 1.0,
+
             mouse_position_last_frame: Vector2::zero(),
             dragged_body: None,
+            rewind_buffer: RewindBuffer::default(),
+            hotbar: Default::default(),
+            freeze_region_start: None,
+            launch_start: None,
+            traced_id: None,
+            traced_trail: VecDeque::new(),
+            flow_probe: None,
+            flow_probe_start: None,
+            debug_log: false,
+            loaded_catapult: None,
+            gif_recorder: None,
+            drop_test: None,
+            thread_pool: build_thread_pool(thread_pool_size),
+            thread_pool_size,
         };
 
         game.preview_body = game.body_from_body_maker(v2!(50.0, 50.0));
@@ -153,50 +367,105 @@ impl Game {
 
     fn body_from_body_maker(&self, position: Vector2<f32>) -> RigidBody {
         let body_maker = &self.ingame_ui.body_maker;
-        let size = body_maker.size();
+        let size = from_cm(body_maker.size());
         let orientation = body_maker.orientation;
         let lock_rotation = body_maker.lock_rotation;
         let mass = body_maker.mass;
         let mut color = body_maker.color();
         let behaviour = body_maker.behaviour;
+        let collides_with_fluid = body_maker.collides_with_fluid;
+        let draw_over_fluid = body_maker.draw_over_fluid;
         let elasticity = body_maker.elasticity;
         let static_friction = body_maker.static_friction;
         let dynamic_friction = body_maker.dynamic_friction;
 
         // Create body and set state values
-        let mut body = Rectangle!(position; size.x, size.y; behaviour);
+        let mut body = if body_maker.ellipse {
+            RigidBody::new_ellipse(position, size.x * 0.5, size.y * 0.5, behaviour)
+        } else {
+            Rectangle!(position; size.x, size.y; behaviour)
+        };
         body.state_mut().orientation = orientation * (PI / 180.0);
         body.state_mut().lock_rotation = lock_rotation;
-        body.state_mut().set_mass(mass);
+        body.state_mut().collides_with_fluid = collides_with_fluid;
+        body.state_mut().draw_over_fluid = draw_over_fluid;
+        match body_maker.mass_mode() {
+            MassMode::Fixed => body.state_mut().set_mass(mass),
+            MassMode::Density => body.set_density(body_maker.density),
+        }
         color.a = 0.5;
         body.state_mut().color = color;
         body.state_mut().elasticity = SharedProperty::Value(elasticity);
         body.state_mut().static_friction = SharedProperty::Value(static_friction);
         body.state_mut().dynamic_friction = SharedProperty::Value(dynamic_friction);
+        // Apply the orientation to the global points
+        body.update_inner_values();
 
         body
     }
 
     pub fn handle_input(&mut self) {
         let mouse_pos = mouse_position();
-        let position = Vector2::new(mouse_pos.0, mouse_pos.1);
-        self.mouse_in_gameview = self.is_in_gameview(position);
+        let screen_position = Vector2::new(mouse_pos.0, mouse_pos.1);
+        self.mouse_in_gameview = self.is_in_gameview(screen_position);
+        let position = self.screen_to_world(screen_position);
 
         // Release dragged body
         if is_mouse_button_released(MouseButton::Left) && self.dragged_body.is_some() {
             self.dragged_body = None;
         }
 
+        // The amount per notch differs between platforms, so only the direction is used
+        let (_, wheel) = mouse_wheel();
+        if wheel != 0.0 && self.mouse_in_gameview {
+            self.ingame_ui.adjust_selected_tool(wheel.signum());
+        }
+
         match self.ingame_ui.selected_tool {
+            Tool::Info => {
+                if is_mouse_button_pressed(MouseButton::Left) && self.mouse_in_gameview {
+                    self.select_body_under_mouse();
+                    self.trace_particle_under_mouse();
+                }
+                self.apply_tag_input();
+
+                // Draw a flow probe by dragging with right mouse button
+                if is_mouse_button_pressed(MouseButton::Right) && self.mouse_in_gameview {
+                    self.flow_probe_start = Some(position);
+                    self.flow_probe = None;
+                }
+                if is_mouse_button_released(MouseButton::Right) {
+                    if let Some(start) = self.flow_probe_start.take() {
+                        // A click without dragging only removes the probe
+                        if (position - start).length() > 1.0 {
+                            self.flow_probe = Some(FlowProbe::new(start, position));
+                        }
+                    }
+                }
+            }
             Tool::Fluid => {
                 if is_mouse_button_down(MouseButton::Left) && self.mouse_in_gameview {
-                    self.add_fluid(position);
+                    let cursor_velocity =
+                        (position - self.mouse_position_last_frame) / self.game_config.time_step;
+                    self.add_fluid(position, cursor_velocity);
+                }
+
+                // Freeze the fluid in a box drawn by dragging with right mouse button
+                if is_mouse_button_pressed(MouseButton::Right) && self.mouse_in_gameview {
+                    self.freeze_region_start = Some(position);
+                }
+                if is_mouse_button_released(MouseButton::Right) {
+                    if let Some(start) = self.freeze_region_start.take() {
+                        let (min, max) = merge_boxes((start, start), (position, position));
+                        self.freeze_fluid_region(min, max);
+                    }
                 }
             }
             Tool::Rigidbody => {
-                if self.ingame_ui.body_maker.changed() {
-                    self.preview_body = self.body_from_body_maker(position);
-                }
+                // Rebuild every frame so that changes in the body maker show up immediately. While
+                // aiming, the preview stays at the spawn point.
+                self.preview_body =
+                    self.body_from_body_maker(self.launch_start.unwrap_or(position));
 
                 // Set dragged body by holding left mouse button on it
                 if is_mouse_button_down(MouseButton::Left) && self.dragged_body.is_none() {
@@ -206,7 +475,7 @@ impl Game {
                         ..
                     } = self.ingame_ui.info_panel.under_mouse_entity
                     {
-                        if index >= 4 {
+                        if !is_wall(index) {
                             self.dragged_body = Some(DraggedBody {
                                 index,
                                 drag_offset: position - body_position,
@@ -216,47 +485,126 @@ impl Game {
                 }
                 // Move dragged body
                 if let Some(DraggedBody { index, drag_offset }) = self.dragged_body {
-                    let state = self.rb_simulator.bodies[index].state_mut();
                     let position = position.clamp(
                         v2!(0.0, 0.0),
                         v2!(self.gameview_width, self.gameview_height),
                     );
+                    let velocity_cap = self.drag_velocity_cap(index, position - drag_offset);
+                    let state = self.rb_simulator.bodies[index].state_mut();
                     match state.behaviour {
                         BodyBehaviour::Dynamic => {
                             let pos_diff = position - state.position - drag_offset;
-                            state.velocity = pos_diff * 10.0;
+                            let velocity = pos_diff * 10.0;
+                            state.velocity = if velocity.length() > velocity_cap {
+                                velocity.normalized() * velocity_cap
+                            } else {
+                                velocity
+                            };
                         }
                         BodyBehaviour::Static => {
                             let new_pos = position - drag_offset;
-                            self.rb_simulator.bodies[index].set_position(new_pos);
+                            let body = &mut self.rb_simulator.bodies[index];
+                            body.set_position(new_pos);
+                            // Follow the cursor without being interpolated
+                            body.state_mut().previous_position = new_pos;
                         }
                     }
                 }
 
-                // Spawn bodies with right click
+                // Spawn bodies with right click. Dragging back before the release launches them
+                // in the opposite direction, like from a slingshot.
                 if is_mouse_button_pressed(MouseButton::Right) && self.mouse_in_gameview {
-                    let new_body = self.body_from_body_maker(position);
-
-                    let mut body = std::mem::replace(&mut self.preview_body, new_body);
-                    // Set color alpha to 1.0 - it was lowered for preview
-                    body.state_mut().color.a = 1.0;
-
-                    self.rb_simulator.bodies.push(body);
+                    self.launch_start = Some(position);
+                } else if is_mouse_button_released(MouseButton::Right) {
+                    if let Some(start) = self.launch_start.take() {
+                        // With Shift held a rope pinned at the start is spawned instead
+                        if is_shift_down() {
+                            let segment_length = from_cm(ROPE_SEGMENT_LENGTH);
+                            let segments = ((position - start).length() / segment_length).ceil();
+                            let thickness = from_cm(ROPE_THICKNESS);
+                            self.spawn_rope(
+                                start,
+                                position,
+                                segments as usize + 1,
+                                thickness,
+                                true,
+                            );
+                        } else {
+                            let new_body = self.body_from_body_maker(position);
+
+                            let mut body = std::mem::replace(&mut self.preview_body, new_body);
+                            let state = body.state_mut();
+                            // Set color alpha to 1.0 - it was lowered for preview
+                            state.color.a = 1.0;
+                            if state.behaviour == BodyBehaviour::Dynamic {
+                                state.velocity = (start - position) * LAUNCH_SPEED_PER_DRAG;
+                            }
+
+                            self.rb_simulator.bodies.push(body);
+                        }
+                    }
                 }
                 // Delete bodies with middle click
                 else if is_mouse_button_pressed(MouseButton::Middle) {
                     if let EntityInfo::Body { index, .. } =
                         self.ingame_ui.info_panel.under_mouse_entity
                     {
-                        // Do not remove the first 4 bodies - those are walls
-                        if index >= 4 {
-                            self.rb_simulator.bodies.swap_remove(index);
+                        // Do not remove the walls
+                        if !is_wall(index) {
+                            self.delete_body(index);
+                        }
+                    }
+                }
+
+                self.handle_hotbar_keys(position);
+
+                // Break bodies into fragments with X
+                if is_key_pressed(KeyCode::X) && !self.ingame_ui.taken_input() {
+                    if let EntityInfo::Body { index, .. } =
+                        self.ingame_ui.info_panel.under_mouse_entity
+                    {
+                        // Do not break the walls
+                        if !is_wall(index) {
+                            self.fragment_body(index, FRAGMENT_PIECES);
+                        }
+                    }
+                }
+
+                // Spawn a catapult with K, pressing it again fires it
+                if is_key_pressed(KeyCode::K) && !self.ingame_ui.taken_input() {
+                    match self.loaded_catapult.take() {
+                        Some(loaded) => loaded.release(&mut self.rb_simulator),
+                        None if self.mouse_in_gameview => {
+                            self.loaded_catapult = Some(catapult(&mut self.rb_simulator, position));
+                        }
+                        None => {}
+                    }
+                }
+
+                // Duplicate bodies with Ctrl + D
+                if is_key_pressed(KeyCode::D)
+                    && (is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl))
+                {
+                    if let EntityInfo::Body { index, .. } =
+                        self.ingame_ui.info_panel.under_mouse_entity
+                    {
+                        // Do not duplicate the walls
+                        if !is_wall(index) {
+                            self.duplicate_body(index, DUPLICATE_OFFSET);
                         }
                     }
-                } else if self.mouse_in_gameview {
-                    self.preview_body.set_position(position);
                 }
             }
+            Tool::Paint if is_mouse_button_down(MouseButton::Left) && self.mouse_in_gameview => {
+                self.paint_bodies(position);
+            }
+            Tool::Stir if is_mouse_button_down(MouseButton::Left) && self.mouse_in_gameview => {
+                // Movement of the cursor during one frame is converted into a velocity
+                let velocity =
+                    (position - self.mouse_position_last_frame) / self.game_config.time_step;
+                self.fluid_system
+                    .stir(position, self.ingame_ui.stirrer.radius, velocity);
+            }
             _ => {}
         }
 
@@ -265,61 +613,321 @@ impl Game {
             self.toggle_pause();
         }
 
+        // Toggle logging of the solver statistics
+        if is_key_pressed(KeyCode::F3) {
+            self.debug_log = !self.debug_log;
+        }
+
+        // Validate the solver by dropping boxes of different elasticities
+        if is_key_pressed(KeyCode::F4) && self.drop_test.is_none() {
+            self.start_drop_test();
+        }
+        self.receive_drop_test();
+
+        // Record a short GIF of the gameview
+        if is_key_pressed(KeyCode::F9) && self.gif_recorder.is_none() {
+            self.record_gif(GIF_FRAMES, GIF_PATH);
+        }
+
+        // Rewind to the last captured state
+        if is_key_pressed(KeyCode::R) && !self.ingame_ui.taken_input() {
+            self.rewind();
+        }
+
+        // Tilt and scale gravity with the arrow keys. The side of the pressed arrow goes down.
+        if !self.ingame_ui.taken_input() {
+            if is_key_pressed(KeyCode::Left) {
+                self.adjust_gravity(GRAVITY_ROTATION_STEP, 1.0);
+            } else if is_key_pressed(KeyCode::Right) {
+                self.adjust_gravity(-GRAVITY_ROTATION_STEP, 1.0);
+            } else if is_key_pressed(KeyCode::Up) {
+                self.adjust_gravity(0.0, GRAVITY_SCALE_STEP);
+            } else if is_key_pressed(KeyCode::Down) {
+                self.adjust_gravity(0.0, 1.0 / GRAVITY_SCALE_STEP);
+            }
+        }
+
         // Set new mouse last pos
         self.mouse_position_last_frame = position;
     }
 
+    /// Rotates the gravity by `degrees` clockwise and multiplies its strength by `scale`.
+    pub fn adjust_gravity(&mut self, degrees: f32, scale: f32) {
+        let gravity = self.game_config.gravity;
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        self.game_config.gravity = v2!(
+            gravity.x * cos - gravity.y * sin,
+            gravity.x * sin + gravity.y * cos
+        ) * scale;
+    }
+
+    /// Runs the drop test for each of `DROP_TEST_ELASTICITIES` on a worker thread, so that the game
+    /// does not freeze until it is done.
+    fn start_drop_test(&mut self) {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let results = DROP_TEST_ELASTICITIES
+                .iter()
+                .map(|&elasticity| {
+                    let result = RbSimulator::drop_test(elasticity);
+                    format!("Elasticity {elasticity}: {result}")
+                })
+                .collect();
+            // The game could have been loaded again in the meantime
+            let _ = sender.send(results);
+        });
+        self.drop_test = Some(receiver);
+    }
+
+    /// Passes the results of a finished drop test to the info panel.
+    fn receive_drop_test(&mut self) {
+        let Some(receiver) = &self.drop_test else {
+            return;
+        };
+        match receiver.try_recv() {
+            Ok(results) => {
+                self.ingame_ui.info_panel.drop_test_results = results;
+                self.drop_test = None;
+            }
+            Err(TryRecvError::Empty) => {}
+            Err(TryRecvError::Disconnected) => self.drop_test = None,
+        }
+    }
+
+    /// Starts recording the next `frames` frames of the gameview. The GIF is saved to `path` once
+    /// all frames are captured. Replaces a recording that is in progress.
+    pub fn record_gif(&mut self, frames: usize, path: &str) {
+        let min = self.world_to_screen(Vector2::zero());
+        let max = self.world_to_screen(v2!(self.gameview_width, self.gameview_height));
+        self.gif_recorder = Some(GifRecorder::new(frames, path.into(), min, max));
+    }
+
+    /// Captures the drawn gameview if a GIF is being recorded and stops the recording when it is
+    /// done. The encoder finishes the remaining frames on its own.
+    fn capture_gif_frame(&mut self) {
+        let Some(recorder) = &mut self.gif_recorder else {
+            return;
+        };
+        recorder.capture();
+        if recorder.is_finished() {
+            self.gif_recorder = None;
+        }
+    }
+
+    /// Returns the highest speed at which the dragged body at `index` can move towards `target`
+    /// without passing through a static body in one step.
+    fn drag_velocity_cap(&self, index: usize, target: Vector2<f32>) -> f32 {
+        let body = &self.rb_simulator.bodies[index];
+        let direction = target - body.state().position;
+        if direction.is_zero() {
+            return f32::INFINITY;
+        }
+        let direction = direction.normalized();
+
+        self.rb_simulator
+            .bodies
+            .iter()
+            .enumerate()
+            .filter(|(i, other)| {
+                let state = other.state();
+                *i != index
+                    && state.behaviour == BodyBehaviour::Static
+                    && state.collides_with_bodies
+            })
+            .filter_map(|(_, other)| body.sweep_distance(other, direction))
+            .fold(f32::INFINITY, f32::min)
+            / self.game_config.time_step
+    }
+
     fn toggle_pause(&mut self) {
         self.is_simulating = !self.is_simulating;
         self.ingame_ui.info_panel.is_simulating = self.is_simulating;
     }
 
+    /// Restores the most recent snapshot from the rewind buffer and pauses the simulation.
+    /// Pressing it repeatedly goes further back in time.
+    fn rewind(&mut self) {
+        let Some(Snapshot {
+            bodies,
+            constraints,
+            particles,
+        }) = self.rewind_buffer.pop()
+        else {
+            return;
+        };
+
+        self.rb_simulator.bodies = bodies;
+        self.rb_simulator.constraints = constraints;
+        // The traced particle keeps being traced, but its path is in the future now
+        self.fluid_system.restore_particles(particles);
+        self.traced_trail.clear();
+        // The indexes could be invalid now
+        self.dragged_body = None;
+        self.ingame_ui.info_panel.selected_body = None;
+        self.loaded_catapult = None;
+
+        if self.is_simulating {
+            self.toggle_pause();
+        }
+    }
+
+    /// Returns how many steps of `time_step` should be simulated in this frame and updates the
+    /// interpolation of the drawn bodies.
+    fn steps_this_frame(&mut self) -> u32 {
+        if !self.is_simulating {
+            self.render_interpolation = 1.0;
+            return 0;
+        }
+        if !self.game_config.real_time_stepping {
+            self.step_accumulator.reset();
+            self.render_interpolation = 1.0;
+            return 1;
+        }
+
+        let time_step = self.game_config.time_step;
+        let steps = self.step_accumulator.advance(get_frame_time(), time_step);
+        self.render_interpolation = self.step_accumulator.fraction(time_step);
+        steps
+    }
+
     /// Performs a single update of the game. Should correspond to a single frame.
     pub fn physics_update(&mut self) {
-        if self.is_simulating {
+        self.update_walls();
+        if self.game_config.worker_threads != self.thread_pool_size {
+            self.thread_pool_size = self.game_config.worker_threads;
+            self.thread_pool = build_thread_pool(self.thread_pool_size);
+        }
+
+        let steps = self.steps_this_frame();
+        if steps > 0 {
             let dt = self.game_config.time_step / self.game_config.sub_steps as f32;
 
-            for _ in 0..self.game_config.sub_steps {
-                let fluid_forces_on_bodies =
-                    self.fluid_system
-                        .step(&self.rb_simulator.bodies, &self.game_config, dt);
-                for (index, force_accumulation) in fluid_forces_on_bodies {
-                    let state = self.rb_simulator.bodies[index].state_mut();
-                    state.add_force_accumulation(force_accumulation);
-                    state.apply_accumulated_forces(dt);
+            self.rb_simulator.measure_penetration_after = self.debug_log;
+            let (mut max_penetration_before, mut max_penetration_after) = (0.0_f32, 0.0_f32);
+            for step in 0..steps {
+                // The bodies are drawn between the states before and after the last step
+                if step + 1 == steps {
+                    self.rb_simulator.store_previous_transforms();
+                }
+                for _ in 0..self.game_config.sub_steps {
+                    if self.simulate_fluid {
+                        let fluid_forces_on_bodies = in_pool(self.thread_pool.as_ref(), || {
+                            self.fluid_system
+                                .step(&self.rb_simulator.bodies, &self.game_config, dt)
+                        });
+                        if let Some(probe) = &mut self.flow_probe {
+                            let world_size = v2!(self.gameview_width, self.gameview_height);
+                            probe.measure(self.fluid_system.iter_particles(), world_size, dt);
+                        }
+                        // Frozen bodies would otherwise gather velocity from the fluid
+                        if self.simulate_bodies {
+                            for (index, force_accumulation) in fluid_forces_on_bodies {
+                                let state = self.rb_simulator.bodies[index].state_mut();
+                                state.add_force_accumulation(force_accumulation);
+                                state.apply_accumulated_forces(dt);
+                            }
+                        }
+                    }
+
+                    if self.simulate_bodies {
+                        in_pool(self.thread_pool.as_ref(), || {
+                            self.rb_simulator.step(&self.game_config, dt)
+                        });
+                        self.update_impact_flashes(dt);
+                        max_penetration_before =
+                            max_penetration_before.max(self.rb_simulator.max_penetration_before);
+                        max_penetration_after =
+                            max_penetration_after.max(self.rb_simulator.max_penetration_after);
+                    }
+                    self.confine_to_world();
                 }
+            }
 
-                self.rb_simulator.step(&self.game_config, dt);
+            if self.debug_log {
+                eprintln!(
+                    "Sub-steps: {}, contacts: {}, max penetration: {:.3} before / {:.3} after resolution, state hash: {:016x}",
+                    self.game_config.sub_steps,
+                    self.rb_simulator.contact_count,
+                    max_penetration_before,
+                    max_penetration_after,
+                    self.state_hash()
+                );
             }
+
+            if self.game_config.render_config.draw_trails {
+                self.update_trails();
+            }
+            self.update_traced_particle();
+
+            self.rewind_buffer.tick(
+                &self.rb_simulator.bodies,
+                &self.rb_simulator.constraints,
+                self.fluid_system.iter_particles(),
+            );
         }
 
         // Setup graphics
+        self.renderer
+            .set_step_size(self.game_config.render_config.fluid_cell_size.get_value());
+        let (visible_min, visible_max) = self.visible_region();
+        self.renderer.set_visible_region(visible_min, visible_max);
+        self.renderer
+            .set_color_mode(*self.game_config.render_config.fluid_color_mode.get_value());
+        self.renderer
+            .set_color_mix_mode(*self.game_config.render_config.fluid_color_mix.get_value());
+        self.renderer
+            .set_depth_shading(self.game_config.render_config.fluid_depth_shading);
+        self.renderer
+            .set_motion_blur(self.game_config.render_config.fluid_motion_blur.get_value());
+        self.renderer.set_color_blending(
+            self.game_config
+                .render_config
+                .fluid_blend_threshold
+                .get_value(),
+            self.game_config
+                .render_config
+                .fluid_alpha_exponent
+                .get_value(),
+        );
         self.renderer.setup(&self.fluid_system);
 
         // Pass infos to InGameUI
         self.ingame_ui.info_panel.particle_count = self.fluid_system.particle_count();
+        self.ingame_ui.info_panel.fluid_mass = self.fluid_system.total_mass();
         self.ingame_ui.info_panel.body_count = self.rb_simulator.bodies.len();
+        self.ingame_ui.info_panel.contact_count = self.rb_simulator.contact_count;
+        self.ingame_ui.info_panel.island_count = self.rb_simulator.island_count;
+        self.ingame_ui.info_panel.body_kinetic_energy = self.rb_simulator.kinetic_energy();
+        self.ingame_ui.info_panel.fluid_kinetic_energy = self.fluid_system.kinetic_energy();
+        self.ingame_ui.info_panel.body_momentum = self.rb_simulator.total_momentum();
+        self.ingame_ui.info_panel.fluid_momentum = self.fluid_system.total_momentum();
+        self.ingame_ui.info_panel.flow_rate = self.flow_probe.as_ref().map(|probe| probe.rate());
+        self.ingame_ui.info_panel.traced_particle = self.traced_id;
+        self.ingame_ui.info_panel.time_step = self.game_config.time_step;
+        self.ingame_ui.info_panel.sub_steps = self.game_config.sub_steps;
+        self.ingame_ui.info_panel.drop_test_running = self.drop_test.is_some();
 
         // Find under mouse entity
         let mouse_pos = {
             let (x, y) = mouse_position();
-            v2!(x, y)
+            self.screen_to_world(v2!(x, y))
         };
 
         let mut entity_info = EntityInfo::Nothing {
             position: mouse_pos,
         };
-        for (index, body) in self.rb_simulator.bodies.iter().enumerate() {
-            if body.contains_point(mouse_pos) {
-                entity_info = EntityInfo::Body {
-                    index,
-                    position: body.state().position,
-                    velocity: body.state().velocity,
-                    mass: body.state().mass(),
-                    color: body.state().color,
-                };
-                break;
-            }
+        if let Some((index, body)) = self.body_at(mouse_pos) {
+            entity_info = EntityInfo::Body {
+                index,
+                position: body.state().position,
+                velocity: body.state().velocity,
+                mass: body.state().mass(),
+                area: body.area(),
+                perimeter: body.perimeter(),
+                color: body.state().color,
+                contact_count: self.rb_simulator.body_contact_count(index),
+                resting: self.rb_simulator.is_resting(index),
+            };
         }
         if let EntityInfo::Nothing { .. } = entity_info {
             if let Some((_, closest_p)) = self
@@ -330,9 +938,11 @@ impl Game {
                 .min_by(|a, b| a.0.total_cmp(&b.0))
             {
                 entity_info = EntityInfo::Fluid {
+                    id: closest_p.id(),
                     position: closest_p.position,
                     velocity: closest_p.velocity,
-                    density: closest_p.mass(),
+                    mass: closest_p.mass(),
+                    sph_density: closest_p.sph_density,
                     color: closest_p.color,
                 };
             }
@@ -341,16 +951,162 @@ impl Game {
         self.ingame_ui.info_panel.under_mouse_entity = entity_info;
     }
 
+    /// Returns the body containing `position`. If there is none, the body closest to `position`
+    /// within the pick tolerance is returned instead. Hidden bodies can not be picked.
+    fn body_at(&self, position: Vector2<f32>) -> Option<(usize, &RigidBody)> {
+        let pickable = || {
+            self.rb_simulator
+                .bodies
+                .iter()
+                .enumerate()
+                .filter(|(index, body)| {
+                    (!is_wall(*index) || self.is_wall_enabled(*index))
+                        && !self.is_hidden_by_tag_filter(body)
+                })
+        };
+        if let Some(found) = pickable().find(|(_, body)| body.contains_point(position)) {
+            return Some(found);
+        }
+
+        let tolerance = from_cm(self.game_config.pick_tolerance.get_value());
+        pickable()
+            .map(|(index, body)| (body.distance_to_point(position), index, body))
+            .filter(|(distance, _, _)| *distance <= tolerance)
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, index, body)| (index, body))
+    }
+
+    /// Starts tracing the particle under the cursor. Clicking on anything else stops the tracing.
+    fn trace_particle_under_mouse(&mut self) {
+        self.traced_id = match self.ingame_ui.info_panel.under_mouse_entity {
+            EntityInfo::Fluid { id, .. } => Some(id),
+            _ => None,
+        };
+        self.traced_trail.clear();
+    }
+
+    /// Records the current position of the traced particle into its trail. Stops the tracing if
+    /// the particle is no longer in the simulation.
+    fn update_traced_particle(&mut self) {
+        let Some(id) = self.traced_id else {
+            return;
+        };
+        let Some(particle) = self.fluid_system.particle_by_id(id) else {
+            self.traced_id = None;
+            self.traced_trail.clear();
+            return;
+        };
+
+        let trail_length = self.game_config.render_config.trail_length as usize;
+        self.traced_trail.push_back(particle.position);
+        while self.traced_trail.len() > trail_length {
+            self.traced_trail.pop_front();
+        }
+    }
+
+    /// Draws the path of the traced particle and highlights the particle itself.
+    fn draw_traced_particle(&self) {
+        let Some(particle) = self
+            .traced_id
+            .and_then(|id| self.fluid_system.particle_by_id(id))
+        else {
+            return;
+        };
+
+        draw_trail(&self.traced_trail, TRACED_PARTICLE_COLOR);
+        let position = particle.position;
+        draw_circle(
+            position.x,
+            position.y,
+            TRACED_PARTICLE_RADIUS,
+            TRACED_PARTICLE_COLOR.as_mq(),
+        );
+    }
+
+    /// Fades the impact flashes of the bodies and starts new ones from the contact impulses of the
+    /// last step. The flash is based on the change of velocity, so light bodies flash more easily.
+    fn update_impact_flashes(&mut self, dt: f32) {
+        let impulses = &self.rb_simulator.contact_impulses;
+        for (index, body) in self.rb_simulator.bodies.iter_mut().enumerate() {
+            let state = body.state_mut();
+            state.impact_flash = (state.impact_flash - dt / IMPACT_FLASH_DURATION).max(0.0);
+            if state.behaviour != BodyBehaviour::Dynamic {
+                continue;
+            }
+
+            let impulse = impulses.get(index).copied().unwrap_or(0.0);
+            let speed_change = to_cm(impulse / state.mass());
+            let flash = (speed_change - IMPACT_FLASH_MIN_SPEED)
+                / (IMPACT_FLASH_FULL_SPEED - IMPACT_FLASH_MIN_SPEED);
+            state.impact_flash = state.impact_flash.max(flash.min(1.0));
+        }
+    }
+
+    /// Records the current center of mass of each dynamic body into its trail.
+    fn update_trails(&mut self) {
+        let trail_length = self.game_config.render_config.trail_length as usize;
+        for body in &mut self.rb_simulator.bodies {
+            if body.state().behaviour != BodyBehaviour::Dynamic {
+                continue;
+            }
+
+            let center = body.center_of_mass();
+            let trail = &mut body.state_mut().trail;
+            trail.push_back(center);
+            while trail.len() > trail_length {
+                trail.pop_front();
+            }
+        }
+    }
+
     pub fn draw(&self) {
         clear_background(Color::rgb(120, 120, 120).as_mq());
-        self.renderer.draw();
-        for body in &self.rb_simulator.bodies {
-            body.draw();
+        set_camera(&self.gameview_camera());
+        let fluid_over_bodies = *self.game_config.render_config.fluid_draw_order.get_value()
+            == FluidDrawOrder::OverBodies;
+        if !fluid_over_bodies {
+            self.renderer.draw();
+        }
+        if self.game_config.render_config.draw_trails {
+            for body in &self.rb_simulator.bodies {
+                draw_trail(&body.state().trail, body.state().color);
+            }
+        }
+        if fluid_over_bodies {
+            self.draw_bodies(|body| !body.state().draw_over_fluid);
+            self.renderer.draw();
+            self.draw_bodies(|body| body.state().draw_over_fluid);
+        } else {
+            self.draw_bodies(|_| true);
+        }
+        self.draw_constraints();
+        self.draw_tag_highlights();
+        self.draw_selected_body_contacts();
+        self.draw_loaded_catapult();
+        if self.game_config.render_config.draw_triangulation {
+            self.draw_triangulations();
+        }
+        self.draw_flow_probe();
+        self.draw_freeze_region();
+        self.draw_launch_aim();
+        self.draw_traced_particle();
+        if self.game_config.render_config.draw_flow_field {
+            self.draw_flow_field();
+        }
+        if self.game_config.render_config.draw_projection_axes {
+            self.draw_projection_axes();
+        }
+        if self.game_config.render_config.draw_body_velocities {
+            self.draw_body_velocities();
         }
 
         // Draw individual particles as circles
         if self.draw_particles {
-            for p in &self.fluid_system.particles {
+            let visible_region = self.visible_region();
+            for p in self.fluid_system.iter_particles() {
+                if !boxes_intersect((p.position, p.position), visible_region) {
+                    continue;
+                }
                 draw_circle(
                     p.position.x,
                     p.position.y,
@@ -359,28 +1115,49 @@ impl Game {
                 );
             }
         }
+        set_default_camera();
     }
 
     pub fn draw_ui(&mut self) {
+        let gameview_end = self.world_to_screen(v2!(self.gameview_width, self.gameview_height));
         self.ingame_ui.draw(
-            Vector2::new(self.gameview_width + 50.0, 40.0),
+            Vector2::new(gameview_end.x + 50.0, 40.0),
             &mut self.game_config,
+            &mut self.simulate_fluid,
+            &mut self.simulate_bodies,
         );
 
         if let Tool::Rigidbody = self.ingame_ui.selected_tool {
             if self.mouse_in_gameview && self.dragged_body.is_none() {
-                self.preview_body.draw();
+                set_camera(&self.gameview_camera());
+                self.draw_body(&self.preview_body, None);
+                draw_orientation_indicator(
+                    &self.preview_body,
+                    self.ingame_ui.body_maker.size().x * 0.5,
+                );
+                set_default_camera();
             }
         }
 
         if let Tool::Fluid = self.ingame_ui.selected_tool {
-            if let FluidSelectorAction::ClearParticles = self.ingame_ui.fluid_selector.action {
-                self.fluid_system.clear_all_particles();
+            match self.ingame_ui.fluid_selector.action {
+                FluidSelectorAction::ClearParticles => self.fluid_system.clear_all_particles(),
+                FluidSelectorAction::ImportCsv => {
+                    if let Err(error) = self.fluid_system.import_csv(FLUID_CSV_PATH) {
+                        eprintln!("Failed to import the fluid: {error}");
+                    }
+                }
+                FluidSelectorAction::Nothing => {}
             }
         }
 
+        if self.game_config.render_config.draw_coordinates {
+            self.draw_coordinate_overlay();
+        }
+        self.draw_gravity_arrow();
+
         // Draw name and description text
-        let offset = v2!(30.0, self.gameview_height + 30.0);
+        let offset = v2!(30.0, gameview_end.y + 30.0);
         draw_text(
             &self.name,
             offset.x,
@@ -402,8 +1179,490 @@ impl Game {
         }
     }
 
+    /// Draws the visible bodies for which `should_draw` returns true, colored by their stress if
+    /// the stress heatmap is on.
+    fn draw_bodies(&self, should_draw: impl Fn(&RigidBody) -> bool) {
+        if self.game_config.render_config.draw_stress_heatmap {
+            self.draw_stress_heatmap(should_draw);
+        } else {
+            for (_, body) in self.drawn_bodies().filter(|(_, body)| should_draw(body)) {
+                self.draw_simulated_body(body, self.impact_flash_color(body));
+            }
+        }
+    }
+
+    /// Returns the color of the body blended with the flash color, if it is flashing.
+    fn impact_flash_color(&self, body: &RigidBody) -> Option<Color> {
+        let state = body.state();
+        if !self.game_config.render_config.impact_flash || state.impact_flash <= 0.0 {
+            return None;
+        }
+
+        // Keep the transparency of the body
+        let flash_color = Color {
+            a: state.color.a,
+            ..IMPACT_FLASH_COLOR
+        };
+        Some(state.color.lerp(flash_color, state.impact_flash))
+    }
+
+    /// Draws the body in the style selected in the config, optionally with a different `color`.
+    fn draw_body(&self, body: &RigidBody, color: Option<Color>) {
+        let render_config = &self.game_config.render_config;
+        let style = *render_config.body_style.get_value();
+        let thickness = render_config.outline_thickness;
+        let segments = render_config.circle_segments;
+        match color {
+            Some(color) => draw_styled_with_color(body, color, style, thickness, segments),
+            None => draw_styled(body, style, thickness, segments),
+        }
+    }
+
+    /// Same as `draw_body` but the body is drawn at its transform interpolated by
+    /// `render_interpolation`.
+    fn draw_simulated_body(&self, body: &RigidBody, color: Option<Color>) {
+        if self.render_interpolation < 1.0 {
+            self.draw_body(&body.interpolated(self.render_interpolation), color);
+        } else {
+            self.draw_body(body, color);
+        }
+    }
+
+    /// Draws the edges of the triangulation of each visible polygon. Circles are not triangulated.
+    fn draw_triangulations(&self) {
+        for (_, body) in self.drawn_bodies() {
+            if let RigidBody::Polygon(inner) = body {
+                draw_triangulation_edges(inner.global_triangulation(), TRIANGULATION_COLOR);
+            }
+        }
+    }
+
+    /// Draws a line between the ends of each distance constraint.
+    fn draw_constraints(&self) {
+        let bodies = &self.rb_simulator.bodies;
+        let position_of = |index: usize| bodies.get(index).map(|body| body.state().position);
+        for constraint in &self.rb_simulator.constraints {
+            let anchor = match constraint.anchor {
+                Anchor::Body(other) => position_of(other),
+                Anchor::World(point) => Some(point),
+            };
+            let (Some(a), Some(b)) = (position_of(constraint.index), anchor) else {
+                continue;
+            };
+            draw_line(
+                a.x,
+                a.y,
+                b.x,
+                b.y,
+                CONSTRAINT_THICKNESS,
+                CONSTRAINT_COLOR.as_mq(),
+            );
+        }
+    }
+
+    /// Draws the speed and angular velocity of the fastest visible dynamic bodies at their center
+    /// of mass.
+    fn draw_body_velocities(&self) {
+        let mut moving: Vec<_> = self
+            .drawn_bodies()
+            .map(|(_, body)| body)
+            .filter(|body| body.state().behaviour == BodyBehaviour::Dynamic)
+            .map(|body| (to_cm(body.state().velocity.length()), body))
+            .filter(|(speed, _)| *speed >= BODY_VELOCITY_MIN_SPEED)
+            .collect();
+        moving.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+
+        for (speed, body) in moving.into_iter().take(MAX_BODY_VELOCITY_LABELS) {
+            let spin = body.state().angular_velocity;
+            let center = body.center_of_mass();
+            draw_text(
+                &format!("{speed:.1} cm/s, {spin:.2} rad/s"),
+                center.x,
+                center.y,
+                FONT_SIZE_SMALL,
+                BODY_VELOCITY_COLOR.as_mq(),
+            );
+        }
+    }
+
+    /// Draws the box of fluid to be frozen while it is being drawn.
+    fn draw_freeze_region(&self) {
+        let Some(start) = self.freeze_region_start else {
+            return;
+        };
+        let (x, y) = mouse_position();
+        let end = self.screen_to_world(v2!(x, y));
+        let (min, max) = merge_boxes((start, start), (end, end));
+        let size = max - min;
+        draw_rectangle_lines(
+            min.x,
+            min.y,
+            size.x,
+            size.y,
+            FREEZE_REGION_THICKNESS,
+            FREEZE_REGION_COLOR.as_mq(),
+        );
+    }
+
+    /// Draws the direction and speed at which the aimed body will be launched. The line is as long
+    /// as the distance the body travels in a tenth of a second.
+    fn draw_launch_aim(&self) {
+        let Some(start) = self.launch_start else {
+            return;
+        };
+        // A rope is being spawned, which is not launched
+        if is_shift_down() {
+            return;
+        }
+        let (x, y) = mouse_position();
+        let pull = start - self.screen_to_world(v2!(x, y));
+        let end = start + pull * (LAUNCH_SPEED_PER_DRAG * 0.1);
+        draw_line(
+            start.x,
+            start.y,
+            end.x,
+            end.y,
+            LAUNCH_AIM_THICKNESS,
+            LAUNCH_AIM_COLOR.as_mq(),
+        );
+    }
+
+    /// Draws the flow probe line with an arrow in the direction of positive flow, or the line being
+    /// drawn if the user is placing a new probe.
+    fn draw_flow_probe(&self) {
+        let color = FLOW_PROBE_COLOR.as_mq();
+        if let Some(start) = self.flow_probe_start {
+            let (x, y) = mouse_position();
+            let end = self.screen_to_world(v2!(x, y));
+            draw_line(start.x, start.y, end.x, end.y, FLOW_PROBE_THICKNESS, color);
+        }
+
+        let Some(probe) = &self.flow_probe else {
+            return;
+        };
+        let line = &probe.line;
+        draw_line(
+            line.start.x,
+            line.start.y,
+            line.end.x,
+            line.end.y,
+            FLOW_PROBE_THICKNESS,
+            color,
+        );
+        let middle = line.middle();
+        let arrow_end = middle + line.normal() * FLOW_PROBE_ARROW_LENGTH;
+        draw_line(
+            middle.x,
+            middle.y,
+            arrow_end.x,
+            arrow_end.y,
+            FLOW_PROBE_THICKNESS * 0.5,
+            color,
+        );
+    }
+
+    /// Returns the minimum and maximum corner of the part of the world that is on the screen. It is
+    /// smaller than the world if the gameview is not fit to the window and the window is small.
+    fn visible_region(&self) -> (Vector2<f32>, Vector2<f32>) {
+        (
+            self.screen_to_world(Vector2::zero()),
+            self.screen_to_world(v2!(screen_width(), screen_height())),
+        )
+    }
+
+    /// Returns the bodies with their indexes that should be drawn. Skips the walls if they are
+    /// hidden or disabled, bodies hidden by the tag filter and bodies outside of the visible region.
+    fn drawn_bodies(&self) -> impl Iterator<Item = (usize, &RigidBody)> {
+        let draw_walls = self.game_config.render_config.draw_walls;
+
+        self.rb_simulator
+            .bodies
+            .iter()
+            .enumerate()
+            .filter(move |(index, _)| {
+                !is_wall(*index) || (draw_walls && self.is_wall_enabled(*index))
+            })
+            .filter(|(_, body)| !self.is_hidden_by_tag_filter(body) && self.is_body_visible(body))
+    }
+
+    /// Returns true if the bounding box of the body overlaps the visible region.
+    fn is_body_visible(&self, body: &RigidBody) -> bool {
+        boxes_intersect(body.bounding_box(), self.visible_region())
+    }
+
+    /// Returns true if the tag filter is set to the tag of this body.
+    fn matches_tag_filter(&self, body: &RigidBody) -> bool {
+        let filter = self.game_config.render_config.tag_filter.trim();
+        !filter.is_empty() && body.state().tag.as_deref() == Some(filter)
+    }
+
+    fn is_hidden_by_tag_filter(&self, body: &RigidBody) -> bool {
+        *self.game_config.render_config.tag_filter_mode.get_value() == TagFilterMode::Hide
+            && self.matches_tag_filter(body)
+    }
+
+    /// Outlines bodies matching the tag filter if it is in the highlight mode.
+    fn draw_tag_highlights(&self) {
+        if *self.game_config.render_config.tag_filter_mode.get_value() != TagFilterMode::Highlight {
+            return;
+        }
+
+        for body in &self.rb_simulator.bodies {
+            if self.matches_tag_filter(body) {
+                draw_outline_with_color(
+                    body,
+                    HIGHLIGHT_COLOR,
+                    HIGHLIGHT_THICKNESS,
+                    self.game_config.render_config.circle_segments,
+                );
+            }
+        }
+    }
+
+    /// Outlines the bodies touching the body selected in the info tool.
+    fn draw_selected_body_contacts(&self) {
+        if self.ingame_ui.selected_tool != Tool::Info {
+            return;
+        }
+        let Some(index) = self.ingame_ui.info_panel.selected_body else {
+            return;
+        };
+
+        // The contacts are from the last step, bodies could have been removed since then
+        let contacts = self.rb_simulator.contacts_of(index);
+        for body in contacts
+            .iter()
+            .filter_map(|&other| self.rb_simulator.bodies.get(other))
+        {
+            draw_outline_with_color(
+                body,
+                CONTACT_HIGHLIGHT_COLOR,
+                HIGHLIGHT_THICKNESS,
+                self.game_config.render_config.circle_segments,
+            );
+        }
+    }
+
+    /// Outlines the projectile of the catapult that is ready to be fired.
+    fn draw_loaded_catapult(&self) {
+        let Some(loaded) = &self.loaded_catapult else {
+            return;
+        };
+        if let Some(projectile) = self.rb_simulator.bodies.get(loaded.projectile) {
+            draw_outline_with_color(
+                projectile,
+                HIGHLIGHT_COLOR,
+                HIGHLIGHT_THICKNESS,
+                self.game_config.render_config.circle_segments,
+            );
+        }
+    }
+
+    /// Draws the visible bodies for which `should_draw` returns true colored by the contact
+    /// impulses they received in the last step, relative to the most loaded body. Green is the
+    /// lowest load, red the highest.
+    fn draw_stress_heatmap(&self, should_draw: impl Fn(&RigidBody) -> bool) {
+        const LOW: Color = Color::rgb(0, 255, 0);
+        const HIGH: Color = Color::rgb(255, 0, 0);
+
+        let impulses = &self.rb_simulator.contact_impulses;
+        let max_impulse = impulses.iter().fold(0.0f32, |acc, x| acc.max(*x));
+
+        for (index, body) in self.drawn_bodies().filter(|(_, body)| should_draw(body)) {
+            let impulse = impulses.get(index).copied().unwrap_or(0.0);
+            let t = if max_impulse > 0.0 {
+                impulse / max_impulse
+            } else {
+                0.0
+            };
+            self.draw_simulated_body(body, Some(LOW.lerp(HIGH, t)));
+        }
+    }
+
+    /// Draws the projection axes of the polygon under the cursor as rays from its center.
+    fn draw_projection_axes(&self) {
+        let EntityInfo::Body { index, .. } = self.ingame_ui.info_panel.under_mouse_entity else {
+            return;
+        };
+        let Some(body) = self.rb_simulator.bodies.get(index) else {
+            return;
+        };
+        // Circles do not have any fixed axes
+        let RigidBody::Polygon(inner) = body else {
+            return;
+        };
+
+        let color = PROJECTION_AXIS_COLOR.as_mq();
+        let center = body.state().position;
+        for axis in inner.projection_axes() {
+            let end = center + axis * PROJECTION_AXIS_LENGTH;
+            draw_line(center.x, center.y, end.x, end.y, 2.0, color);
+        }
+    }
+
+    /// Draws an arrow of the fluid velocity at each point of a grid over the visible region. The
+    /// arrows are scaled and colored by the speed relative to the fastest one.
+    fn draw_flow_field(&self) {
+        const SLOW: Color = Color::rgb(0, 0, 255);
+        const FAST: Color = Color::rgb(255, 0, 0);
+
+        let spacing = self
+            .game_config
+            .render_config
+            .flow_field_spacing
+            .get_value();
+        let (min, max) = self.visible_region();
+        let mut samples = Vec::new();
+        let mut y = min.y + spacing * 0.5;
+        while y < max.y {
+            let mut x = min.x + spacing * 0.5;
+            while x < max.x {
+                let position = v2!(x, y);
+                let velocity = self.fluid_system.velocity_at(position);
+                if !velocity.is_zero() {
+                    samples.push((position, velocity));
+                }
+                x += spacing;
+            }
+            y += spacing;
+        }
+
+        let max_speed = samples
+            .iter()
+            .fold(0.0f32, |acc, (_, velocity)| acc.max(velocity.length()));
+        if max_speed <= 0.0 {
+            return;
+        }
+        for (position, velocity) in samples {
+            let t = velocity.length() / max_speed;
+            let color = SLOW.lerp(FAST, t).as_mq();
+            let direction = velocity.normalized();
+            let tip = position + direction * (spacing * FLOW_FIELD_ARROW_SCALE * t);
+            draw_line(position.x, position.y, tip.x, tip.y, 1.0, color);
+
+            // Arrow head
+            let head = direction * (spacing * FLOW_FIELD_HEAD_SCALE);
+            for side in [head.normal(), head.normal() * -1.0] {
+                let end = tip - head + side * 0.5;
+                draw_line(tip.x, tip.y, end.x, end.y, 1.0, color);
+            }
+        }
+    }
+
+    /// Draws an arrow showing the direction and strength of gravity in the top-right corner of the
+    /// gameview. Only the label is drawn if gravity is turned off.
+    fn draw_gravity_arrow(&self) {
+        let color = GRAVITY_ARROW_COLOR.as_mq();
+        let corner = self.world_to_screen(v2!(self.gameview_width, 0.0));
+        let center = corner + v2!(-GRAVITY_ARROW_MARGIN, GRAVITY_ARROW_MARGIN);
+        let gravity = self.game_config.gravity;
+        let strength = gravity.length();
+
+        let label = if self.game_config.gravity_enabled {
+            format!("{strength:.0} cm/s^2")
+        } else {
+            "Gravity off".to_owned()
+        };
+        let label_size = measure_text(&label, None, FONT_SIZE_SMALL as u16, 1.0);
+        draw_text(
+            &label,
+            center.x - label_size.width * 0.5,
+            center.y + GRAVITY_ARROW_LENGTH * 2.0 + FONT_SIZE_SMALL,
+            FONT_SIZE_SMALL,
+            color,
+        );
+        if !self.game_config.gravity_enabled || strength == 0.0 {
+            return;
+        }
+
+        let length = GRAVITY_ARROW_LENGTH * (strength / EARTH_GRAVITY_CM).min(2.0);
+        let direction = gravity.normalized();
+        let tip = center + direction * length;
+        draw_line(center.x, center.y, tip.x, tip.y, 2.0, color);
+        // Arrow head
+        let head = direction * (GRAVITY_ARROW_LENGTH * 0.3);
+        for side in [head.normal(), head.normal() * -1.0] {
+            let end = tip - head + side * 0.5;
+            draw_line(tip.x, tip.y, end.x, end.y, 2.0, color);
+        }
+    }
+
+    /// Draws labeled ticks along the top and left edge of the gameview, a marker of the origin and
+    /// the coordinates of the mouse cursor.
+    fn draw_coordinate_overlay(&self) {
+        let color = Color::rgb(255, 255, 255).as_mq();
+        let origin = self.gameview_offset;
+
+        draw_circle(origin.x, origin.y, 5.0, Color::rgb(255, 0, 0).as_mq());
+        draw_text(
+            "(0, 0)",
+            origin.x + 6.0,
+            origin.y + FONT_SIZE_SMALL,
+            FONT_SIZE_SMALL,
+            color,
+        );
+
+        // Ticks along the X axis
+        let mut x = COORDINATE_TICK_SPACING;
+        while x < self.gameview_width {
+            let tick = self.world_to_screen(v2!(x, 0.0));
+            draw_line(
+                tick.x,
+                tick.y,
+                tick.x,
+                tick.y + COORDINATE_TICK_LENGTH,
+                1.0,
+                color,
+            );
+            draw_text(
+                format!("{x}").as_str(),
+                tick.x + 2.0,
+                tick.y + FONT_SIZE_SMALL,
+                FONT_SIZE_SMALL,
+                color,
+            );
+            x += COORDINATE_TICK_SPACING;
+        }
+
+        // Ticks along the Y axis
+        let mut y = COORDINATE_TICK_SPACING;
+        while y < self.gameview_height {
+            let tick = self.world_to_screen(v2!(0.0, y));
+            draw_line(
+                tick.x,
+                tick.y,
+                tick.x + COORDINATE_TICK_LENGTH,
+                tick.y,
+                1.0,
+                color,
+            );
+            draw_text(
+                format!("{y}").as_str(),
+                tick.x + 2.0,
+                tick.y - 2.0,
+                FONT_SIZE_SMALL,
+                color,
+            );
+            y += COORDINATE_TICK_SPACING;
+        }
+
+        // Readout of the cursor position
+        if self.mouse_in_gameview {
+            let world_mouse = self.mouse_position_last_frame;
+            let mouse = self.world_to_screen(world_mouse);
+            draw_text(
+                format!("({:.0}, {:.0})", world_mouse.x, world_mouse.y).as_str(),
+                mouse.x + 10.0,
+                mouse.y - 10.0,
+                FONT_SIZE_SMALL,
+                color,
+            );
+        }
+    }
+
+    /// Returns true if the `position` on the screen is inside the gameview.
     fn is_in_gameview(&self, position: Vector2<f32>) -> bool {
-        let relative = position - self.gameview_offset;
+        let relative = self.screen_to_world(position);
 
         relative.x >= 0.0
             && relative.x < self.gameview_width
@@ -411,22 +1670,493 @@ impl Game {
             && relative.y < self.gameview_height
     }
 
-    fn add_fluid(&mut self, position: Vector2<f32>) {
+    /// Fits the gameview into the part of the window that is not reserved for the UI. The gameview
+    /// is scaled uniformly to keep its aspect ratio and centered in that part, leaving empty bars
+    /// on the sides.
+    fn update_gameview_transform(&mut self) {
+        if !self.game_config.render_config.fit_gameview {
+            self.gameview_scale = 1.0;
+            self.gameview_offset = Vector2::zero();
+            return;
+        }
+
+        let available = v2!(
+            (screen_width() - UI_PANEL_WIDTH).max(1.0),
+            (screen_height() - DESCRIPTION_HEIGHT).max(1.0)
+        );
+        let scale = (available.x / self.gameview_width).min(available.y / self.gameview_height);
+        let size = v2!(self.gameview_width, self.gameview_height) * scale;
+
+        self.gameview_scale = scale;
+        self.gameview_offset = (available - size) * 0.5;
+    }
+
+    /// Converts a position on the screen into a position in the game world.
+    fn screen_to_world(&self, position: Vector2<f32>) -> Vector2<f32> {
+        (position - self.gameview_offset) / self.gameview_scale
+    }
+
+    /// Converts a position in the game world into a position on the screen.
+    fn world_to_screen(&self, position: Vector2<f32>) -> Vector2<f32> {
+        position * self.gameview_scale + self.gameview_offset
+    }
+
+    /// Camera that draws the game world at its place in the gameview.
+    fn gameview_camera(&self) -> Camera2D {
+        // Part of the world that is covered by the whole screen
+        let screen_min = self.screen_to_world(Vector2::zero());
+        let size = v2!(screen_width(), screen_height()) / self.gameview_scale;
+        let center = screen_min + size * 0.5;
+
+        Camera2D {
+            target: center.as_mq(),
+            // Positive zoom on Y keeps the Y axis pointing down
+            zoom: v2!(2.0 / size.x, 2.0 / size.y).as_mq(),
+            ..Default::default()
+        }
+    }
+
+    /// Spawns droplets of the fluid selected in the fluid tool around `position`.
+    /// `cursor_velocity` is added to their velocity if the tool is set to do so.
+    fn add_fluid(&mut self, position: Vector2<f32>, cursor_velocity: Vector2<f32>) {
         let fluid_tool = &self.ingame_ui.fluid_selector;
         let droplet_count = fluid_tool.droplet_count;
         let mass = fluid_tool.density;
         let color = fluid_tool.color();
+        let charge = fluid_tool.charge;
+        let viscosity = fluid_tool.viscosity;
+        let mut velocity = from_cm(fluid_tool.spawn_velocity());
+        if fluid_tool.inherit_cursor_velocity {
+            velocity += cursor_velocity;
+        }
 
         for _ in 0..droplet_count {
             let x_off = 2.0 * fastrand::f32() - 1.0;
             let y_off = 2.0 * fastrand::f32() - 1.0;
             let position = position + v2!(x_off, y_off);
 
-            let particle = Particle::new(position).with_mass(mass).with_color(color);
+            let particle = Particle::new(position)
+                .with_mass(mass)
+                .with_color(color)
+                .with_velocity(velocity)
+                .with_charge(charge)
+                .with_viscosity(viscosity);
             self.fluid_system.add_particle(particle);
         }
     }
 
+    /// Returns true if the wall at `index` should collide. That is if the world edges are in the
+    /// `BoundaryMode::Walls` mode and this wall was not removed in the config.
+    fn is_wall_enabled(&self, index: usize) -> bool {
+        let config = &self.game_config;
+        let enabled = match index {
+            0 => config.floor_enabled,
+            1 => config.ceiling_enabled,
+            2 => config.left_wall_enabled,
+            3 => config.right_wall_enabled,
+            _ => false,
+        };
+        enabled && *config.boundary_mode.get_value() == BoundaryMode::Walls
+    }
+
+    /// Sets the elasticity of the walls from the game config and turns the collisions of each
+    /// wall on or off. See `is_wall_enabled`.
+    fn update_walls(&mut self) {
+        let elasticity = if self.game_config.custom_wall_elasticity {
+            SharedProperty::Value(self.game_config.wall_elasticity.get_value())
+        } else {
+            SharedProperty::Pass
+        };
+
+        for index in 0..WALL_COUNT {
+            let collides = self.is_wall_enabled(index);
+            let state = self.rb_simulator.bodies[index].state_mut();
+            state.elasticity = elasticity;
+            state.collides_with_fluid = collides;
+            state.collides_with_bodies = collides;
+        }
+    }
+
+    /// Brings the bodies and particles that left the world back into it according to the
+    /// boundary mode. The previous positions are moved along, so that a wrapped object is not
+    /// drawn or measured as if it crossed the whole world. With walls, whatever left through a
+    /// disabled wall is removed instead.
+    fn confine_to_world(&mut self) {
+        let mode = *self.game_config.boundary_mode.get_value();
+        let size = v2!(self.gameview_width, self.gameview_height);
+        if mode == BoundaryMode::Walls {
+            self.remove_outside_world(size);
+            return;
+        }
+
+        for body in self.rb_simulator.bodies.iter_mut().skip(WALL_COUNT) {
+            let state = body.state();
+            if state.behaviour == BodyBehaviour::Static {
+                continue;
+            }
+            let (position, velocity) = mode.confine(state.position, state.velocity, size);
+            let offset = position - state.position;
+            if offset.is_zero() {
+                continue;
+            }
+
+            body.set_position(position);
+            let state = body.state_mut();
+            state.velocity = velocity;
+            state.previous_position += offset;
+        }
+
+        self.fluid_system.iter_particles_mut().for_each(|p| {
+            let (position, velocity) = mode.confine(p.position, p.velocity, size);
+            let offset = position - p.position;
+            p.position = position;
+            p.predicted_position += offset;
+            p.previous_position += offset;
+            p.velocity = velocity;
+        });
+    }
+
+    /// Removes the dynamic bodies and the particles whose center is outside of a world of `size`.
+    fn remove_outside_world(&mut self, size: Vector2<f32>) {
+        let is_outside = |position: Vector2<f32>| {
+            position.x < 0.0 || position.y < 0.0 || position.x > size.x || position.y > size.y
+        };
+
+        let outside: Vec<usize> = self
+            .rb_simulator
+            .bodies
+            .iter()
+            .enumerate()
+            .skip(WALL_COUNT)
+            .filter(|(_, body)| {
+                let state = body.state();
+                state.behaviour != BodyBehaviour::Static && is_outside(state.position)
+            })
+            .map(|(index, _)| index)
+            .collect();
+        // From the back so that the bodies swapped into the removed places were already checked
+        for &index in outside.iter().rev() {
+            self.rb_simulator.remove_body(index);
+        }
+        if !outside.is_empty() {
+            // The indexes could be invalid now
+            self.dragged_body = None;
+            self.ingame_ui.info_panel.selected_body = None;
+            self.loaded_catapult = None;
+        }
+
+        self.fluid_system
+            .remove_particles_where(|p| is_outside(p.position));
+    }
+
+    /// Spawns a copy of the template in the hotbar slot of the pressed number key at `position`.
+    /// With Shift held the slot is set to the current body maker configuration instead.
+    fn handle_hotbar_keys(&mut self, position: Vector2<f32>) {
+        if self.ingame_ui.taken_input() {
+            return;
+        }
+        let Some(slot) = HOTBAR_KEYS.iter().position(|key| is_key_pressed(*key)) else {
+            return;
+        };
+
+        if is_shift_down() {
+            let mut template = self.body_from_body_maker(Vector2::zero());
+            // Undo the transparency of the preview
+            template.state_mut().color.a = 1.0;
+            self.hotbar[slot] = Some(template);
+        } else if self.mouse_in_gameview {
+            if let Some(template) = &self.hotbar[slot] {
+                let mut body = template.clone();
+                body.set_position(position);
+                body.state_mut().previous_position = position;
+                self.rb_simulator.bodies.push(body);
+            }
+        }
+    }
+
+    /// Creates a copy of the body at `index` moved by `offset` and adds it to the simulation.
+    pub fn duplicate_body(&mut self, index: usize, offset: Vector2<f32>) {
+        let mut body = self.rb_simulator.bodies[index].clone();
+        let position = body.state().position + offset;
+        body.set_position(position);
+        let state = body.state_mut();
+        state.previous_position = position;
+        state.trail.clear();
+
+        self.rb_simulator.bodies.push(body);
+    }
+
+    /// Spawns a rope from `start` to `end` made of `segments` circles with the diameter of
+    /// `thickness`. Neighboring circles are held together by distance constraints and if
+    /// `pin_start` is true, the first circle is held at `start`.
+    pub fn spawn_rope(
+        &mut self,
+        start: Vector2<f32>,
+        end: Vector2<f32>,
+        segments: usize,
+        thickness: f32,
+        pin_start: bool,
+    ) {
+        if segments < 2 {
+            return;
+        }
+
+        let first_index = self.rb_simulator.bodies.len();
+        let step = (end - start) / (segments - 1) as f32;
+        for i in 0..segments {
+            let position = start + step * i as f32;
+            let mut body = RigidBody::new_circle(position, thickness * 0.5, BodyBehaviour::Dynamic);
+            body.set_density(ROPE_DENSITY);
+            body.state_mut().color = ROPE_COLOR;
+            self.rb_simulator.bodies.push(body);
+        }
+
+        let length = step.length();
+        for i in 1..segments {
+            self.rb_simulator.constraints.push(DistanceConstraint {
+                index: first_index + i,
+                anchor: Anchor::Body(first_index + i - 1),
+                length,
+            });
+        }
+        if pin_start {
+            self.rb_simulator.constraints.push(DistanceConstraint {
+                index: first_index,
+                anchor: Anchor::World(start),
+                length: 0.0,
+            });
+        }
+    }
+
+    /// Breaks the polygon body at `index` into at least `pieces` triangles which fly apart from
+    /// its center. The triangles keep the properties and the velocity of the original body, which
+    /// is removed. Circles are not broken.
+    pub fn fragment_body(&mut self, index: usize, pieces: usize) {
+        let RigidBody::Polygon(polygon) = &self.rb_simulator.bodies[index] else {
+            return;
+        };
+        let mut triangles = polygon.global_triangulation().clone();
+        if triangles.is_empty() {
+            return;
+        }
+        // Halve the biggest triangles until there is enough of them
+        while triangles.len() < pieces {
+            let biggest = triangles
+                .iter()
+                .enumerate()
+                .max_by(|(_, a), (_, b)| a.area().total_cmp(&b.area()))
+                .map(|(i, _)| i)
+                .unwrap_or(0);
+            let (first, second) = triangles.swap_remove(biggest).split();
+            triangles.push(first);
+            triangles.push(second);
+        }
+
+        let parent = self.rb_simulator.remove_body(index);
+        let parent = parent.state();
+        let total_area: f32 = triangles.iter().map(|t| t.area()).sum();
+        for triangle in triangles {
+            let centroid = triangle.centroid();
+            let points = vec![
+                triangle.a - centroid,
+                triangle.b - centroid,
+                triangle.c - centroid,
+            ];
+            let mut fragment = RigidBody::new_polygon(centroid, points, parent.behaviour);
+
+            let offset = centroid - parent.position;
+            let outward = if offset.is_zero() {
+                Vector2::zero()
+            } else {
+                offset.normalized()
+            };
+            let state = fragment.state_mut();
+            state.set_mass(parent.mass * triangle.area() / total_area);
+            // Velocity of this point of the parent, including its rotation
+            state.velocity = parent.velocity
+                + offset.normal() * parent.angular_velocity
+                + outward * from_cm(FRAGMENT_SPEED);
+            state.angular_velocity = parent.angular_velocity;
+            state.lock_rotation = parent.lock_rotation;
+            state.collides_with_fluid = parent.collides_with_fluid;
+            state.elasticity = parent.elasticity;
+            state.static_friction = parent.static_friction;
+            state.dynamic_friction = parent.dynamic_friction;
+            state.color = parent.color;
+            state.tag = parent.tag.clone();
+
+            self.rb_simulator.bodies.push(fragment);
+        }
+
+        // The indexes could be invalid now
+        self.dragged_body = None;
+        self.ingame_ui.info_panel.selected_body = None;
+        self.loaded_catapult = None;
+    }
+
+    /// Collects statistics about the current scene. See `SceneStats` for what is included.
+    pub fn stats(&self) -> SceneStats {
+        let bodies = &self.rb_simulator.bodies;
+        // Skip the walls
+        let bounds = bodies
+            .iter()
+            .skip(WALL_COUNT)
+            .map(|body| body.bounding_box())
+            .reduce(merge_boxes)
+            .map(|(min, max)| Bounds { min, max });
+        let (scene_min, scene_max) = self.scene_aabb();
+
+        SceneStats {
+            body_count: bodies.len(),
+            particle_count: self.fluid_system.particle_count(),
+            body_kinetic_energy: self.rb_simulator.kinetic_energy(),
+            fluid_kinetic_energy: self.fluid_system.kinetic_energy(),
+            fluid_mass: self.fluid_system.total_mass(),
+            bounds,
+            scene_bounds: Bounds {
+                min: scene_min,
+                max: scene_max,
+            },
+            config: self.game_config.clone(),
+        }
+    }
+
+    /// Returns the minimum and maximum corner of the axis aligned box enclosing all bodies except
+    /// the walls and all fluid particles. If there is nothing, the whole gameview is returned.
+    pub fn scene_aabb(&self) -> (Vector2<f32>, Vector2<f32>) {
+        let body_boxes = self
+            .rb_simulator
+            .bodies
+            .iter()
+            .skip(WALL_COUNT)
+            .map(|body| body.bounding_box());
+        let particle_boxes = self
+            .fluid_system
+            .iter_particles()
+            .map(|p| (p.position, p.position));
+
+        body_boxes
+            .chain(particle_boxes)
+            .reduce(merge_boxes)
+            .unwrap_or((
+                Vector2::zero(),
+                v2!(self.gameview_width, self.gameview_height),
+            ))
+    }
+
+    /// Writes the statistics of the current scene as JSON into the file at `path`.
+    pub fn export_stats(&self, path: &str) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(&self.stats())?;
+        std::fs::write(path, json)
+    }
+
+    /// Turns the fluid inside the box given by its minimum and maximum corner into a single body
+    /// shaped as the convex hull of the particles. The body has the total mass, the average
+    /// velocity and the average color of the particles, which are removed.
+    /// Nothing happens if the particles do not span an area.
+    pub fn freeze_fluid_region(&mut self, min: Vector2<f32>, max: Vector2<f32>) {
+        let positions: Vec<Vector2<f32>> = self
+            .fluid_system
+            .iter_particles()
+            .map(|p| p.position)
+            .filter(|pos| pos.x >= min.x && pos.x <= max.x && pos.y >= min.y && pos.y <= max.y)
+            .collect();
+        let hull = convex_hull(&positions);
+        if hull.len() < 3 {
+            return;
+        }
+
+        let particles = self.fluid_system.take_particles_in_region(min, max);
+        let mass: f32 = particles.iter().map(|p| p.mass()).sum();
+        let momentum = particles
+            .iter()
+            .fold(Vector2::zero(), |acc, p| acc + p.velocity * p.mass());
+        let count = particles.len() as f32;
+        let color = particles
+            .iter()
+            .fold(Color::rgba(0, 0, 0, 0), |acc, p| Color {
+                r: acc.r + p.color.r / count,
+                g: acc.g + p.color.g / count,
+                b: acc.b + p.color.b / count,
+                a: acc.a + p.color.a / count,
+            });
+
+        let center = hull.iter().fold(Vector2::zero(), |acc, p| acc + *p) / hull.len() as f32;
+        let points = hull.into_iter().map(|p| p - center).collect();
+        let mut body = RigidBody::new_polygon(center, points, BodyBehaviour::Dynamic);
+        // The average of the hull points is not the center of mass if they are spread unevenly
+        body.recenter();
+        let state = body.state_mut();
+        state.set_mass(mass);
+        state.velocity = momentum / mass;
+        state.color = color;
+
+        self.rb_simulator.bodies.push(body);
+    }
+
+    /// Returns a hash of the positions and orientations of all bodies and the positions of all
+    /// particles. Runs that simulated the same thing, for example in a regression test, have the
+    /// same hash.
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = StateHasher::default();
+        hasher.write_bodies(&self.rb_simulator.bodies);
+        hasher.write_particles(self.fluid_system.iter_particles());
+
+        hasher.finish()
+    }
+
+    /// Writes all bodies, including the walls, as a JSON array into the file at `path`. Unlike a
+    /// save, it does not contain the fluid or the configuration.
+    pub fn export_bodies_json(&self, path: &str) -> std::io::Result<()> {
+        let bodies: Vec<BodySerializedForm> = self
+            .rb_simulator
+            .bodies
+            .iter()
+            .map(|body| body.to_serialized_form())
+            .collect();
+        let json = serde_json::to_string_pretty(&bodies)?;
+        std::fs::write(path, json)
+    }
+
+    /// Selects the body under the cursor for editing its tag in the info panel. Clicking on
+    /// anything else clears the selection.
+    fn select_body_under_mouse(&mut self) {
+        let info_panel = &mut self.ingame_ui.info_panel;
+        info_panel.selected_body = match info_panel.under_mouse_entity {
+            EntityInfo::Body { index, .. } => Some(index),
+            _ => None,
+        };
+        info_panel.tag_input = info_panel
+            .selected_body
+            .and_then(|index| self.rb_simulator.bodies[index].state().tag.clone())
+            .unwrap_or_default();
+    }
+
+    /// Sets the tag edited in the info panel to the selected body. Empty input removes the tag.
+    fn apply_tag_input(&mut self) {
+        let info_panel = &self.ingame_ui.info_panel;
+        let Some(body) = info_panel
+            .selected_body
+            .and_then(|index| self.rb_simulator.bodies.get_mut(index))
+        else {
+            return;
+        };
+
+        let tag = info_panel.tag_input.trim();
+        body.state_mut().tag = (!tag.is_empty()).then(|| tag.to_owned());
+    }
+
+    /// Sets the color of every body under `position` to the color selected in the paint tool.
+    fn paint_bodies(&mut self, position: Vector2<f32>) {
+        let color = self.ingame_ui.painter.color();
+
+        for body in &mut self.rb_simulator.bodies {
+            if body.contains_point(position) {
+                body.state_mut().color = color;
+            }
+        }
+    }
+
     fn handle_save_loads(&mut self) {
         let save_file_name = self.ingame_ui.save_loads.save_file_name.clone();
         match std::mem::replace(
@@ -444,6 +2174,9 @@ impl Game {
             SaveLoadAction::Load(game_serialized_form) => {
                 *self = self.prepared_load_game(game_serialized_form);
             }
+            SaveLoadAction::Import(game_serialized_form) => {
+                self.import_scene(game_serialized_form, Vector2::zero());
+            }
             _ => {}
         }
     }
@@ -454,12 +2187,58 @@ impl Game {
         // Swap things that should not change
         std::mem::swap(&mut self.ingame_ui, &mut new_game.ingame_ui);
         std::mem::swap(&mut self.preview_body, &mut new_game.preview_body);
+        new_game.ingame_ui.info_panel.selected_body = None;
 
         new_game
     }
 
+    /// Adds the bodies, except for the walls, their constraints and the fluid of `other` to the
+    /// current scene. They are moved by `offset`. The configuration and everything else of the
+    /// current scene is kept.
+    pub fn import_scene(&mut self, other: GameSerializedForm, offset: Vector2<f32>) {
+        let first_index = self.rb_simulator.bodies.len();
+        for body_form in other.rb.bodies.into_iter().skip(WALL_COUNT) {
+            let mut body = RigidBody::from_serialized_form(body_form);
+            let position = body.state().position + offset;
+            body.set_position(position);
+            body.state_mut().previous_position = position;
+
+            self.rb_simulator.bodies.push(body);
+        }
+
+        // Constraints attached to the walls are skipped, as the walls are not imported
+        let imported_index =
+            |index: usize| (!is_wall(index)).then(|| index - WALL_COUNT + first_index);
+        for constraint_form in other.rb.constraints {
+            let mut constraint = DistanceConstraint::from_serialized_form(constraint_form);
+            let Some(index) = imported_index(constraint.index) else {
+                continue;
+            };
+            constraint.index = index;
+            constraint.anchor = match constraint.anchor {
+                Anchor::Body(other) => match imported_index(other) {
+                    Some(other) => Anchor::Body(other),
+                    None => continue,
+                },
+                Anchor::World(point) => Anchor::World(point + offset),
+            };
+
+            self.rb_simulator.constraints.push(constraint);
+        }
+        self.rb_simulator.remove_invalid_constraints();
+
+        for particle_form in other.sph.particles {
+            let mut particle = Particle::from_serialized_form(particle_form);
+            particle.position += offset;
+            particle.predicted_position = particle.position;
+            particle.previous_position = particle.position;
+
+            self.fluid_system.add_particle(particle);
+        }
+    }
+
     fn handle_tool_change_keys(&mut self) {
-        if self.ingame_ui.save_loads.taken_input {
+        if self.ingame_ui.taken_input() {
             return;
         }
 
@@ -469,6 +2248,10 @@ impl Game {
             self.ingame_ui.selected_tool = Tool::Fluid;
         } else if is_key_pressed(KeyCode::B) {
             self.ingame_ui.selected_tool = Tool::Rigidbody;
+        } else if is_key_pressed(KeyCode::P) {
+            self.ingame_ui.selected_tool = Tool::Paint;
+        } else if is_key_pressed(KeyCode::S) {
+            self.ingame_ui.selected_tool = Tool::Stir;
         } else if is_key_pressed(KeyCode::C) {
             self.ingame_ui.selected_tool = Tool::Configuration;
         } else if is_key_pressed(KeyCode::L) {
@@ -483,14 +2266,50 @@ impl Game {
                 *self = self.prepared_load_game(save_load::load_save(self.save_name.as_str()));
             }
             QuickAction::TogglePause => self.toggle_pause(),
+            QuickAction::ClearBodies => self.clear_all_bodies(),
+            QuickAction::ExportStats => {
+                if let Err(error) = self.export_stats(STATS_PATH) {
+                    eprintln!("Failed to export the statistics: {error}");
+                }
+            }
+            QuickAction::ExportBodies => {
+                if let Err(error) = self.export_bodies_json(BODIES_PATH) {
+                    eprintln!("Failed to export the bodies: {error}");
+                }
+            }
             QuickAction::Nothing => {}
         }
+
+        if self.ingame_ui.clear_bodies_clicked {
+            self.clear_all_bodies();
+        }
+    }
+
+    /// Removes the body at `index` and forgets everything that referenced bodies by index.
+    fn delete_body(&mut self, index: usize) {
+        self.rb_simulator.remove_body(index);
+        // The indexes could be invalid now
+        self.dragged_body = None;
+        self.ingame_ui.info_panel.selected_body = None;
+        self.loaded_catapult = None;
+    }
+
+    /// Removes all bodies except the walls.
+    pub fn clear_all_bodies(&mut self) {
+        self.rb_simulator.bodies.truncate(WALL_COUNT);
+        self.rb_simulator.constraints.clear();
+        // The indexes are invalid now
+        self.dragged_body = None;
+        self.ingame_ui.info_panel.selected_body = None;
+        self.loaded_catapult = None;
     }
 
     pub fn update(&mut self) {
+        self.update_gameview_transform();
         self.handle_input();
         self.physics_update();
         self.draw();
+        self.capture_gif_frame();
         self.draw_ui();
 
         // Handle UI events
@@ -499,3 +2318,70 @@ impl Game {
         self.handle_tool_change_keys();
     }
 }
+
+/// Returns the smallest axis aligned box enclosing both boxes given by their minimum and maximum corner.
+fn merge_boxes(
+    (min_a, max_a): (Vector2<f32>, Vector2<f32>),
+    (min_b, max_b): (Vector2<f32>, Vector2<f32>),
+) -> (Vector2<f32>, Vector2<f32>) {
+    (min_a.min(min_b), max_a.max(max_b))
+}
+
+/// Returns true if the 2 axis aligned boxes given by their minimum and maximum corner overlap.
+fn boxes_intersect(
+    (min_a, max_a): (Vector2<f32>, Vector2<f32>),
+    (min_b, max_b): (Vector2<f32>, Vector2<f32>),
+) -> bool {
+    min_a.x <= max_b.x && max_a.x >= min_b.x && min_a.y <= max_b.y && max_a.y >= min_b.y
+}
+
+/// Returns true if the body at `index` is one of the walls.
+fn is_wall(index: usize) -> bool {
+    index < WALL_COUNT
+}
+
+fn is_shift_down() -> bool {
+    is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift)
+}
+
+/// Builds a pool of `threads` threads, or of as many as there are cores if `threads` is zero.
+/// Returns None if the threads could not be spawned, so the global pool is used instead.
+fn build_thread_pool(threads: u8) -> Option<ThreadPool> {
+    ThreadPoolBuilder::new()
+        .num_threads(threads as usize)
+        .build()
+        .inspect_err(|error| eprintln!("Failed to create the worker threads: {error}"))
+        .ok()
+}
+
+/// Runs `op` so that all parallel iterators in it use `pool`, or the global pool if it is None.
+fn in_pool<R: Send>(pool: Option<&ThreadPool>, op: impl FnOnce() -> R + Send) -> R {
+    match pool {
+        Some(pool) => pool.install(op),
+        None => op(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DraggedBody, Game};
+    use crate::math::{v2, Vector2};
+    use crate::physics::rigidbody::presets::catapult;
+
+    #[test]
+    fn deleting_catapult_part_unloads_it() {
+        let mut game = Game::new(1000, 1000);
+        let loaded = catapult(&mut game.rb_simulator, v2!(500.0, 500.0));
+        let projectile = loaded.projectile;
+        game.loaded_catapult = Some(loaded);
+        game.dragged_body = Some(DraggedBody {
+            index: game.rb_simulator.bodies.len() - 1,
+            drag_offset: Vector2::zero(),
+        });
+
+        game.delete_body(projectile);
+
+        assert!(game.loaded_catapult.is_none());
+        assert!(game.dragged_body.is_none());
+    }
+}
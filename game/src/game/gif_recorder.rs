@@ -0,0 +1,107 @@
+use std::fs::File;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use gif::{Encoder, EncodingError, Frame, Repeat};
+use macroquad::{texture::get_screen_data, window::screen_dpi_scale};
+
+use crate::math::Vector2;
+
+/// Delay between 2 frames of the GIF in hundredths of a second. GIFs can not play at 60 FPS, so
+/// this is the closest delay to it.
+const FRAME_DELAY: u16 = 2;
+/// Speed of the color quantization from 1 to 30. Higher is faster but has worse colors.
+const QUANTIZATION_SPEED: i32 = 10;
+
+/// Captures a region of the screen for a fixed number of frames and encodes them as a GIF. The
+/// frames are encoded on a worker thread as they are captured, which keeps writing the file after
+/// the recorder is dropped.
+pub struct GifRecorder {
+    remaining_frames: usize,
+    /// Sends the captured frames as RGBA bytes with rows going from top to bottom to the encoder.
+    frames: Sender<Vec<u8>>,
+    /// Top-left corner of the captured region in physical pixels.
+    corner: (usize, usize),
+    width: u16,
+    height: u16,
+}
+
+impl GifRecorder {
+    /// Creates a recorder of `frames` frames of the screen region between `min` and `max` that
+    /// will be saved to `path`. The region is in logical pixels, like the mouse position.
+    pub fn new(frames: usize, path: PathBuf, min: Vector2<f32>, max: Vector2<f32>) -> Self {
+        // The screen data has one pixel per physical pixel
+        let scale = screen_dpi_scale();
+        let (min, max) = ((min * scale).max(Vector2::zero()), max * scale);
+        let width = (max.x - min.x).max(1.0) as u16;
+        let height = (max.y - min.y).max(1.0) as u16;
+
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            if let Err(error) = encode(path, width, height, receiver) {
+                eprintln!("Failed to save the GIF: {error}");
+            }
+        });
+
+        GifRecorder {
+            remaining_frames: frames,
+            frames: sender,
+            corner: (min.x as usize, min.y as usize),
+            width,
+            height,
+        }
+    }
+
+    /// Should be called once every frame after the captured region is drawn.
+    pub fn capture(&mut self) {
+        if self.is_finished() {
+            return;
+        }
+        self.remaining_frames -= 1;
+
+        let screen = get_screen_data();
+        let screen_width = screen.width as usize;
+        let screen_height = screen.height as usize;
+        let (left, top) = self.corner;
+        let width = (self.width as usize).min(screen_width.saturating_sub(left));
+        let height = (self.height as usize).min(screen_height.saturating_sub(top));
+
+        // The screen data has its rows from bottom to top and the region may not fit the screen
+        // anymore if the window was resized, so the missing part stays black.
+        let mut frame = vec![0; self.width as usize * self.height as usize * 4];
+        for y in 0..height {
+            let screen_row = screen_height - 1 - (top + y);
+            let from = (screen_row * screen_width + left) * 4;
+            let to = y * self.width as usize * 4;
+            frame[to..to + width * 4].copy_from_slice(&screen.bytes[from..from + width * 4]);
+        }
+        // The encoder stopped after an error, which it reported
+        let _ = self.frames.send(frame);
+    }
+
+    /// Returns true if all frames were captured.
+    pub fn is_finished(&self) -> bool {
+        self.remaining_frames == 0
+    }
+}
+
+/// Encodes the frames received from `frames` into a GIF at `path` until the sender is dropped.
+fn encode(
+    path: PathBuf,
+    width: u16,
+    height: u16,
+    frames: Receiver<Vec<u8>>,
+) -> Result<(), EncodingError> {
+    let file = File::create(path)?;
+    let mut encoder = Encoder::new(file, width, height, &[])?;
+    encoder.set_repeat(Repeat::Infinite)?;
+
+    for mut bytes in frames {
+        let mut frame = Frame::from_rgba_speed(width, height, &mut bytes, QUANTIZATION_SPEED);
+        frame.delay = FRAME_DELAY;
+        encoder.write_frame(&frame)?;
+    }
+
+    Ok(())
+}
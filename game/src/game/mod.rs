@@ -1,6 +1,9 @@
 mod config;
 mod game;
+mod gif_recorder;
+mod rewind;
 mod save_load;
+mod step_accumulator;
 mod ui;
 
 pub use config::*;
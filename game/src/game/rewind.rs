@@ -0,0 +1,58 @@
+use std::collections::VecDeque;
+
+use crate::{
+    physics::rigidbody::{DistanceConstraint, RigidBody},
+    Particle,
+};
+
+/// How many simulated frames pass between 2 captured snapshots.
+const CAPTURE_INTERVAL: u32 = 10;
+/// Maximum number of kept snapshots. Together with `CAPTURE_INTERVAL` this bounds how far back can
+/// the simulation be rewound.
+const CAPACITY: usize = 30;
+
+/// A copy of the simulated state at some point in time.
+pub struct Snapshot {
+    pub bodies: Vec<RigidBody>,
+    pub constraints: Vec<DistanceConstraint>,
+    pub particles: Vec<Particle>,
+}
+
+/// A ring buffer of recent simulation states that allows rewinding the simulation.
+#[derive(Default)]
+pub struct RewindBuffer {
+    snapshots: VecDeque<Snapshot>,
+    frames_since_capture: u32,
+}
+
+impl RewindBuffer {
+    /// Should be called once every simulated frame. Captures a new snapshot every
+    /// `CAPTURE_INTERVAL` frames and drops the oldest one if the buffer is full.
+    pub fn tick<'a>(
+        &mut self,
+        bodies: &[RigidBody],
+        constraints: &[DistanceConstraint],
+        particles: impl IntoIterator<Item = &'a Particle>,
+    ) {
+        self.frames_since_capture += 1;
+        if self.frames_since_capture < CAPTURE_INTERVAL {
+            return;
+        }
+        self.frames_since_capture = 0;
+
+        if self.snapshots.len() == CAPACITY {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(Snapshot {
+            bodies: bodies.to_vec(),
+            constraints: constraints.to_vec(),
+            particles: particles.into_iter().cloned().collect(),
+        });
+    }
+
+    /// Removes and returns the most recent snapshot.
+    pub fn pop(&mut self) -> Option<Snapshot> {
+        self.frames_since_capture = 0;
+        self.snapshots.pop_back()
+    }
+}
@@ -25,7 +25,10 @@ pub fn save(game_ser_form: GameSerializedForm, name: &str) {
 
 pub fn list_saves() -> LinkedList<String> {
     let path = Path::new(ROOT).join("saves/");
-    let paths = read_dir(path).expect("Failed to read directory.");
+    // There are no saves to list when the game is not run from the directory with them
+    let Ok(paths) = read_dir(path) else {
+        return LinkedList::new();
+    };
 
     paths
         .map(|p| p.unwrap().file_name().to_str().unwrap().to_owned())
@@ -0,0 +1,65 @@
+/// The most steps done in a single frame. After a long frame, eg. while the window was dragged,
+/// the rest of the time is dropped instead of freezing the game to catch up with it.
+const MAX_STEPS_PER_FRAME: u32 = 5;
+
+/// Collects the real time between frames and splits it into simulation steps of a fixed length.
+#[derive(Default)]
+pub struct StepAccumulator {
+    /// Time in seconds that was not simulated yet.
+    leftover: f32,
+}
+
+impl StepAccumulator {
+    /// Adds the duration of the last frame and returns how many steps of `time_step` should be
+    /// simulated.
+    pub fn advance(&mut self, frame_time: f32, time_step: f32) -> u32 {
+        let time = self.leftover + frame_time;
+        let steps = (time / time_step) as u32;
+        if steps > MAX_STEPS_PER_FRAME {
+            // The dropped time must not be simulated later
+            self.leftover = 0.0;
+            return MAX_STEPS_PER_FRAME;
+        }
+        self.leftover = (time - steps as f32 * time_step).max(0.0);
+
+        steps
+    }
+
+    /// How far is the simulation into the next step, in [0..1] range. Used to interpolate the
+    /// drawn state between the last 2 steps.
+    pub fn fraction(&self, time_step: f32) -> f32 {
+        (self.leftover / time_step).clamp(0.0, 1.0)
+    }
+
+    pub fn reset(&mut self) {
+        self.leftover = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{StepAccumulator, MAX_STEPS_PER_FRAME};
+
+    const TIME_STEP: f32 = 0.01;
+
+    #[test]
+    fn short_frames_add_up_to_a_step() {
+        let mut accumulator = StepAccumulator::default();
+
+        assert_eq!(accumulator.advance(0.004, TIME_STEP), 0);
+        assert!((accumulator.fraction(TIME_STEP) - 0.4).abs() < 1e-4);
+        assert_eq!(accumulator.advance(0.004, TIME_STEP), 0);
+        assert_eq!(accumulator.advance(0.004, TIME_STEP), 1);
+        assert!((accumulator.fraction(TIME_STEP) - 0.2).abs() < 1e-4);
+    }
+
+    #[test]
+    fn long_frame_is_not_caught_up() {
+        let mut accumulator = StepAccumulator::default();
+
+        assert_eq!(accumulator.advance(1.0, TIME_STEP), MAX_STEPS_PER_FRAME);
+        // The rest of the long frame was dropped
+        assert_eq!(accumulator.advance(0.0, TIME_STEP), 0);
+        assert!(accumulator.fraction(TIME_STEP) < 1.0);
+    }
+}
@@ -3,7 +3,7 @@ use macroquad::text::draw_text;
 use macroquad::ui::root_ui;
 use macroquad::ui::widgets::Checkbox;
 
-use crate::game::{draw_slider, FONT_SIZE_SMALL};
+use crate::game::{draw_slider, Selection, UIEdit, FONT_SIZE_SMALL};
 use crate::physics::rigidbody::{
     BodyBehaviour, DEFAULT_DYNAMIC_FRICTION, DEFAULT_ELASTICITY, DEFAULT_STATIC_FRICTION,
 };
@@ -18,31 +18,102 @@ use super::{ColorPicker, GAP, SLIDER_HEIGHT, SLIDER_LENGTH};
 
 const MIN_SIZE: f32 = 5.0;
 const DEFAULT_MAX_SIZE: f32 = 500.0;
+/// Factor by which the size is scaled per notch of the mouse wheel.
+const SIZE_SCALE_STEP: f32 = 1.1;
 const MIN_MASS: f32 = 500.0;
 const MAX_MASS: f32 = 50_000.0;
+const MIN_DENSITY: f32 = 0.5;
+const MAX_DENSITY: f32 = 50.0;
 const MIN_ORIENTATION: f32 = 0.0;
 const MAX_ORIENTATION: f32 = 360.0;
 
-const TUTORIAL_LINES: [&str; 3] = [
+const MATERIAL_VALUES: [Material; 5] = [
+    Material::Custom,
+    Material::Ice,
+    Material::Rubber,
+    Material::Wood,
+    Material::Metal,
+];
+const MATERIAL_NAMES: [&str; 5] = ["Custom", "Ice", "Rubber", "Wood", "Metal"];
+const MATERIAL_BOX: Selection<Material, 5> = Selection::new(MATERIAL_VALUES, MATERIAL_NAMES);
+
+const TUTORIAL_LINES: [&str; 11] = [
     "[Left MB] - Drag rigidbodies",
     "[Right MB] - Spawn new rigidbody",
+    "[Right MB] + drag back - Launch new rigidbody",
+    "[Shift + Right MB] + drag - Spawn rope pinned at the start",
     "[Middle MB] - Delete rigidbody under cursor",
+    "[Ctrl + D] - Duplicate rigidbody under cursor",
+    "[X] - Break rigidbody under cursor into fragments",
+    "[K] - Spawn catapult, press again to fire it",
+    "[1-9] - Spawn rigidbody from hotbar slot",
+    "[Shift + 1-9] - Save current rigidbody to hotbar slot",
+    "[Mouse wheel] - Change the size of new rigidbodies",
 ];
 
+const MASS_MODE_VALUES: [MassMode; 2] = [MassMode::Fixed, MassMode::Density];
+const MASS_MODE_NAMES: [&str; 2] = ["Fixed mass", "From density"];
+const MASS_MODE_BOX: Selection<MassMode, 2> = Selection::new(MASS_MODE_VALUES, MASS_MODE_NAMES);
+
+/// How is the mass of a created body decided.
+#[derive(Clone, Copy, PartialEq)]
+pub enum MassMode {
+    /// The mass is set directly, no matter the size of the body.
+    Fixed,
+    /// The mass is the density multiplied by the area of the body.
+    Density,
+}
+
+/// Preset of elasticity and frictions of a body.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Material {
+    /// Values are set by hand with the sliders.
+    Custom,
+    Ice,
+    Rubber,
+    Wood,
+    Metal,
+}
+
+impl Material {
+    /// Returns the elasticity, static friction and dynamic friction of this material.
+    /// `None` for `Material::Custom`.
+    pub fn properties(&self) -> Option<(f32, f32, f32)> {
+        match self {
+            Material::Custom => None,
+            Material::Ice => Some((0.1, 0.1, 0.05)),
+            Material::Rubber => Some((0.9, 0.9, 0.8)),
+            Material::Wood => Some((0.4, 0.5, 0.4)),
+            Material::Metal => Some((0.25, 0.3, 0.2)),
+        }
+    }
+}
+
 pub struct BodyMaker {
     width: f32,
     height: f32,
     pub mass: f32,
+    /// Density in g/cm^2 used when the mass is computed from the size.
+    pub density: f32,
+    mass_mode: Selection<MassMode, 2>,
     pub orientation: f32,
     pub lock_rotation: bool,
     pub behaviour: BodyBehaviour,
+    pub collides_with_fluid: bool,
+    /// Draw the body on top of the fluid when the fluid is drawn over bodies.
+    pub draw_over_fluid: bool,
+    /// Create an ellipse fitting into the width and height instead of a rectangle.
+    pub ellipse: bool,
 
     pub elasticity: f32,
     pub static_friction: f32,
     pub dynamic_friction: f32,
+    /// Selecting a material sets the elasticity and frictions above.
+    material: Selection<Material, 5>,
+    /// Material applied last, so that the sliders are overwritten only when the material changes.
+    applied_material: Material,
 
     max_size: f32,
-    changed: bool,
 
     color_picker: ColorPicker,
 }
@@ -53,16 +124,22 @@ impl Default for BodyMaker {
             width: 30.0,
             height: 30.0,
             mass: 5000.0,
+            density: 5.0,
+            mass_mode: MASS_MODE_BOX,
             orientation: 0.0,
             lock_rotation: false,
             behaviour: BodyBehaviour::Dynamic,
+            collides_with_fluid: true,
+            draw_over_fluid: false,
+            ellipse: false,
 
             elasticity: DEFAULT_ELASTICITY,
             static_friction: DEFAULT_STATIC_FRICTION,
             dynamic_friction: DEFAULT_DYNAMIC_FRICTION,
+            material: MATERIAL_BOX,
+            applied_material: Material::Custom,
 
             max_size: DEFAULT_MAX_SIZE,
-            changed: false,
 
             color_picker: ColorPicker::new(Color::rgb(0, 0, 0)),
         }
@@ -71,19 +148,6 @@ impl Default for BodyMaker {
 
 impl UIComponent for BodyMaker {
     fn draw(&mut self, offset: Vector2<f32>) {
-        let BodyMaker {
-            width: old_width,
-            height: old_height,
-            mass: old_mass,
-            orientation: old_orientation,
-            lock_rotation: old_lock_rotation,
-            behaviour: old_behaviour,
-            elasticity: old_elasticity,
-            static_friction: old_static_friction,
-            dynamic_friction: old_dynamic_friction,
-            ..
-        } = *self;
-
         let mut offset = offset;
         for line in TUTORIAL_LINES {
             draw_text(
@@ -103,6 +167,12 @@ impl UIComponent for BodyMaker {
             &mut self.width,
             MIN_SIZE..self.max_size,
         );
+        let side_offset = offset + v2!(400.0, 0.0);
+        Checkbox::new(71)
+            .pos(side_offset.as_mq())
+            .label("Ellipse?")
+            .size(v2!(SLIDER_HEIGHT, SLIDER_HEIGHT).as_mq())
+            .ui(&mut root_ui(), &mut self.ellipse);
 
         let offset = offset + v2!(0.0, SLIDER_HEIGHT + GAP);
         draw_slider(
@@ -155,6 +225,18 @@ impl UIComponent for BodyMaker {
             BodyBehaviour::Dynamic
         };
 
+        let offset = offset + v2!(0.0, SLIDER_HEIGHT + GAP);
+        draw_slider(
+            offset,
+            "Density [g/cm^2]",
+            SLIDER_LENGTH,
+            &mut self.density,
+            MIN_DENSITY..MAX_DENSITY,
+        );
+        let side_offset = offset + v2!(400.0, 0.0);
+        self.mass_mode
+            .draw_edit(side_offset, v2!(150.0, SLIDER_HEIGHT), "Mass mode");
+
         let offset = offset + v2!(0.0, SLIDER_HEIGHT + GAP);
         draw_slider(
             offset,
@@ -163,6 +245,12 @@ impl UIComponent for BodyMaker {
             &mut self.elasticity,
             0.05..0.95,
         );
+        let side_offset = offset + v2!(400.0, 0.0);
+        Checkbox::new(70)
+            .pos(side_offset.as_mq())
+            .label("Collides with fluid?")
+            .size(v2!(SLIDER_HEIGHT, SLIDER_HEIGHT).as_mq())
+            .ui(&mut root_ui(), &mut self.collides_with_fluid);
 
         let offset = offset + v2!(0.0, SLIDER_HEIGHT + GAP);
         draw_slider(
@@ -172,6 +260,10 @@ impl UIComponent for BodyMaker {
             &mut self.static_friction,
             0.05..0.95,
         );
+        let side_offset = offset + v2!(400.0, 0.0);
+        self.material
+            .draw_edit(side_offset, v2!(150.0, SLIDER_HEIGHT), "Material");
+        self.apply_material();
 
         let offset = offset + v2!(0.0, SLIDER_HEIGHT + GAP);
         draw_slider(
@@ -181,21 +273,22 @@ impl UIComponent for BodyMaker {
             &mut self.dynamic_friction,
             0.05..0.95,
         );
+        let side_offset = offset + v2!(400.0, 0.0);
+        Checkbox::new(73)
+            .pos(side_offset.as_mq())
+            .label("Draw over fluid?")
+            .size(v2!(SLIDER_HEIGHT, SLIDER_HEIGHT).as_mq())
+            .ui(&mut root_ui(), &mut self.draw_over_fluid);
 
-        let old_color = self.color_picker.color();
         self.color_picker
             .draw(offset + v2!(0.0, SLIDER_HEIGHT + 25.0));
+    }
 
-        self.changed = self.width != old_width
-            || self.height != old_height
-            || self.mass != old_mass
-            || self.orientation != old_orientation
-            || self.lock_rotation != old_lock_rotation
-            || old_color != self.color_picker.color()
-            || self.behaviour != old_behaviour
-            || self.elasticity != old_elasticity
-            || self.static_friction != old_static_friction
-            || self.dynamic_friction != old_dynamic_friction;
+    /// Scales both sides of new bodies, keeping their aspect ratio unless a side hits a limit.
+    fn adjust_primary_parameter(&mut self, steps: f32) {
+        let scale = SIZE_SCALE_STEP.powf(steps);
+        self.width = (self.width * scale).clamp(MIN_SIZE, self.max_size);
+        self.height = (self.height * scale).clamp(MIN_SIZE, self.max_size);
     }
 }
 
@@ -208,11 +301,26 @@ impl BodyMaker {
         v2!(self.width, self.height)
     }
 
+    pub fn mass_mode(&self) -> MassMode {
+        *self.mass_mode.get_value()
+    }
+
     pub fn set_max_size(&mut self, new_max: f32) {
         self.max_size = new_max;
     }
 
-    pub fn changed(&self) -> bool {
-        self.changed
+    /// Sets the elasticity and frictions from the selected material if it changed.
+    fn apply_material(&mut self) {
+        let material = *self.material.get_value();
+        if material == self.applied_material {
+            return;
+        }
+
+        self.applied_material = material;
+        if let Some((elasticity, static_friction, dynamic_friction)) = material.properties() {
+            self.elasticity = elasticity;
+            self.static_friction = static_friction;
+            self.dynamic_friction = dynamic_friction;
+        }
     }
 }
@@ -1,8 +1,8 @@
 use macroquad::text::draw_text;
 use macroquad::ui::root_ui;
-use macroquad::ui::widgets::Button;
+use macroquad::ui::widgets::{Button, Checkbox};
 
-use crate::game::ui::RED_BUTTON_SKIN;
+use crate::game::ui::{red_button_skin, RED_BUTTON_SKIN};
 use crate::game::{draw_slider, FONT_SIZE_SMALL};
 use crate::utility::AsMq;
 use crate::{
@@ -19,13 +19,28 @@ const MIN_DENSITY: f32 = 0.1;
 const MAX_DENSITY: f32 = 13.5;
 /// Default density - water
 const DEFAULT_DENSITY: f32 = 1.0;
+/// Maximum speed of newly spawned particles.
+const MAX_SPAWN_SPEED: f32 = 500.0;
+/// Maximum magnitude of the charge of particles.
+const MAX_CHARGE: f32 = 10.0;
+/// Maximum viscosity of the fluid. Higher values make the simulation unstable.
+const MAX_VISCOSITY: f32 = 50.0;
+/// Range of the number of particles spawned at once.
+const MIN_DROPLET_COUNT: u32 = 1;
+const MAX_DROPLET_COUNT: u32 = 10;
 
-const TUTORIAL_LINES: [&str; 1] = ["[Left MB] - Spawn fluid"];
+const TUTORIAL_LINES: [&str; 3] = [
+    "[Left MB] - Spawn fluid",
+    "[Right MB] - Drag a box to freeze the fluid in it into a body",
+    "[Mouse wheel] - Change the droplet count",
+];
 
 #[derive(Clone, Copy)]
 pub enum FluidSelectorAction {
     Nothing,
     ClearParticles,
+    /// Replaces the fluid with the particles from a CSV file.
+    ImportCsv,
 }
 
 pub struct FluidSelector {
@@ -33,6 +48,16 @@ pub struct FluidSelector {
     color_picker: ColorPicker,
     pub action: FluidSelectorAction,
     pub droplet_count: u32,
+    /// Direction in which new particles move, in degrees. 0 is to the right, 90 is down.
+    pub spawn_direction: f32,
+    /// Speed of newly spawned particles.
+    pub spawn_speed: f32,
+    /// If true, the velocity of the cursor is added to the velocity of new particles.
+    pub inherit_cursor_velocity: bool,
+    /// Charge of newly spawned particles. Zero means no electric interaction.
+    pub charge: f32,
+    /// Viscosity of newly spawned particles. Zero is as runny as water.
+    pub viscosity: f32,
 }
 
 impl Default for FluidSelector {
@@ -42,6 +67,11 @@ impl Default for FluidSelector {
             color_picker: ColorPicker::new(Color::rgb(10, 24, 189)),
             action: FluidSelectorAction::Nothing,
             droplet_count: 4,
+            spawn_direction: 0.0,
+            spawn_speed: 0.0,
+            inherit_cursor_velocity: false,
+            charge: 0.0,
+            viscosity: 0.0,
         }
     }
 }
@@ -60,7 +90,7 @@ impl UIComponent for FluidSelector {
             offset += v2!(0.0, FONT_SIZE_SMALL + 10.0);
         }
 
-        root_ui().push_skin(RED_BUTTON_SKIN.get().unwrap());
+        root_ui().push_skin(RED_BUTTON_SKIN.get_or_init(red_button_skin));
         if Button::new("Clear fluid")
             .size(v2!(100.0, 25.0).as_mq())
             .position(offset.as_mq())
@@ -72,6 +102,14 @@ impl UIComponent for FluidSelector {
         }
         root_ui().pop_skin();
 
+        if Button::new("Import CSV")
+            .size(v2!(100.0, 25.0).as_mq())
+            .position((offset + v2!(110.0, 0.0)).as_mq())
+            .ui(&mut root_ui())
+        {
+            self.action = FluidSelectorAction::ImportCsv;
+        }
+
         let offset = offset + v2!(0.0, 45.0);
         self.draw_density_selector(offset);
 
@@ -82,13 +120,60 @@ impl UIComponent for FluidSelector {
             "Droplet count",
             SLIDER_LENGTH,
             &mut f_count,
-            1.0..10.0,
+            MIN_DROPLET_COUNT as f32..MAX_DROPLET_COUNT as f32,
         );
         self.droplet_count = f_count.round() as u32;
 
+        let offset = offset + v2!(0.0, SLIDER_HEIGHT + GAP);
+        draw_slider(
+            offset,
+            "Spawn direction [degrees]",
+            SLIDER_LENGTH,
+            &mut self.spawn_direction,
+            0.0..360.0,
+        );
+
+        let offset = offset + v2!(0.0, SLIDER_HEIGHT + GAP);
+        draw_slider(
+            offset,
+            "Spawn speed [cm/s]",
+            SLIDER_LENGTH,
+            &mut self.spawn_speed,
+            0.0..MAX_SPAWN_SPEED,
+        );
+        let side_offset = offset + v2!(400.0, 0.0);
+        Checkbox::new(72)
+            .pos(side_offset.as_mq())
+            .label("Add cursor velocity?")
+            .size(v2!(SLIDER_HEIGHT, SLIDER_HEIGHT).as_mq())
+            .ui(&mut root_ui(), &mut self.inherit_cursor_velocity);
+
+        let offset = offset + v2!(0.0, SLIDER_HEIGHT + GAP);
+        draw_slider(
+            offset,
+            "Charge",
+            SLIDER_LENGTH,
+            &mut self.charge,
+            -MAX_CHARGE..MAX_CHARGE,
+        );
+
+        let offset = offset + v2!(0.0, SLIDER_HEIGHT + GAP);
+        draw_slider(
+            offset,
+            "Viscosity",
+            SLIDER_LENGTH,
+            &mut self.viscosity,
+            0.0..MAX_VISCOSITY,
+        );
+
         self.color_picker
             .draw(offset + v2!(0.0, SLIDER_HEIGHT + 25.0));
     }
+
+    fn adjust_primary_parameter(&mut self, steps: f32) {
+        let count = self.droplet_count as f32 + steps;
+        self.droplet_count = (count.round() as u32).clamp(MIN_DROPLET_COUNT, MAX_DROPLET_COUNT);
+    }
 }
 
 impl FluidSelector {
@@ -96,6 +181,12 @@ impl FluidSelector {
         self.color_picker.color()
     }
 
+    /// Velocity given to newly spawned particles, not including the cursor velocity.
+    pub fn spawn_velocity(&self) -> Vector2<f32> {
+        let angle = self.spawn_direction.to_radians();
+        v2!(angle.cos(), angle.sin()) * self.spawn_speed
+    }
+
     fn draw_density_selector(&mut self, offset: Vector2<f32>) {
         draw_slider(
             offset,
@@ -1,16 +1,32 @@
+use std::collections::VecDeque;
+
 use macroquad::{
+    shapes::{draw_line, draw_rectangle_lines},
     text::{draw_text, TextDimensions},
-    time::get_fps,
+    time::{get_fps, get_frame_time},
+    ui::{root_ui, widgets::InputText},
 };
 
 use crate::{
-    game::ui::game_ui::FONT_SIZE_MEDIUM,
+    game::ui::game_ui::{FONT_SIZE_MEDIUM, FONT_SIZE_SMALL},
     game::UIComponent,
     math::{v2, Vector2},
+    physics::units::{to_cm, to_cm2, CM_PER_UNIT},
     rendering::Color,
     utility::AsMq,
 };
 
+/// How many of the last frame times are shown in the performance graph. About 3 seconds at 60 FPS.
+const FRAME_TIME_SAMPLES: usize = 180;
+/// Frame time the game aims for, shown as a threshold line in the performance graph. In seconds.
+const TARGET_FRAME_TIME: f32 = 1.0 / 60.0;
+const GRAPH_SIZE: Vector2<f32> = v2!(300.0, 60.0);
+const GRAPH_COLOR: Color = Color::rgb(0, 0, 0);
+const GRAPH_THRESHOLD_COLOR: Color = Color::rgb(220, 0, 0);
+/// Below this many particles the fluid is not blamed for a low frame rate.
+const MIN_SLOW_PARTICLE_COUNT: usize = 1000;
+const WARNING_COLOR: Color = Color::rgb(220, 0, 0);
+
 fn draw_vector2(vector: Vector2<f32>, offset: Vector2<f32>, preword: &str) -> TextDimensions {
     let text = format!("{} X: {:.2}, Y: {:.2}", preword, vector.x, vector.y);
     draw_text(
@@ -32,12 +48,21 @@ pub enum EntityInfo {
         position: Vector2<f32>,
         velocity: Vector2<f32>,
         mass: f32,
+        /// Area and perimeter in world units.
+        area: f32,
+        perimeter: f32,
         color: Color,
+        /// Contact points of the body during the last step.
+        contact_count: usize,
+        resting: bool,
     },
     Fluid {
+        id: u32,
         position: Vector2<f32>,
         velocity: Vector2<f32>,
-        density: f32,
+        mass: f32,
+        /// Density estimated by the SPH simulation from the surrounding particles.
+        sph_density: f32,
         color: Color,
     },
 }
@@ -46,19 +71,23 @@ impl EntityInfo {
     pub fn draw(&self, offset: Vector2<f32>) {
         match self {
             EntityInfo::Nothing { position } => {
-                draw_vector2(*position, offset, "Mouse position:");
+                draw_vector2(to_cm(*position), offset, "Mouse position [cm]:");
             }
             EntityInfo::Body {
                 index: _,
                 position,
                 velocity,
                 mass,
+                area,
+                perimeter,
                 color,
+                contact_count,
+                resting,
             } => {
-                let dim = draw_vector2(*position, offset, "Position:");
+                let dim = draw_vector2(to_cm(*position), offset, "Position [cm]:");
 
                 let offset = offset + v2!(0.0, dim.height + 20.0);
-                let dim = draw_vector2(*velocity, offset, "Velocity:");
+                let dim = draw_vector2(to_cm(*velocity), offset, "Velocity [cm/s]:");
 
                 let offset = offset + v2!(0.0, dim.height + 20.0);
                 let dim = draw_text(
@@ -70,7 +99,21 @@ impl EntityInfo {
                 );
 
                 let offset = offset + v2!(0.0, dim.height + 20.0);
-                let _dim = draw_text(
+                let dim = draw_text(
+                    format!(
+                        "Area: {:.1} [cm^2], Perimeter: {:.1} [cm]",
+                        to_cm2(*area),
+                        to_cm(*perimeter)
+                    )
+                    .as_str(),
+                    offset.x,
+                    offset.y,
+                    FONT_SIZE_MEDIUM,
+                    Color::rgb(0, 0, 0).as_mq(),
+                );
+
+                let offset = offset + v2!(0.0, dim.height + 20.0);
+                let dim = draw_text(
                     format!(
                         "Color: ({}, {}, {})",
                         (color.r * 255.0) as u8,
@@ -83,21 +126,48 @@ impl EntityInfo {
                     FONT_SIZE_MEDIUM,
                     Color::rgb(0, 0, 0).as_mq(),
                 );
+
+                let offset = offset + v2!(0.0, dim.height + 20.0);
+                let _dim = draw_text(
+                    format!(
+                        "Contact points: {}{}",
+                        contact_count,
+                        if *resting { " (resting)" } else { "" }
+                    )
+                    .as_str(),
+                    offset.x,
+                    offset.y,
+                    FONT_SIZE_MEDIUM,
+                    Color::rgb(0, 0, 0).as_mq(),
+                );
             }
             EntityInfo::Fluid {
                 position,
                 velocity,
-                density,
+                mass,
+                sph_density,
                 color,
+                ..
             } => {
-                let dim = draw_vector2(*position, offset, "Position:");
+                let dim = draw_vector2(to_cm(*position), offset, "Position [cm]:");
+
+                let offset = offset + v2!(0.0, dim.height + 20.0);
+                let dim = draw_vector2(to_cm(*velocity), offset, "Velocity [cm/s]:");
 
                 let offset = offset + v2!(0.0, dim.height + 20.0);
-                let dim = draw_vector2(*velocity, offset, "Velocity:");
+                let dim = draw_text(
+                    format!("Mass: {:.2} [g]", mass).as_str(),
+                    offset.x,
+                    offset.y,
+                    FONT_SIZE_MEDIUM,
+                    Color::rgb(0, 0, 0).as_mq(),
+                );
 
+                // The simulation is 2D so the density is per area
+                let density = sph_density / (CM_PER_UNIT * CM_PER_UNIT);
                 let offset = offset + v2!(0.0, dim.height + 20.0);
                 let dim = draw_text(
-                    format!("Density: {:.2} [g/cm^3]", density).as_str(),
+                    format!("Density: {:.2} [g/cm^2]", density).as_str(),
                     offset.x,
                     offset.y,
                     FONT_SIZE_MEDIUM,
@@ -125,26 +195,144 @@ impl EntityInfo {
 
 pub struct InfoPanel {
     pub particle_count: usize,
+    pub fluid_mass: f32,
     pub body_count: usize,
+    pub contact_count: usize,
+    pub island_count: usize,
+    pub body_kinetic_energy: f32,
+    pub fluid_kinetic_energy: f32,
+    pub body_momentum: Vector2<f32>,
+    pub fluid_momentum: Vector2<f32>,
+    /// Mass per second crossing the flow probe, if there is one.
+    pub flow_rate: Option<f32>,
+    /// Id of the particle whose path is traced, if there is one.
+    pub traced_particle: Option<u32>,
+    /// Simulated time per frame in seconds and the number of sub-steps it is divided into.
+    pub time_step: f32,
+    pub sub_steps: u8,
+    /// True while the drop test runs. Its results are one line per dropped box, empty until the
+    /// test is run for the first time.
+    pub drop_test_running: bool,
+    pub drop_test_results: Vec<String>,
+    /// Durations of the last frames in seconds, oldest first.
+    frame_times: VecDeque<f32>,
+    /// The lowest particle count at which the frame time was over the target. More fluid than this
+    /// is expected to slow down the game again.
+    slow_particle_count: Option<usize>,
     pub under_mouse_entity: EntityInfo,
     pub is_simulating: bool,
+    /// Index of the body whose tag is being edited. Selected by clicking on a body.
+    pub selected_body: Option<usize>,
+    pub tag_input: String,
+    pub taken_input: bool,
 }
 
 impl Default for InfoPanel {
     fn default() -> Self {
         InfoPanel {
             particle_count: 0,
+            fluid_mass: 0.0,
             body_count: 0,
+            contact_count: 0,
+            island_count: 0,
+            body_kinetic_energy: 0.0,
+            fluid_kinetic_energy: 0.0,
+            body_momentum: Vector2::zero(),
+            fluid_momentum: Vector2::zero(),
+            flow_rate: None,
+            traced_particle: None,
+            time_step: 0.0,
+            sub_steps: 0,
+            drop_test_running: false,
+            drop_test_results: Vec::new(),
+            frame_times: VecDeque::with_capacity(FRAME_TIME_SAMPLES),
+            slow_particle_count: None,
             under_mouse_entity: EntityInfo::Nothing {
                 position: Vector2::zero(),
             },
             is_simulating: true,
+            selected_body: None,
+            tag_input: String::new(),
+            taken_input: false,
+        }
+    }
+}
+
+impl InfoPanel {
+    /// Remembers the particle count if the recent frames were too slow while there was a lot of
+    /// fluid. Returns true if the current particle count is at or above the remembered one.
+    fn update_slow_particle_count(&mut self) -> bool {
+        let average_frame_time = self.average_frame_time();
+        let is_full = self.frame_times.len() == FRAME_TIME_SAMPLES;
+        if self.is_simulating
+            && is_full
+            && average_frame_time > TARGET_FRAME_TIME
+            && self.particle_count >= MIN_SLOW_PARTICLE_COUNT
+        {
+            let count = self.slow_particle_count.unwrap_or(usize::MAX);
+            self.slow_particle_count = Some(count.min(self.particle_count));
+        }
+
+        self.slow_particle_count
+            .is_some_and(|count| self.particle_count >= count)
+    }
+
+    fn average_frame_time(&self) -> f32 {
+        self.frame_times.iter().sum::<f32>() / self.frame_times.len().max(1) as f32
+    }
+
+    /// Draws the recent frame times as a line graph with the top-left corner at `offset`.
+    /// The graph is scaled so that both the slowest frame and the target frame time fit in.
+    fn draw_frame_time_graph(&self, offset: Vector2<f32>) {
+        let max_time = self
+            .frame_times
+            .iter()
+            .copied()
+            .fold(TARGET_FRAME_TIME * 2.0, f32::max);
+        let point = |index: usize, time: f32| {
+            let x = index as f32 / (FRAME_TIME_SAMPLES - 1) as f32 * GRAPH_SIZE.x;
+            let y = (1.0 - time / max_time) * GRAPH_SIZE.y;
+            offset + v2!(x, y)
+        };
+
+        draw_rectangle_lines(
+            offset.x,
+            offset.y,
+            GRAPH_SIZE.x,
+            GRAPH_SIZE.y,
+            1.0,
+            GRAPH_COLOR.as_mq(),
+        );
+        let threshold_y = point(0, TARGET_FRAME_TIME).y;
+        draw_line(
+            offset.x,
+            threshold_y,
+            offset.x + GRAPH_SIZE.x,
+            threshold_y,
+            1.0,
+            GRAPH_THRESHOLD_COLOR.as_mq(),
+        );
+
+        let points: Vec<Vector2<f32>> = self
+            .frame_times
+            .iter()
+            .enumerate()
+            .map(|(index, time)| point(index, *time))
+            .collect();
+        for segment in points.windows(2) {
+            let (start, end) = (segment[0], segment[1]);
+            draw_line(start.x, start.y, end.x, end.y, 1.5, GRAPH_COLOR.as_mq());
         }
     }
 }
 
 impl UIComponent for InfoPanel {
     fn draw(&mut self, offset: Vector2<f32>) {
+        if self.frame_times.len() == FRAME_TIME_SAMPLES {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(get_frame_time());
+
         let offset = offset + v2!(0.0, 20.0);
         let fps = if self.is_simulating {
             format!("FPS: {}", get_fps())
@@ -159,7 +347,29 @@ impl UIComponent for InfoPanel {
             Color::rgb(0, 0, 0).as_mq(),
         );
 
+        // Above 1 the simulation runs faster than real time, below 1 slower
         let offset = offset + v2!(0.0, dim.height + 20.0);
+        let real_time_factor = if self.is_simulating {
+            format!("{:.2}x", self.time_step / self.average_frame_time())
+        } else {
+            "(paused)".to_owned()
+        };
+        let time_step = format!(
+            "Real-time factor: {real_time_factor} ({:.3} s in {} sub-steps per frame)",
+            self.time_step, self.sub_steps
+        );
+        let dim = draw_text(
+            time_step.as_str(),
+            offset.x,
+            offset.y,
+            FONT_SIZE_SMALL,
+            Color::rgb(0, 0, 0).as_mq(),
+        );
+
+        let offset = offset + v2!(0.0, dim.height);
+        self.draw_frame_time_graph(offset);
+
+        let offset = offset + v2!(0.0, GRAPH_SIZE.y + 30.0);
         let p_count = format!("Particle count: {}", self.particle_count);
         let dim = draw_text(
             p_count.as_str(),
@@ -169,6 +379,38 @@ impl UIComponent for InfoPanel {
             Color::rgb(0, 0, 0).as_mq(),
         );
 
+        let offset = if self.update_slow_particle_count() {
+            let offset = offset + v2!(0.0, FONT_SIZE_SMALL + 5.0);
+            draw_text(
+                "Too much fluid to simulate smoothly: lower the droplet count",
+                offset.x,
+                offset.y,
+                FONT_SIZE_SMALL,
+                WARNING_COLOR.as_mq(),
+            );
+            let offset = offset + v2!(0.0, FONT_SIZE_SMALL);
+            draw_text(
+                "or turn on merging of close particles in the configuration.",
+                offset.x,
+                offset.y,
+                FONT_SIZE_SMALL,
+                WARNING_COLOR.as_mq(),
+            );
+            offset
+        } else {
+            offset
+        };
+
+        let offset = offset + v2!(0.0, dim.height + 20.0);
+        let fluid_mass = format!("Total fluid mass: {:.2}", self.fluid_mass);
+        let dim = draw_text(
+            fluid_mass.as_str(),
+            offset.x,
+            offset.y,
+            FONT_SIZE_MEDIUM,
+            Color::rgb(0, 0, 0).as_mq(),
+        );
+
         let offset = offset + v2!(0.0, dim.height + 20.0);
         let body_count = format!("Body count: {}", self.body_count);
         let dim = draw_text(
@@ -179,6 +421,90 @@ impl UIComponent for InfoPanel {
             Color::rgb(0, 0, 0).as_mq(),
         );
 
+        let offset = offset + v2!(0.0, dim.height + 20.0);
+        let contacts = format!(
+            "Contacts: {}, Islands: {}",
+            self.contact_count, self.island_count
+        );
+        let dim = draw_text(
+            contacts.as_str(),
+            offset.x,
+            offset.y,
+            FONT_SIZE_MEDIUM,
+            Color::rgb(0, 0, 0).as_mq(),
+        );
+
+        let offset = offset + v2!(0.0, dim.height + 20.0);
+        let kinetic_energy = format!(
+            "Kinetic energy: Bodies {:.0}, Fluid {:.0}",
+            to_cm2(self.body_kinetic_energy),
+            to_cm2(self.fluid_kinetic_energy)
+        );
+        let dim = draw_text(
+            kinetic_energy.as_str(),
+            offset.x,
+            offset.y,
+            FONT_SIZE_MEDIUM,
+            Color::rgb(0, 0, 0).as_mq(),
+        );
+
+        let offset = offset + v2!(0.0, dim.height + 20.0);
+        let dim = draw_vector2(to_cm(self.body_momentum), offset, "Momentum of bodies:");
+
+        let offset = offset + v2!(0.0, dim.height + 20.0);
+        let dim = draw_vector2(to_cm(self.fluid_momentum), offset, "Momentum of fluid:");
+
+        let offset = offset + v2!(0.0, dim.height + 20.0);
+        let flow_rate = match self.flow_rate {
+            Some(rate) => format!("Flow rate: {:.2} [g/s]", rate),
+            None => "Flow rate: [Right MB] drag to place a probe".to_string(),
+        };
+        let dim = draw_text(
+            flow_rate.as_str(),
+            offset.x,
+            offset.y,
+            FONT_SIZE_MEDIUM,
+            Color::rgb(0, 0, 0).as_mq(),
+        );
+
+        let offset = offset + v2!(0.0, dim.height + 20.0);
+        let traced_particle = match self.traced_particle {
+            Some(id) => format!("Traced particle: {id}"),
+            None => "Traced particle: [Left MB] on fluid to trace".to_string(),
+        };
+        let dim = draw_text(
+            traced_particle.as_str(),
+            offset.x,
+            offset.y,
+            FONT_SIZE_MEDIUM,
+            Color::rgb(0, 0, 0).as_mq(),
+        );
+
+        let offset = offset + v2!(0.0, dim.height + 20.0);
+        let drop_test = if self.drop_test_running {
+            "Drop test: running..."
+        } else {
+            "Drop test: [F4] to run"
+        };
+        let dim = draw_text(
+            drop_test,
+            offset.x,
+            offset.y,
+            FONT_SIZE_MEDIUM,
+            Color::rgb(0, 0, 0).as_mq(),
+        );
+        let mut offset = offset;
+        for line in &self.drop_test_results {
+            offset += v2!(0.0, FONT_SIZE_SMALL);
+            draw_text(
+                line.as_str(),
+                offset.x,
+                offset.y,
+                FONT_SIZE_SMALL,
+                Color::rgb(0, 0, 0).as_mq(),
+            );
+        }
+
         let offset = offset + v2!(0.0, dim.height + 40.0);
         let entity_name = match self.under_mouse_entity {
             EntityInfo::Nothing { .. } => "Nothing",
@@ -195,5 +521,26 @@ impl UIComponent for InfoPanel {
 
         let offset = offset + v2!(20.0, dim.height + 20.0);
         self.under_mouse_entity.draw(offset);
+
+        self.taken_input = false;
+        if let Some(index) = self.selected_body {
+            // Leave space for the under-cursor entity info
+            let offset = offset + v2!(-20.0, 260.0);
+            let dim = draw_text(
+                format!("Tag of body {index}:").as_str(),
+                offset.x,
+                offset.y,
+                FONT_SIZE_MEDIUM,
+                Color::rgb(0, 0, 0).as_mq(),
+            );
+
+            let offset_input = offset + v2!(dim.width + 10.0, -dim.height);
+            let old_tag_input = self.tag_input.clone();
+            InputText::new(43)
+                .position(offset_input.as_mq())
+                .size(v2!(200.0, 25.0).as_mq())
+                .ui(&mut root_ui(), &mut self.tag_input);
+            self.taken_input = self.tag_input != old_tag_input;
+        }
     }
 }
@@ -2,17 +2,21 @@ mod body_maker;
 mod color_picker;
 mod fluid_selector;
 mod info;
+mod painter;
 mod quick_menu;
 mod saves_loads;
+mod stirrer;
 
 use std::ops::Range;
 
-pub use body_maker::BodyMaker;
+pub use body_maker::{BodyMaker, MassMode};
 pub use color_picker::ColorPicker;
 pub use fluid_selector::{FluidSelector, FluidSelectorAction};
 pub use info::{EntityInfo, InfoPanel};
+pub use painter::Painter;
 pub use quick_menu::{QuickAction, QuickMenu};
 pub use saves_loads::{SaveLoadAction, SavesLoads};
+pub use stirrer::Stirrer;
 
 use macroquad::ui::{root_ui, widgets::Slider};
 
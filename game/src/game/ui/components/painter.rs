@@ -0,0 +1,49 @@
+use macroquad::text::draw_text;
+
+use crate::game::FONT_SIZE_SMALL;
+use crate::utility::AsMq;
+use crate::{
+    game::UIComponent,
+    math::{v2, Vector2},
+    rendering::Color,
+};
+
+use super::ColorPicker;
+
+const TUTORIAL_LINES: [&str; 1] = ["[Left MB] - Paint bodies under cursor"];
+
+pub struct Painter {
+    color_picker: ColorPicker,
+}
+
+impl Default for Painter {
+    fn default() -> Self {
+        Painter {
+            color_picker: ColorPicker::new(Color::rgb(200, 30, 30)),
+        }
+    }
+}
+
+impl UIComponent for Painter {
+    fn draw(&mut self, offset: Vector2<f32>) {
+        let mut offset = offset;
+        for line in TUTORIAL_LINES {
+            draw_text(
+                line,
+                offset.x,
+                offset.y,
+                FONT_SIZE_SMALL,
+                Color::rgb(0, 0, 0).as_mq(),
+            );
+            offset += v2!(0.0, FONT_SIZE_SMALL + 10.0);
+        }
+
+        self.color_picker.draw(offset + v2!(0.0, 25.0));
+    }
+}
+
+impl Painter {
+    pub fn color(&self) -> Color {
+        self.color_picker.color()
+    }
+}
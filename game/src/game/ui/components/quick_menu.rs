@@ -15,6 +15,9 @@ pub enum QuickAction {
     Quit,
     Restart,
     TogglePause,
+    ClearBodies,
+    ExportStats,
+    ExportBodies,
 }
 
 impl Default for QuickAction {
@@ -46,6 +49,9 @@ impl UIComponent for QuickMenu {
             ("Restart", QuickAction::Restart, &red_skin),
             ("Quit", QuickAction::Quit, &red_skin),
             ("(Un)Pause", QuickAction::TogglePause, &default_skin),
+            ("Clear bodies", QuickAction::ClearBodies, &red_skin),
+            ("Export stats", QuickAction::ExportStats, &default_skin),
+            ("Export bodies", QuickAction::ExportBodies, &default_skin),
         ];
 
         for (row_index, item) in items.iter().enumerate() {
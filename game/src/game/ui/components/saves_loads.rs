@@ -8,7 +8,7 @@ use macroquad::text::draw_text;
 use macroquad::ui::root_ui;
 use macroquad::ui::widgets::{Button, InputText};
 
-use crate::game::ui::RED_BUTTON_SKIN;
+use crate::game::ui::{red_button_skin, RED_BUTTON_SKIN};
 use crate::game::{save_load, FONT_SIZE_MEDIUM};
 use crate::rendering::Color;
 use crate::serialization::GameSerializedForm;
@@ -34,6 +34,8 @@ pub enum SaveLoadAction {
     Nothing,
     Save,
     Load(GameSerializedForm),
+    /// Adds the contents of the save to the current scene instead of replacing it.
+    Import(GameSerializedForm),
 }
 
 impl Default for SavesLoads {
@@ -153,14 +155,23 @@ impl UIComponent for SavesLoads {
                     return;
                 }
 
+                if Button::new("Import")
+                    .size(v2!(60.0, 25.0).as_mq())
+                    .position((offset + v2!(220.0, 0.0)).as_mq())
+                    .ui(&mut root_ui())
+                {
+                    self.action = SaveLoadAction::Import(save_load::load_save(save));
+                    return;
+                }
+
                 offset += v2!(0.0, 35.0);
             }
 
-            // Draw a second column of button for deleting
-            root_ui().push_skin(RED_BUTTON_SKIN.get().unwrap());
+            // Draw a third column of button for deleting
+            root_ui().push_skin(RED_BUTTON_SKIN.get_or_init(red_button_skin));
             offset = og_offset;
             for save in &*read {
-                let side_offset = offset + v2!(240.0, 0.0);
+                let side_offset = offset + v2!(300.0, 0.0);
 
                 // Do not draw delete button for pretected savefiles - containing '_'
                 if !save.starts_with('_') {
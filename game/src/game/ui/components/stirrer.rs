@@ -0,0 +1,60 @@
+use macroquad::text::draw_text;
+
+use crate::game::{draw_slider, FONT_SIZE_SMALL};
+use crate::utility::AsMq;
+use crate::{
+    game::UIComponent,
+    math::{v2, Vector2},
+    rendering::Color,
+};
+
+use super::SLIDER_LENGTH;
+
+const MIN_RADIUS: f32 = 10.0;
+const MAX_RADIUS: f32 = 100.0;
+/// Change of the radius per notch of the mouse wheel.
+const RADIUS_STEP: f32 = 5.0;
+
+const TUTORIAL_LINES: [&str; 2] = [
+    "[Left MB] - Drag through fluid to stir it",
+    "[Mouse wheel] - Change the radius",
+];
+
+pub struct Stirrer {
+    /// Particles closer to the cursor than this are dragged along.
+    pub radius: f32,
+}
+
+impl Default for Stirrer {
+    fn default() -> Self {
+        Stirrer { radius: 40.0 }
+    }
+}
+
+impl UIComponent for Stirrer {
+    fn draw(&mut self, offset: Vector2<f32>) {
+        let mut offset = offset;
+        for line in TUTORIAL_LINES {
+            draw_text(
+                line,
+                offset.x,
+                offset.y,
+                FONT_SIZE_SMALL,
+                Color::rgb(0, 0, 0).as_mq(),
+            );
+            offset += v2!(0.0, FONT_SIZE_SMALL + 10.0);
+        }
+
+        draw_slider(
+            offset,
+            "Radius",
+            SLIDER_LENGTH,
+            &mut self.radius,
+            MIN_RADIUS..MAX_RADIUS,
+        );
+    }
+
+    fn adjust_primary_parameter(&mut self, steps: f32) {
+        self.radius = (self.radius + steps * RADIUS_STEP).clamp(MIN_RADIUS, MAX_RADIUS);
+    }
+}
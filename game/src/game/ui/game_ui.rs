@@ -12,8 +12,8 @@ use crate::{
 };
 
 use super::{
-    red_button_skin, BodyMaker, FluidSelector, InfoPanel, QuickMenu, SavesLoads, UIComponent,
-    UIEdit, RED_BUTTON_SKIN,
+    red_button_skin, BodyMaker, FluidSelector, InfoPanel, Painter, QuickMenu, SavesLoads, Stirrer,
+    UIComponent, UIEdit, RED_BUTTON_SKIN,
 };
 
 pub const FONT_SIZE_LARGE: f32 = 36.0;
@@ -27,11 +27,18 @@ const TOOL_BUTTON_HEIGHT: f32 = 25.0;
 const TOOL_BUTTON_GAP: f32 = 40.0;
 const TOOL_BUTTON_SELECTED_OUTLINE: f32 = 4.0;
 
+const CONFIG_INPUT_SIZE: Vector2<f32> = v2!(80.0, 20.0);
+const SUBSYSTEM_TOGGLE_GAP: f32 = 200.0;
+const CONFIG_BUTTON_SIZE: Vector2<f32> = v2!(110.0, 25.0);
+const CONFIG_BUTTON_GAP: f32 = 10.0;
+
 #[derive(Clone, Copy, PartialEq)]
 pub enum Tool {
     Info,
     Fluid,
     Rigidbody,
+    Paint,
+    Stir,
     Configuration,
     SaveLoads,
 }
@@ -43,29 +50,44 @@ pub struct InGameUI {
     pub info_panel: InfoPanel,
     pub save_loads: SavesLoads,
     pub body_maker: BodyMaker,
+    pub painter: Painter,
+    pub stirrer: Stirrer,
     pub quick_menu: QuickMenu,
 
     pub selected_tool: Tool,
+    /// True in the frame when the "Clear bodies" button of the configuration was clicked.
+    pub clear_bodies_clicked: bool,
 }
 
 impl Default for InGameUI {
     fn default() -> Self {
-        let _ = RED_BUTTON_SKIN.set(red_button_skin());
-
         InGameUI {
             fluid_selector: FluidSelector::default(),
             info_panel: InfoPanel::default(),
             save_loads: SavesLoads::default(),
             body_maker: BodyMaker::default(),
+            painter: Painter::default(),
+            stirrer: Stirrer::default(),
             quick_menu: QuickMenu::default(),
 
             selected_tool: Tool::Info,
+            clear_bodies_clicked: false,
         }
     }
 }
 
 impl InGameUI {
-    pub fn draw(&mut self, offset: Vector2<f32>, game_config: &mut GameConfig) {
+    /// Draws the tools and the selected one. `simulate_fluid` and `simulate_bodies` are edited
+    /// in the configuration tool.
+    pub fn draw(
+        &mut self,
+        offset: Vector2<f32>,
+        game_config: &mut GameConfig,
+        simulate_fluid: &mut bool,
+        simulate_bodies: &mut bool,
+    ) {
+        self.clear_bodies_clicked = false;
+
         draw_text(
             "Tools",
             offset.x,
@@ -84,6 +106,12 @@ impl InGameUI {
             let offset = offset + v2!(TOOL_BUTTON_WIDTH + TOOL_BUTTON_GAP, 0.0);
             self.draw_tool_button("Bodies [B]", Tool::Rigidbody, offset);
 
+            let offset = offset + v2!(TOOL_BUTTON_WIDTH + TOOL_BUTTON_GAP, 0.0);
+            self.draw_tool_button("Paint [P]", Tool::Paint, offset);
+
+            let offset = offset + v2!(TOOL_BUTTON_WIDTH + TOOL_BUTTON_GAP, 0.0);
+            self.draw_tool_button("Stir [S]", Tool::Stir, offset);
+
             let offset = offset + v2!(TOOL_BUTTON_WIDTH + TOOL_BUTTON_GAP, 0.0);
             self.draw_tool_button("Config [C]", Tool::Configuration, offset);
 
@@ -100,13 +128,57 @@ impl InGameUI {
             Tool::Info => self.info_panel.draw(offset),
             Tool::Fluid => self.fluid_selector.draw(offset),
             Tool::Rigidbody => self.body_maker.draw(offset),
+            Tool::Paint => self.painter.draw(offset),
+            Tool::Stir => self.stirrer.draw(offset),
             Tool::Configuration => {
-                game_config.draw_edit(offset, v2!(80.0, 20.0), "");
+                root_ui().push_skin(RED_BUTTON_SKIN.get_or_init(red_button_skin));
+                let button_offset = offset + v2!(SUBSYSTEM_TOGGLE_GAP * 2.0, 0.0);
+                self.clear_bodies_clicked = Button::new("Clear bodies")
+                    .size(CONFIG_BUTTON_SIZE.as_mq())
+                    .position(button_offset.as_mq())
+                    .ui(&mut root_ui());
+                root_ui().pop_skin();
+
+                let button_offset =
+                    button_offset + v2!(CONFIG_BUTTON_SIZE.x + CONFIG_BUTTON_GAP, 0.0);
+                let reset_clicked = Button::new("Reset config")
+                    .size(CONFIG_BUTTON_SIZE.as_mq())
+                    .position(button_offset.as_mq())
+                    .ui(&mut root_ui());
+                if reset_clicked {
+                    *game_config = GameConfig::default();
+                }
+
+                simulate_fluid.draw_edit(offset, CONFIG_INPUT_SIZE, "Simulate fluid");
+                simulate_bodies.draw_edit(
+                    offset + v2!(SUBSYSTEM_TOGGLE_GAP, 0.0),
+                    CONFIG_INPUT_SIZE,
+                    "Simulate bodies",
+                );
+
+                let offset = offset + v2!(0.0, CONFIG_INPUT_SIZE.y * 2.0);
+                game_config.draw_edit(offset, CONFIG_INPUT_SIZE, "");
             }
             Tool::SaveLoads => self.save_loads.draw(offset),
         };
     }
 
+    /// Changes the main setting of the selected tool, see `UIComponent::adjust_primary_parameter`.
+    pub fn adjust_selected_tool(&mut self, steps: f32) {
+        match self.selected_tool {
+            Tool::Fluid => self.fluid_selector.adjust_primary_parameter(steps),
+            Tool::Rigidbody => self.body_maker.adjust_primary_parameter(steps),
+            Tool::Stir => self.stirrer.adjust_primary_parameter(steps),
+            _ => {}
+        }
+    }
+
+    /// Returns true if any text input was edited this frame, in which case keyboard shortcuts
+    /// should be ignored.
+    pub fn taken_input(&self) -> bool {
+        self.save_loads.taken_input || self.info_panel.taken_input
+    }
+
     fn draw_tool_button(&mut self, title: &'static str, tool: Tool, offset: Vector2<f32>) {
         if self.selected_tool == tool {
             draw_rectangle(
@@ -9,7 +9,7 @@ use macroquad::{
     text::draw_text,
     ui::{
         root_ui,
-        widgets::{Button, ComboBox, InputText, Label},
+        widgets::{Button, Checkbox, ComboBox, InputText, Label, Slider},
         Skin, Style,
     },
 };
@@ -82,9 +82,55 @@ impl<T, const C: usize> Selection<T, C> {
     }
 }
 
+#[cfg(test)]
+impl<T: PartialEq, const C: usize> Selection<T, C> {
+    /// Selects the first option with the `value`. Does nothing if there is no such option.
+    pub fn select(&mut self, value: T) {
+        if let Some(index) = self.values.iter().position(|v| *v == value) {
+            self.selected = index;
+        }
+    }
+}
+
+/// Serialized as the name of the selected option.
+impl<T, const C: usize> serde::Serialize for Selection<T, C> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.names[self.selected])
+    }
+}
+
+/// A number that is edited with a slider within the `min..max` range.
+#[derive(Clone)]
+pub struct Ranged {
+    value: f32,
+    min: f32,
+    max: f32,
+}
+
+impl Ranged {
+    pub const fn new(value: f32, min: f32, max: f32) -> Self {
+        Ranged { value, min, max }
+    }
+
+    pub fn get_value(&self) -> f32 {
+        self.value
+    }
+}
+
+/// Serialized as just the value.
+impl serde::Serialize for Ranged {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f32(self.value)
+    }
+}
+
 pub trait UIComponent {
     /// Draws this component to the screen at the specified offset.
     fn draw(&mut self, offset: Vector2<f32>);
+
+    /// Changes the main setting of this component by `steps` notches of the mouse wheel. Positive
+    /// steps increase it. Does nothing by default.
+    fn adjust_primary_parameter(&mut self, _steps: f32) {}
 }
 
 /// Draws this type to the screen just as `UIComponent` but specificly for edititng.
@@ -160,6 +206,23 @@ impl UIEdit for Vector2<f32> {
     }
 }
 
+impl UIEdit for bool {
+    fn draw_edit(
+        &mut self,
+        position: Vector2<f32>,
+        input_size: Vector2<f32>,
+        label: &str,
+    ) -> Vector2<f32> {
+        Checkbox::new(id_from_position(position))
+            .pos(position.as_mq())
+            .label(label)
+            .size(v2!(input_size.y, input_size.y).as_mq())
+            .ui(&mut root_ui(), self);
+
+        input_size
+    }
+}
+
 impl<T, const C: usize> UIEdit for Selection<T, C> {
     fn draw_edit(
         &mut self,
@@ -180,6 +243,45 @@ impl<T, const C: usize> UIEdit for Selection<T, C> {
     }
 }
 
+impl UIEdit for String {
+    fn draw_edit(
+        &mut self,
+        position: Vector2<f32>,
+        size: Vector2<f32>,
+        label: &str,
+    ) -> Vector2<f32> {
+        InputText::new(id_from_position(position))
+            .position(position.as_mq())
+            .size(size.as_mq())
+            .label(label)
+            .label_font_size(FONT_SIZE_SMALL)
+            .input_font_size(FONT_SIZE_SMALL)
+            .ui(&mut root_ui(), self);
+
+        size
+    }
+}
+
+impl UIEdit for Ranged {
+    fn draw_edit(
+        &mut self,
+        position: Vector2<f32>,
+        input_size: Vector2<f32>,
+        label: &str,
+    ) -> Vector2<f32> {
+        let size = v2!(input_size.x * 3.0, input_size.y);
+        Slider::new(id_from_position(position), self.min..self.max)
+            .label(label)
+            .position(position.as_mq())
+            .size(size.as_mq())
+            .ui(&mut root_ui(), &mut self.value);
+        // Clamp the value into the range - in case of change using the input box
+        self.value = self.value.clamp(self.min, self.max);
+
+        size
+    }
+}
+
 impl UIEdit for &str {
     fn draw_edit(
         &mut self,
@@ -24,6 +24,8 @@ fn window_conf() -> Conf {
         window_height: HEIGHT as i32,
         window_resizable: true,
         fullscreen: true,
+        // MSAA for smooth edges of bodies and their outlines
+        sample_count: 4,
         ..Default::default()
     }
 }
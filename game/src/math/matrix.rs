@@ -38,6 +38,21 @@ impl Matrix<f32, 2, 2> {
 
         Matrix::new([[cos, -sin], [sin, cos]])
     }
+
+    pub fn determinant(&self) -> f32 {
+        self.inner[0][0] * self.inner[1][1] - self.inner[0][1] * self.inner[1][0]
+    }
+
+    /// Returns the inverse of this matrix or `None` if it is singular.
+    pub fn inverse(&self) -> Option<Self> {
+        let det = self.determinant();
+        if det == 0.0 {
+            return None;
+        }
+
+        let [[a, b], [c, d]] = self.inner;
+        Some(Matrix::new([[d, -b], [-c, a]]) * (1.0 / det))
+    }
 }
 
 impl<T> From<Vector2<T>> for Matrix<T, 2, 1>
@@ -155,4 +170,14 @@ mod tests {
 
         assert_eq!(res, Matrix::new([[36, 72], [126, 252],]))
     }
+
+    #[test]
+    fn inverse_2x2() {
+        let mat = Matrix::new([[2.0, 1.0], [1.0, 1.0]]);
+
+        let res = mat.inverse().unwrap();
+
+        assert_eq!(res, Matrix::new([[1.0, -1.0], [-1.0, 2.0]]));
+        assert_eq!(Matrix::new([[1.0, 2.0], [2.0, 4.0]]).inverse(), None);
+    }
 }
@@ -135,27 +135,30 @@ where
         self.x * other.y - self.y * other.x
     }
 
-    pub fn clamp(&self, min: Vector2<T>, max: Vector2<T>) -> Vector2<T>
+    /// Component-wise minimum of the 2 vectors.
+    pub fn min(&self, other: Vector2<T>) -> Vector2<T>
     where
         T: PartialOrd,
     {
-        let x = if self.x < min.x {
-            min.x
-        } else if self.x > max.x {
-            max.x
-        } else {
-            self.x
-        };
-
-        let y = if self.y < min.y {
-            min.y
-        } else if self.y > max.y {
-            max.y
-        } else {
-            self.y
-        };
+        let pick = |a: T, b: T| if b < a { b } else { a };
+        Vector2::new(pick(self.x, other.x), pick(self.y, other.y))
+    }
 
-        Vector2 { x, y }
+    /// Component-wise maximum of the 2 vectors.
+    pub fn max(&self, other: Vector2<T>) -> Vector2<T>
+    where
+        T: PartialOrd,
+    {
+        let pick = |a: T, b: T| if b > a { b } else { a };
+        Vector2::new(pick(self.x, other.x), pick(self.y, other.y))
+    }
+
+    /// Clamps each component of this vector between the components of `min` and `max`.
+    pub fn clamp(&self, min: Vector2<T>, max: Vector2<T>) -> Vector2<T>
+    where
+        T: PartialOrd,
+    {
+        self.max(min).min(max)
     }
 }
 
@@ -287,4 +290,18 @@ mod tests {
 
         assert_eq!(reflected, v2!(3, 3; f32))
     }
+
+    #[test]
+    fn component_wise_min_max_clamp() {
+        let a = v2!(1.0, 5.0);
+        let b = v2!(3.0, -2.0);
+
+        assert_eq!(a.min(b), v2!(1.0, -2.0));
+        assert_eq!(a.max(b), v2!(3.0, 5.0));
+        assert_eq!(
+            v2!(-1.0, 4.0).clamp(v2!(0.0, 0.0), v2!(2.0, 2.0)),
+            v2!(0.0, 2.0)
+        );
+        assert_eq!(v2!(1, 1).clamp(v2!(0, 0), v2!(2, 2)), v2!(1, 1));
+    }
 }
@@ -1,3 +1,4 @@
 #[macro_use]
 pub mod rigidbody;
 pub mod sph;
+pub mod units;
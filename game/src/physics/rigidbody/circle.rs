@@ -2,6 +2,7 @@ use crate::math::{v2, Vector2};
 
 use super::{BodyState, PointsProjection};
 
+#[derive(Clone)]
 pub struct CircleInner {
     pub(super) state: BodyState,
     pub radius: f32,
@@ -5,6 +5,7 @@ use super::{circle::CircleInner, polygon::PolygonInner, BodyCollisionData};
 pub fn polygon_polygon_collision(
     this: &PolygonInner,
     other: &PolygonInner,
+    contact_offset: f32,
 ) -> Option<BodyCollisionData> {
     let other_position = other.state.position;
     let this_position = this.state.position;
@@ -28,8 +29,8 @@ pub fn polygon_polygon_collision(
 
     // Test projection axes of this body - SAT
     for axis in this_projection_axes {
-        let proj_a = this.project_onto_axis(axis);
-        let proj_b = other.project_onto_axis(axis);
+        let proj_a = this.project_onto_axis(axis, contact_offset);
+        let proj_b = other.project_onto_axis(axis, contact_offset);
 
         if let Some(penetration) = proj_a.get_overlap(&proj_b) {
             if penetration < min_penetration {
@@ -44,8 +45,8 @@ pub fn polygon_polygon_collision(
     // Test projection axes of the other body
     let mut axis_is_from_other = false;
     for axis in other_projection_axes {
-        let proj_a = this.project_onto_axis(axis);
-        let proj_b = other.project_onto_axis(axis);
+        let proj_a = this.project_onto_axis(axis, contact_offset);
+        let proj_b = other.project_onto_axis(axis, contact_offset);
 
         if let Some(penetration) = proj_a.get_overlap(&proj_b) {
             if penetration < min_penetration {
@@ -71,6 +72,9 @@ pub fn polygon_polygon_collision(
 
     // Find the reference and incident line
     let (ref_line, inc_line);
+    // The contact points are the vertices of the incident body, which itself is extended by the
+    // offset too
+    let clip_offset = contact_offset * 2.0;
     let ref_body_proj;
     // Select the line that is more perpendicular to the normal
     if line_a.vector().normalized().dot(min_axis).abs()
@@ -79,12 +83,12 @@ pub fn polygon_polygon_collision(
         ref_line = line_a;
         inc_line = line_b;
 
-        ref_body_proj = this.project_onto_axis(min_axis);
+        ref_body_proj = this.project_onto_axis(min_axis, clip_offset);
     } else {
         ref_line = line_b;
         inc_line = line_a;
 
-        ref_body_proj = other.project_onto_axis(min_axis);
+        ref_body_proj = other.project_onto_axis(min_axis, clip_offset);
     }
 
     // Clip the incident line to find the collision points
@@ -101,12 +105,15 @@ pub fn polygon_polygon_collision(
 pub fn circle_circle_collision(
     this: &CircleInner,
     other: &CircleInner,
+    contact_offset: f32,
 ) -> Option<BodyCollisionData> {
     let this_position = this.state.position;
     let other_position = other.state.position;
     let this_to_other = other_position - this_position;
+    let this_radius = this.radius + contact_offset;
+    let other_radius = other.radius + contact_offset;
 
-    let radius_sum = this.radius + other.radius;
+    let radius_sum = this_radius + other_radius;
     let radius_sum_squared = radius_sum.powi(2);
 
     // Distance of centers is bigger than their summed radiuses -> they do not collide
@@ -121,11 +128,11 @@ pub fn circle_circle_collision(
     // dist = this.radius + other.radius - penetration
     // => penetration = this.radius + other.radius - dist
     let dist = this_to_other.length();
-    let penetration = this.radius + other.radius - dist;
+    let penetration = radius_sum - dist;
 
     // The collision point will be the middle point between the edges of the circles along the
     // normal
-    let collision_point = this_position + normal * (this.radius - penetration * 0.5);
+    let collision_point = this_position + normal * (this_radius - penetration * 0.5);
 
     Some(BodyCollisionData {
         normal,
@@ -137,8 +144,11 @@ pub fn circle_circle_collision(
 pub fn polygon_circle_collision(
     polygon: &PolygonInner,
     circle: &CircleInner,
+    contact_offset: f32,
 ) -> Option<BodyCollisionData> {
     let circle_center = circle.state.position;
+    // Both the polygon and the circle are extended by the offset
+    let radius = circle.radius + contact_offset * 2.0;
 
     let (mut min_distance_sq, mut min_point, mut normal) =
         (f32::MAX, Vector2::zero(), Vector2::zero());
@@ -154,7 +164,7 @@ pub fn polygon_circle_collision(
         }
     }
 
-    if min_distance_sq > circle.radius.powi(2) {
+    if min_distance_sq > radius.powi(2) {
         return None;
     }
 
@@ -162,7 +172,7 @@ pub fn polygon_circle_collision(
 
     // Penetration
     let point_to_center_dist = (collision_point - circle_center).length();
-    let penetration = circle.radius - point_to_center_dist;
+    let penetration = radius - point_to_center_dist;
 
     Some(BodyCollisionData {
         normal,
@@ -0,0 +1,118 @@
+use std::fmt;
+
+use crate::game::GameConfig;
+use crate::math::{v2, Vector2};
+use crate::physics::units::{from_cm, to_cm};
+
+use super::{BodyBehaviour, RbSimulator, Rectangle, RigidBody, SharedProperty};
+
+/// Size of the dropped box and the height of its bottom above the floor at the start. In cm.
+const BOX_SIZE: f32 = 50.0;
+const DROP_HEIGHT: f32 = 200.0;
+/// Top of the floor in cm.
+const FLOOR_TOP: f32 = 500.0;
+/// The box moving upwards faster than this, in cm/s, after moving downwards counts as a bounce.
+/// Slower changes are just the jitter of a resting box.
+const BOUNCE_MIN_SPEED: f32 = 5.0;
+/// The box is settled once it moves slower than this, in cm/s, for `SETTLE_DURATION` seconds.
+const SETTLE_SPEED: f32 = 1.0;
+const SETTLE_DURATION: f32 = 0.5;
+/// The test gives up after this many seconds.
+const MAX_DURATION: f32 = 20.0;
+
+/// Metrics of a box dropped onto the floor by `RbSimulator::drop_test`.
+#[derive(Debug)]
+pub struct DropTestResult {
+    /// Position of the center of the box at the end of the test in cm. The top of the floor is at
+    /// `y = 500`, so a box that settled correctly ends at `y = 475`.
+    pub rest_position: Vector2<f32>,
+    /// How many times the box changed from falling to moving upwards.
+    pub bounces: u32,
+    /// Time in seconds from the release until the box settled. None if it did not settle.
+    pub settle_time: Option<f32>,
+}
+
+impl fmt::Display for DropTestResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "rest position X: {:.2}, Y: {:.2} [cm], {} bounces, ",
+            self.rest_position.x, self.rest_position.y, self.bounces
+        )?;
+        match self.settle_time {
+            Some(time) => write!(f, "settled after {time:.2} s"),
+            None => write!(f, "did not settle"),
+        }
+    }
+}
+
+impl RbSimulator {
+    /// Drops a box with the given `elasticity` from a height onto the floor with the default
+    /// configuration and measures how it comes to rest. Runs without any rendering, so it can be
+    /// used to validate the solver after tuning it.
+    pub fn drop_test(elasticity: f32) -> DropTestResult {
+        let config = GameConfig::default();
+        let mut simulator = RbSimulator::new(from_cm(config.gravity));
+
+        let floor_center = v2!(0.0, FLOOR_TOP + 10.0);
+        let mut floor = Rectangle!(from_cm(floor_center); from_cm(1000.0), from_cm(20.0); BodyBehaviour::Static);
+        // The floor takes the elasticity of the box
+        floor.state_mut().elasticity = SharedProperty::Pass;
+        let box_center = v2!(0.0, FLOOR_TOP - DROP_HEIGHT - BOX_SIZE * 0.5);
+        let mut dropped = Rectangle!(from_cm(box_center); from_cm(BOX_SIZE), from_cm(BOX_SIZE); BodyBehaviour::Dynamic);
+        dropped.state_mut().elasticity = SharedProperty::Value(elasticity);
+        simulator.bodies = vec![floor, dropped];
+
+        let mut bounces = 0;
+        let mut was_falling = false;
+        let mut time = 0.0;
+        let mut settled_since = None;
+        let mut settle_time = None;
+        while time < MAX_DURATION {
+            simulator.step(&config, config.time_step);
+            time += config.time_step;
+
+            let velocity = to_cm(simulator.bodies[1].state().velocity);
+            if velocity.y > BOUNCE_MIN_SPEED {
+                was_falling = true;
+            } else if velocity.y < -BOUNCE_MIN_SPEED && was_falling {
+                bounces += 1;
+                was_falling = false;
+            }
+
+            if velocity.length() < SETTLE_SPEED {
+                let since = *settled_since.get_or_insert(time);
+                if time - since >= SETTLE_DURATION {
+                    settle_time = Some(since);
+                    break;
+                }
+            } else {
+                settled_since = None;
+            }
+        }
+
+        DropTestResult {
+            rest_position: to_cm(simulator.bodies[1].state().position),
+            bounces,
+            settle_time,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RbSimulator;
+
+    #[test]
+    fn bouncy_box_bounces_more_and_still_settles() {
+        let dull = RbSimulator::drop_test(0.0);
+        let bouncy = RbSimulator::drop_test(0.8);
+
+        assert!(dull.bounces < bouncy.bounces, "{dull:?} {bouncy:?}");
+        for result in [dull, bouncy] {
+            assert!(result.settle_time.is_some(), "{result:?}");
+            // The box rests on the floor
+            assert!((result.rest_position.y - 475.0).abs() < 3.0, "{result:?}");
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use core::f32;
 use serde_derive::{Deserialize, Serialize};
+use std::collections::VecDeque;
 
 use crate::{
     math::{v2, Matrix, Vector2},
@@ -9,12 +10,17 @@ use crate::{
 
 mod circle;
 mod collisions;
+mod drop_test;
 mod polygon;
+pub mod presets;
 mod rb_simulation;
 mod rigidbody;
 
 use num_traits::Zero;
-pub use rb_simulation::{RbSimulator, SharedProperty, SharedPropertySelection};
+pub use rb_simulation::{
+    Anchor, CorrectionMode, DistanceConstraint, RbSimulator, SharedProperty,
+    SharedPropertySelection,
+};
 pub use rigidbody::RigidBody;
 
 // Base values for body state properties
@@ -64,14 +70,24 @@ impl BodyForceAccumulation {
 pub struct BodyState {
     // BASIC VALUES for 2D space
     pub position: Vector2<f32>,
-    /// Linear velocity measured in pixels per second (1 pixel = 1 cm ingame)
+    /// Linear velocity measured in world units per second. See `physics::units` for their size.
     pub velocity: Vector2<f32>,
     /// Angular velocity measured in radians
     pub angular_velocity: f32,
     /// Rotation of the body measured in radians
     pub orientation: f32,
+    /// Position before the last simulated step. Used to interpolate the drawn position.
+    pub previous_position: Vector2<f32>,
+    /// Orientation before the last simulated step.
+    pub previous_orientation: f32,
     /// If true, then this object will not rotate under the offect of forces
     pub lock_rotation: bool,
+    /// If false, then fluid particles pass through this body
+    pub collides_with_fluid: bool,
+    /// If false, then other bodies pass through this body
+    pub collides_with_bodies: bool,
+    /// If true, this body is drawn on top of the fluid even if the fluid is drawn over bodies.
+    pub draw_over_fluid: bool,
 
     // PROPERTIES
     pub behaviour: BodyBehaviour,
@@ -87,6 +103,13 @@ pub struct BodyState {
 
     // OTHER PROPERTIES
     pub color: Color,
+    /// Optional user given label used to organize bodies, eg. "supports" or "projectiles".
+    pub tag: Option<String>,
+    /// Recent positions of the center of mass, oldest first. Used only for visualization.
+    pub trail: VecDeque<Vector2<f32>>,
+    /// How strongly the body flashes after a hard impact, from 0 to 1. Used only for
+    /// visualization.
+    pub impact_flash: f32,
 
     // ACCUMULATED FORCES waiting to be applied
     pub(crate) accumulated_force: Vector2<f32>,
@@ -100,7 +123,12 @@ impl BodyState {
             velocity: Vector2::zero(),
             angular_velocity: 0.0,
             orientation: 0.0,
+            previous_position: position,
+            previous_orientation: 0.0,
             lock_rotation: false,
+            collides_with_fluid: true,
+            collides_with_bodies: true,
+            draw_over_fluid: false,
 
             behaviour,
             mass,
@@ -111,6 +139,9 @@ impl BodyState {
             static_friction: SharedProperty::Value(DEFAULT_STATIC_FRICTION),
             dynamic_friction: SharedProperty::Value(DEFAULT_DYNAMIC_FRICTION),
             color: Color::rgb(0, 0, 0),
+            tag: None,
+            trail: VecDeque::new(),
+            impact_flash: 0.0,
 
             accumulated_force: Vector2::zero(),
             accumulated_torque: 0.0,
@@ -165,6 +196,16 @@ impl BodyState {
         }
     }
 
+    /// Translational plus rotational kinetic energy. Static bodies have none.
+    pub fn kinetic_energy(&self) -> f32 {
+        if self.behaviour == BodyBehaviour::Static {
+            return 0.0;
+        }
+
+        0.5 * self.mass * self.velocity.length_squared()
+            + 0.5 * self.moment_of_inertia * self.angular_velocity * self.angular_velocity
+    }
+
     pub fn move_by_velocity(&mut self, time_step: f32) {
         self.position = runge_kutta(self.position, time_step, self.velocity);
 
@@ -197,6 +238,14 @@ impl PointsProjection {
         self.max = self.max.max(value);
     }
 
+    /// Returns this projection extended by `offset` on both sides.
+    pub fn inflated(&self, offset: f32) -> PointsProjection {
+        PointsProjection {
+            min: self.min - offset,
+            max: self.max + offset,
+        }
+    }
+
     /// Returns the size of the overlap between these 2 projections or None if the do not overlap.
     pub fn get_overlap(&self, other: &PointsProjection) -> Option<f32> {
         if self.max > other.min && other.max > self.min {
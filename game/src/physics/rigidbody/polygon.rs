@@ -6,6 +6,7 @@ use crate::shapes::{triangulate_convex_polygon, Line, Triangulation};
 
 use super::{local_point_to_global, BodyState, PointsProjection};
 
+#[derive(Clone)]
 pub struct PolygonInner {
     pub(super) state: BodyState,
     /// These points are the vertices of the polygon - relative to it's position
@@ -45,6 +46,10 @@ impl PolygonInner {
         &self.global_triangulation
     }
 
+    pub fn global_lines(&self) -> &Vec<Line> {
+        &self.global_lines
+    }
+
     /// Returns a normal vector of the provided line that is pointing away from the center of this
     /// polygon.
     pub(super) fn lines_normal_pointing_outside(&self, line: &Line) -> Vector2<f32> {
@@ -71,17 +76,44 @@ impl PolygonInner {
             / self.global_points.len() as f32
     }
 
-    pub(super) fn project_onto_axis(&self, axis: Vector2<f32>) -> PointsProjection {
+    /// Center of the area of the polygon in local space. Unlike `center_of_mass` it is not moved
+    /// towards the side with more vertices. Falls back to the average of the vertices if the
+    /// polygon has no area.
+    pub(super) fn local_centroid(&self) -> Vector2<f32> {
+        let points = &self.points;
+        let (twice_area, weighted_sum) =
+            (0..points.len()).fold((0.0, Vector2::zero()), |(twice_area, weighted_sum), i| {
+                let (this, next) = (points[i], points[(i + 1) % points.len()]);
+                let cross = this.cross(next);
+                (twice_area + cross, weighted_sum + (this + next) * cross)
+            });
+
+        if twice_area == 0.0 {
+            points.iter().fold(Vector2::zero(), |acc, x| acc + *x) / points.len() as f32
+        } else {
+            weighted_sum / (3.0 * twice_area)
+        }
+    }
+
+    /// Projects the polygon onto `axis`. The projection is extended by `contact_offset` on both
+    /// sides.
+    pub(super) fn project_onto_axis(
+        &self,
+        axis: Vector2<f32>,
+        contact_offset: f32,
+    ) -> PointsProjection {
         let mut proj = PointsProjection::default();
         for point in &self.global_points {
             let dist = point.dot(axis);
             proj.add(dist);
         }
 
-        proj
+        proj.inflated(contact_offset)
     }
 
-    pub(super) fn projection_axes(&self) -> LinkedList<Vector2<f32>> {
+    /// Normals of the lines of the polygon pointing outside. These are the axes the polygon is
+    /// projected onto when checking for collisions.
+    pub fn projection_axes(&self) -> LinkedList<Vector2<f32>> {
         self.global_lines
             .iter()
             .map(|line| self.lines_normal_pointing_outside(line))
@@ -0,0 +1,148 @@
+//! Ready-made machines composed of bodies and constraints.
+
+use crate::math::{v2, Vector2};
+use crate::physics::units::from_cm;
+use crate::rendering::Color;
+
+use super::{Anchor, BodyBehaviour, DistanceConstraint, RbSimulator, Rectangle, RigidBody};
+
+// Dimensions of the catapult in cm
+const ARM_LENGTH: f32 = 300.0;
+const ARM_THICKNESS: f32 = 10.0;
+/// How much is the arm tilted towards the projectile before the release. In radians.
+const ARM_TILT: f32 = 0.2;
+const PROJECTILE_SIZE: f32 = 20.0;
+const COUNTERWEIGHT_SIZE: f32 = 60.0;
+/// Height of the bottom of the counterweight above the raised end of the arm.
+const COUNTERWEIGHT_DROP: f32 = 150.0;
+const STOP_SIZE: f32 = 30.0;
+/// Relative distance of the projectile and the counterweight from the pivot along the arm.
+const LOAD_POSITION: f32 = 0.8;
+// Densities in g/cm^2
+const ARM_DENSITY: f32 = 1.0;
+const PROJECTILE_DENSITY: f32 = 1.0;
+const COUNTERWEIGHT_DENSITY: f32 = 20.0;
+
+const WOOD_COLOR: Color = Color::rgb(140, 90, 40);
+const PROJECTILE_COLOR: Color = Color::rgb(200, 40, 40);
+const COUNTERWEIGHT_COLOR: Color = Color::rgb(80, 80, 80);
+
+/// Indexes of the parts of a catapult spawned by `catapult`. They are valid only until bodies
+/// before them are removed from the simulator.
+pub struct Catapult {
+    pub projectile: usize,
+    pub counterweight: usize,
+}
+
+impl Catapult {
+    /// Lets the counterweight fall onto the raised end of the arm, which throws the projectile.
+    pub fn release(&self, simulator: &mut RbSimulator) {
+        let counterweight = self.counterweight;
+        simulator
+            .constraints
+            .retain(|c| !(c.index == counterweight && matches!(c.anchor, Anchor::World(_))));
+    }
+}
+
+/// Spawns a counterweight catapult with the pivot of its arm at `pivot`. The arm rotates freely
+/// around its center, which is pinned to the world by a distance constraint of zero length, and
+/// rests with its lowered end on a stop. The projectile lies on the lowered end and the
+/// counterweight is held above the raised end until `Catapult::release` is called.
+pub fn catapult(simulator: &mut RbSimulator, pivot: Vector2<f32>) -> Catapult {
+    let half_length = from_cm(ARM_LENGTH * 0.5);
+    let half_thickness = from_cm(ARM_THICKNESS * 0.5);
+    // Points from the pivot towards the raised end, `up` is perpendicular to it
+    let along = v2!(ARM_TILT.cos(), -ARM_TILT.sin());
+    let up = v2!(along.y, -along.x);
+
+    let mut add_body = |mut body: RigidBody, density: f32, color: Color| {
+        body.set_density(density);
+        body.state_mut().color = color;
+        simulator.bodies.push(body);
+        simulator.bodies.len() - 1
+    };
+    // A box lying on the arm at `distance` from the pivot
+    let on_arm = |distance: f32, size: f32| {
+        let half = from_cm(size * 0.5);
+        let center = pivot + along * distance + up * (half_thickness + half);
+        let (along, up) = (along * half, up * half);
+        Rectangle!(center - along - up, center + along - up, center + along + up, center - along + up; BodyBehaviour::Dynamic)
+    };
+
+    let (along_arm, up_arm) = (along * half_length, up * half_thickness);
+    let arm = Rectangle!(
+        pivot - along_arm - up_arm,
+        pivot + along_arm - up_arm,
+        pivot + along_arm + up_arm,
+        pivot - along_arm + up_arm;
+        BodyBehaviour::Dynamic
+    );
+    let arm = add_body(arm, ARM_DENSITY, WOOD_COLOR);
+
+    let lowest_point = pivot - along_arm - up_arm;
+    let stop_half = from_cm(STOP_SIZE * 0.5);
+    let stop_center = v2!(lowest_point.x + stop_half, lowest_point.y + stop_half);
+    let stop = Rectangle!(stop_center; stop_half * 2.0, stop_half * 2.0; BodyBehaviour::Static);
+    add_body(stop, ARM_DENSITY, WOOD_COLOR);
+
+    let projectile = on_arm(-half_length * LOAD_POSITION, PROJECTILE_SIZE);
+    let projectile = add_body(projectile, PROJECTILE_DENSITY, PROJECTILE_COLOR);
+
+    let weight_half = from_cm(COUNTERWEIGHT_SIZE * 0.5);
+    let weight_center =
+        pivot + along * (half_length * LOAD_POSITION) - v2!(0.0, from_cm(COUNTERWEIGHT_DROP));
+    let weight_center = weight_center - v2!(0.0, weight_half);
+    let counterweight =
+        Rectangle!(weight_center; weight_half * 2.0, weight_half * 2.0; BodyBehaviour::Dynamic);
+    let counterweight = add_body(counterweight, COUNTERWEIGHT_DENSITY, COUNTERWEIGHT_COLOR);
+
+    simulator.constraints.push(DistanceConstraint {
+        index: arm,
+        anchor: Anchor::World(pivot),
+        length: 0.0,
+    });
+    // The trigger
+    simulator.constraints.push(DistanceConstraint {
+        index: counterweight,
+        anchor: Anchor::World(weight_center),
+        length: 0.0,
+    });
+
+    Catapult {
+        projectile,
+        counterweight,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::catapult;
+    use crate::game::GameConfig;
+    use crate::math::{v2, Vector2};
+    use crate::physics::rigidbody::RbSimulator;
+    use crate::physics::units::from_cm;
+
+    #[test]
+    fn catapult_throws_only_after_release() {
+        let config = GameConfig::default();
+        let mut simulator = RbSimulator::new(from_cm(config.gravity));
+        let catapult = catapult(&mut simulator, v2!(500.0, 500.0));
+        let height =
+            |simulator: &RbSimulator| simulator.bodies[catapult.projectile].state().position.y;
+
+        // The loaded catapult stays still
+        let start = height(&simulator);
+        for _ in 0..60 {
+            simulator.step(&config, config.time_step);
+        }
+        assert!((height(&simulator) - start).abs() < 5.0);
+
+        catapult.release(&mut simulator);
+        let mut highest = height(&simulator);
+        for _ in 0..120 {
+            simulator.step(&config, config.time_step);
+            highest = highest.min(height(&simulator));
+        }
+        assert!(highest < start - 100.0, "{start} {highest}");
+    }
+}
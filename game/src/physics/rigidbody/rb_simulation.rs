@@ -1,14 +1,21 @@
 use core::f32;
 use std::{
-    collections::LinkedList,
+    collections::{HashSet, LinkedList},
     ops::{Add, Mul},
 };
 
-use rayon::iter::{IntoParallelRefMutIterator, ParallelIterator};
+use rayon::iter::{
+    IndexedParallelIterator, IntoParallelRefIterator, IntoParallelRefMutIterator, ParallelIterator,
+};
 use serde_derive::{Deserialize, Serialize};
 
 use super::{BodyBehaviour, BodyCollisionData, RigidBody};
-use crate::{game::GameConfig, math::Vector2};
+use crate::{
+    game::GameConfig,
+    math::{Matrix, Vector2},
+    physics::units::from_cm,
+    utility::UnionFind,
+};
 
 /// Holds `BodyCollisionData` along with indexes of what two bodies collided.
 #[derive(Clone)]
@@ -18,6 +25,48 @@ struct BodyBodyCollision {
     collision_data: BodyCollisionData,
 }
 
+/// An invisible constraint that keeps the centers of dynamic bodies on one side of a line.
+/// The allowed side is the one into which `normal` points.
+#[derive(Clone, Copy)]
+pub struct HalfPlane {
+    /// Any point on the boundary line.
+    pub point: Vector2<f32>,
+    /// Unit normal of the boundary line pointing into the allowed side.
+    pub normal: Vector2<f32>,
+}
+
+impl HalfPlane {
+    pub fn new(point: Vector2<f32>, normal: Vector2<f32>) -> Self {
+        HalfPlane {
+            point,
+            normal: normal.normalized(),
+        }
+    }
+
+    /// Signed distance of `position` from the boundary line. Negative values are on the forbidden
+    /// side.
+    pub fn signed_distance(&self, position: Vector2<f32>) -> f32 {
+        (position - self.point).dot(self.normal)
+    }
+}
+
+/// What the second end of a `DistanceConstraint` is attached to.
+#[derive(Clone, Copy)]
+pub enum Anchor {
+    /// The center of the body with this index.
+    Body(usize),
+    /// A fixed point in the world.
+    World(Vector2<f32>),
+}
+
+/// Keeps the center of a body at a fixed distance from its anchor, like a massless rod.
+#[derive(Clone, Copy)]
+pub struct DistanceConstraint {
+    pub index: usize,
+    pub anchor: Anchor,
+    pub length: f32,
+}
+
 #[derive(Clone, Copy, Serialize, Deserialize)]
 pub enum SharedProperty<T>
 where
@@ -95,30 +144,131 @@ impl SharedPropertySelection {
     }
 }
 
+/// How is the penetration of colliding bodies removed.
+#[derive(Clone, Copy, PartialEq)]
+pub enum CorrectionMode {
+    /// Adds a bias to the velocity of the bodies that pushes them apart. Softer, but can add
+    /// energy to the bodies.
+    Baumgarte,
+    /// Moves the bodies apart directly after they are moved by their velocity.
+    Positional,
+    /// Half of each.
+    Blend,
+}
+
+impl CorrectionMode {
+    /// Returns the multipliers of the correction done by the velocity bias and by moving the
+    /// bodies.
+    fn weights(&self) -> (f32, f32) {
+        match self {
+            Self::Baumgarte => (1.0, 0.0),
+            Self::Positional => (0.0, 1.0),
+            Self::Blend => (0.5, 0.5),
+        }
+    }
+}
+
 pub struct RbSimulator {
     pub bodies: Vec<RigidBody>,
+    /// Constraints that dynamic bodies can not cross.
+    pub half_planes: Vec<HalfPlane>,
+    /// Rods between bodies or between a body and the world. Bodies connected by a constraint do
+    /// not collide with each other.
+    pub constraints: Vec<DistanceConstraint>,
+    /// Total magnitude of normal impulses each body received from contacts during the last step.
+    /// Indexed the same as `bodies`.
+    pub contact_impulses: Vec<f32>,
+    /// Number of contact points found during the last step.
+    pub contact_count: usize,
+    /// Number of contact points of each body during the last step. Indexed the same as `bodies`.
+    body_contact_counts: Vec<usize>,
+    /// For how many steps in a row was each body supported against gravity by a contact. Indexed
+    /// the same as `bodies`.
+    supported_steps: Vec<u32>,
+    /// Indexes of the pairs of bodies that collided during the last step.
+    contact_pairs: Vec<(usize, usize)>,
+    /// Number of groups of dynamic bodies connected by contacts during the last step. Static
+    /// bodies do not connect groups.
+    pub island_count: usize,
+    /// The deepest penetration between two bodies found at the start of the last step.
+    pub max_penetration_before: f32,
+    /// The deepest penetration left at the end of the last step. Measured only with
+    /// `measure_penetration_after`, because it needs another collision check.
+    pub max_penetration_after: f32,
+    pub measure_penetration_after: bool,
 
     pub gravity: Vector2<f32>,
+    pub gravity_enabled: bool,
     pub elasticity_selection: SharedPropertySelection,
     pub friction_selection: SharedPropertySelection,
 
     pub current_time_step: f32,
-    pub iterations: u32,
+    /// How many times are the collision impulses resolved in a step.
+    pub velocity_iterations: u32,
+    /// How many times are penetrating bodies pushed apart in a step.
+    pub position_iterations: u32,
+    pub correction_mode: CorrectionMode,
+    /// Bodies moving further than this in a step are moved in parts with collision checks in
+    /// between. Zero disables it.
+    pub max_move_step: f32,
+    /// Bodies are treated as if they were larger by this in every direction when checking for
+    /// collisions.
+    pub contact_offset: f32,
+    /// Penetrations deeper than this are removed completely by moving the bodies instead of by the
+    /// correction mode. Zero disables it.
+    pub snap_penetration: f32,
+    /// If true, the order in which collisions are resolved is shuffled every step.
+    pub randomize_contact_order: bool,
+    /// Contacts approaching slower than this are considered resting and do not bounce. Otherwise
+    /// the restitution of gravity pulling the body down every step makes resting bodies jitter.
+    pub restitution_threshold: f32,
+    /// If true, the contact points of each collision are averaged into one.
+    pub single_contact_point: bool,
 }
 
 impl RbSimulator {
     const CORRECTION_FACTOR: f32 = 0.2;
     const SLOP: f32 = 1.0;
+    /// How many times are the distance constraints solved in a step.
+    const CONSTRAINT_ITERATIONS: u32 = 10;
+    /// A contact supports a body if the direction towards the other body is at most this far, as a
+    /// cosine of the angle, from the direction of gravity. About 45 degrees.
+    const RESTING_COS: f32 = 0.7;
+    /// A body is resting if it was supported for at least this many steps in a row, so a single
+    /// touch while bouncing does not count.
+    const RESTING_STEPS: u32 = 2;
+    /// Upper limit of parts into which the movement of a fast body is split.
+    const MAX_MOVE_PARTS: u32 = 32;
 
     pub fn new(gravity: Vector2<f32>) -> Self {
         RbSimulator {
             bodies: Vec::new(),
+            half_planes: Vec::new(),
+            constraints: Vec::new(),
+            contact_impulses: Vec::new(),
+            contact_count: 0,
+            body_contact_counts: Vec::new(),
+            supported_steps: Vec::new(),
+            contact_pairs: Vec::new(),
+            island_count: 0,
+            max_penetration_before: 0.0,
+            max_penetration_after: 0.0,
+            measure_penetration_after: false,
             gravity,
+            gravity_enabled: true,
             elasticity_selection: SharedPropertySelection::Average,
             friction_selection: SharedPropertySelection::Average,
 
             current_time_step: 0.0,
-            iterations: 5,
+            velocity_iterations: 5,
+            position_iterations: 2,
+            correction_mode: CorrectionMode::Positional,
+            max_move_step: 0.0,
+            contact_offset: 0.0,
+            snap_penetration: 0.0,
+            randomize_contact_order: false,
+            restitution_threshold: 0.0,
+            single_contact_point: false,
         }
     }
 
@@ -126,22 +276,327 @@ impl RbSimulator {
         // Set time step
         self.current_time_step = dt;
         // Set values from config
-        self.gravity = config.gravity;
+        self.gravity = from_cm(config.gravity);
+        self.gravity_enabled = config.gravity_enabled;
         self.elasticity_selection = *config.rb_config.elasticity_selection.get_value();
         self.friction_selection = *config.rb_config.friction_selection.get_value();
-        self.iterations = config.rb_config.iterations.min(1);
+        self.velocity_iterations = config.rb_config.velocity_iterations.max(1);
+        self.position_iterations = config.rb_config.position_iterations;
+        self.correction_mode = *config.rb_config.correction_mode.get_value();
+        self.max_move_step = from_cm(config.rb_config.max_move_step);
+        self.contact_offset = from_cm(config.rb_config.contact_offset);
+        self.snap_penetration = from_cm(config.rb_config.snap_penetration);
+        self.randomize_contact_order = config.rb_config.randomize_contact_order;
+        self.restitution_threshold = from_cm(config.rb_config.restitution_velocity_threshold);
+        self.single_contact_point = config.rb_config.single_contact_point;
 
         // Apply gravity force
         self.apply_gravity(config.time_step);
 
-        let collisions = self.check_collisions();
+        self.contact_impulses.clear();
+        self.contact_impulses.resize(self.bodies.len(), 0.0);
+
+        let mut collisions = self.check_collisions();
+        // The position iterations estimate the remaining penetration of each collision from how
+        // far its bodies moved since it was detected
+        let detected_positions: Vec<Vector2<f32>> = self
+            .bodies
+            .iter()
+            .map(|body| body.state().position)
+            .collect();
+        self.count_contacts_and_islands(&collisions);
+        self.max_penetration_before = Self::max_penetration(&collisions);
+        // Resolving in the same order every step biases the result towards one direction
+        if self.randomize_contact_order {
+            fastrand::shuffle(&mut collisions);
+        }
         // Iteratively resolve collisions
-        for _ in 0..self.iterations {
+        for _ in 0..self.velocity_iterations {
             self.resolve_collisions(&collisions);
         }
 
         self.move_bodies_by_velocity(config.time_step);
+        self.apply_half_planes();
+        for _ in 0..Self::CONSTRAINT_ITERATIONS {
+            self.solve_constraints();
+        }
         self.update_inner_values();
+
+        // Iteratively remove what is left of the penetrations. Deep penetrations are snapped apart
+        // even if the correction mode does not move the bodies.
+        let (_, position_weight) = self.correction_mode.weights();
+        if position_weight > 0.0 {
+            for _ in 0..self.position_iterations {
+                self.correct_positions(&collisions, &detected_positions, position_weight);
+            }
+        } else if self.snap_penetration > 0.0 {
+            self.correct_positions(&collisions, &detected_positions, 0.0);
+        }
+
+        if self.measure_penetration_after {
+            self.max_penetration_after = Self::max_penetration(&self.check_collisions());
+        }
+    }
+
+    fn max_penetration(collisions: &[BodyBodyCollision]) -> f32 {
+        collisions
+            .iter()
+            .map(|coll| coll.collision_data.penetration)
+            .fold(0.0, f32::max)
+    }
+
+    /// Remembers the current transform of each body as the previous one for render interpolation.
+    pub fn store_previous_transforms(&mut self) {
+        for body in &mut self.bodies {
+            let state = body.state_mut();
+            state.previous_position = state.position;
+            state.previous_orientation = state.orientation;
+        }
+    }
+
+    /// Sum of kinetic energies of all bodies.
+    pub fn kinetic_energy(&self) -> f32 {
+        self.bodies
+            .iter()
+            .map(|body| body.state().kinetic_energy())
+            .sum()
+    }
+
+    /// Sum of `mass * velocity` of all dynamic bodies.
+    pub fn total_momentum(&self) -> Vector2<f32> {
+        self.bodies
+            .iter()
+            .filter(|body| body.state().behaviour == BodyBehaviour::Dynamic)
+            .map(|body| body.state().velocity * body.state().mass())
+            .sum()
+    }
+
+    /// Adds a constraint so that dynamic bodies can not cross the line going through `point`.
+    /// Bodies are kept on the side into which `normal` points.
+    pub fn add_half_plane(&mut self, point: Vector2<f32>, normal: Vector2<f32>) {
+        self.half_planes.push(HalfPlane::new(point, normal));
+    }
+
+    /// Removes the body at `index` by swapping it with the last one, like `Vec::swap_remove`.
+    /// Constraints of the removed body are removed too and those of the swapped body follow it.
+    pub fn remove_body(&mut self, index: usize) -> RigidBody {
+        let last = self.bodies.len() - 1;
+        let body = self.bodies.swap_remove(index);
+        // Bodies added since the last step were not supported yet
+        self.supported_steps.resize(last + 1, 0);
+        self.supported_steps.swap_remove(index);
+
+        self.constraints.retain(|c| {
+            c.index != index && !matches!(c.anchor, Anchor::Body(other) if other == index)
+        });
+        for constraint in &mut self.constraints {
+            if constraint.index == last {
+                constraint.index = index;
+            }
+            if let Anchor::Body(other) = &mut constraint.anchor {
+                if *other == last {
+                    *other = index;
+                }
+            }
+        }
+
+        body
+    }
+
+    /// Removes the constraints attached to bodies that do not exist, eg. after the bodies were
+    /// replaced.
+    pub fn remove_invalid_constraints(&mut self) {
+        let body_count = self.bodies.len();
+        self.constraints.retain(|c| {
+            c.index < body_count && !matches!(c.anchor, Anchor::Body(other) if other >= body_count)
+        });
+    }
+
+    /// Moves the bodies of each distance constraint along it so that it has its length again.
+    /// Heavier bodies are moved less. The velocity along the constraint is removed in the same
+    /// way, so the constraint does not stretch again in the next step. Constraints of bodies that
+    /// do not exist are skipped.
+    fn solve_constraints(&mut self) {
+        let body_count = self.bodies.len();
+        let is_valid = |index: usize| index < body_count;
+        let inverse_mass = |body: &RigidBody| match body.state().behaviour {
+            BodyBehaviour::Dynamic => inverse_value(body.state().mass()),
+            BodyBehaviour::Static => 0.0,
+        };
+
+        for constraint in &self.constraints {
+            let DistanceConstraint {
+                index,
+                anchor,
+                length,
+            } = *constraint;
+            if !is_valid(index) {
+                continue;
+            }
+            let (anchor_position, anchor_velocity, inv_mass_b) = match anchor {
+                Anchor::Body(other) if is_valid(other) => {
+                    let state = self.bodies[other].state();
+                    (
+                        state.position,
+                        state.velocity,
+                        inverse_mass(&self.bodies[other]),
+                    )
+                }
+                Anchor::Body(_) => continue,
+                Anchor::World(point) => (point, Vector2::zero(), 0.0),
+            };
+            let inv_mass_a = inverse_mass(&self.bodies[index]);
+            let inv_masses = inv_mass_a + inv_mass_b;
+            let state = self.bodies[index].state();
+            let diff = anchor_position - state.position;
+            let distance = diff.length();
+            if inv_masses == 0.0 || distance == 0.0 {
+                continue;
+            }
+
+            let direction = diff / distance;
+            let position_error = direction * (distance - length);
+            let velocity_error = direction * (anchor_velocity - state.velocity).dot(direction);
+            let mut apply = |index: usize, weight: f32| {
+                let state = self.bodies[index].state_mut();
+                state.position += position_error * weight;
+                state.velocity += velocity_error * weight;
+            };
+            apply(index, inv_mass_a / inv_masses);
+            if let Anchor::Body(other) = anchor {
+                apply(other, -inv_mass_b / inv_masses);
+            }
+        }
+    }
+
+    /// Moves colliding bodies apart along the collision normal to remove a part of their
+    /// penetration. Heavier bodies are moved less. `weight` scales the removed part. Snapped
+    /// penetrations are removed whole. The collisions are not detected again, their penetration is
+    /// reduced by how far the bodies moved apart since `detected_positions`.
+    fn correct_positions(
+        &mut self,
+        collisions: &[BodyBodyCollision],
+        detected_positions: &[Vector2<f32>],
+        weight: f32,
+    ) {
+        for coll in collisions {
+            let (index_a, index_b) = (coll.index_a, coll.index_b);
+            let collision_data = &coll.collision_data;
+
+            let inv_mass_a = inverse_value(self.bodies[index_a].state().mass());
+            let inv_mass_b = inverse_value(self.bodies[index_b].state().mass());
+            let inv_masses = inv_mass_a + inv_mass_b;
+            // Moving B along the normal, which points from A towards B, separates the bodies
+            let moved =
+                |index: usize| self.bodies[index].state().position - detected_positions[index];
+            let separation = (moved(index_b) - moved(index_a)).dot(collision_data.normal);
+            let remaining = collision_data.penetration - separation;
+            let penetration = (remaining - Self::SLOP).max(0.0);
+            let fraction = if is_snapped(remaining, self.snap_penetration) {
+                1.0
+            } else {
+                weight * Self::CORRECTION_FACTOR
+            };
+            if inv_masses == 0.0 || penetration == 0.0 || fraction == 0.0 {
+                continue;
+            }
+
+            // The normal points from A towards B
+            let correction = collision_data.normal * (fraction * penetration / inv_masses);
+            for (index, offset) in [
+                (index_a, correction * -inv_mass_a),
+                (index_b, correction * inv_mass_b),
+            ] {
+                if offset.is_zero() {
+                    continue;
+                }
+                let position = self.bodies[index].state().position + offset;
+                self.bodies[index].set_position(position);
+            }
+        }
+    }
+
+    /// Sets `contact_count` and `island_count` and the contacts of each body from the collisions of
+    /// this step.
+    fn count_contacts_and_islands(&mut self, collisions: &[BodyBodyCollision]) {
+        self.contact_count = collisions
+            .iter()
+            .map(|coll| coll.collision_data.collision_points.len())
+            .sum();
+
+        self.body_contact_counts.clear();
+        self.body_contact_counts.resize(self.bodies.len(), 0);
+        let mut supported = vec![false; self.bodies.len()];
+        self.contact_pairs = collisions
+            .iter()
+            .map(|coll| (coll.index_a, coll.index_b))
+            .collect();
+        let gravity_direction = if self.gravity_enabled && !self.gravity.is_zero() {
+            self.gravity.normalized()
+        } else {
+            Vector2::zero()
+        };
+        for coll in collisions {
+            let data = &coll.collision_data;
+            self.body_contact_counts[coll.index_a] += data.collision_points.len();
+            self.body_contact_counts[coll.index_b] += data.collision_points.len();
+            // The normal points from A towards B
+            let alignment = data.normal.dot(gravity_direction);
+            if alignment >= Self::RESTING_COS {
+                supported[coll.index_a] = true;
+            } else if alignment <= -Self::RESTING_COS {
+                supported[coll.index_b] = true;
+            }
+        }
+        self.supported_steps.resize(self.bodies.len(), 0);
+        for (steps, supported) in self.supported_steps.iter_mut().zip(supported) {
+            *steps = if supported { *steps + 1 } else { 0 };
+        }
+
+        let is_dynamic =
+            |index: usize| self.bodies[index].state().behaviour == BodyBehaviour::Dynamic;
+        let mut islands = UnionFind::new(self.bodies.len());
+        for coll in collisions {
+            if is_dynamic(coll.index_a) && is_dynamic(coll.index_b) {
+                islands.union(coll.index_a, coll.index_b);
+            }
+        }
+
+        // Every static body is a set on its own, those are not islands
+        let static_count = (0..self.bodies.len()).filter(|i| !is_dynamic(*i)).count();
+        self.island_count = islands.set_count() - static_count;
+    }
+
+    /// Returns the number of contact points the body at `index` had during the last step.
+    pub fn body_contact_count(&self, index: usize) -> usize {
+        self.body_contact_counts.get(index).copied().unwrap_or(0)
+    }
+
+    /// Returns the indexes of the bodies the body at `index` touched during the last step, sorted
+    /// from the lowest.
+    pub fn contacts_of(&self, index: usize) -> Vec<usize> {
+        let mut contacts: Vec<usize> = self
+            .contact_pairs
+            .iter()
+            .filter_map(|&(a, b)| {
+                if a == index {
+                    Some(b)
+                } else if b == index {
+                    Some(a)
+                } else {
+                    None
+                }
+            })
+            .collect();
+        contacts.sort_unstable();
+        contacts
+    }
+
+    /// Returns true if the body at `index` is standing on something, that is it touched another
+    /// body in the direction of gravity during each of the last `RESTING_STEPS` steps. Never true
+    /// without gravity.
+    pub fn is_resting(&self, index: usize) -> bool {
+        self.supported_steps.get(index).copied().unwrap_or(0) >= Self::RESTING_STEPS
     }
 
     /// Update the inner stored values of each body, such as global vertices or lines.
@@ -151,46 +606,154 @@ impl RbSimulator {
             .for_each(|body| body.update_inner_values());
     }
 
-    /// Applies gravity force to bodies with behaviour set to `BodyBehaviour::Dynamic`.
+    /// Applies gravity force to bodies with behaviour set to `BodyBehaviour::Dynamic`. The gravity
+    /// force is skipped if `gravity_enabled` is false, but the other accumulated forces are still applied.
     fn apply_gravity(&mut self, time_step: f32) {
         self.bodies
             .par_iter_mut()
             .filter(|body| body.state().behaviour == BodyBehaviour::Dynamic)
             .for_each(|body| {
                 let state = body.state_mut();
-                state.add_force(self.gravity * state.mass);
+                if self.gravity_enabled {
+                    state.add_force(self.gravity * state.mass);
+                }
 
                 state.apply_accumulated_forces(time_step);
             });
     }
 
+    /// Projects centers of dynamic bodies that ended up on the wrong side of any `HalfPlane` back
+    /// onto its boundary and removes their velocity in the direction of the forbidden side.
+    fn apply_half_planes(&mut self) {
+        if self.half_planes.is_empty() {
+            return;
+        }
+
+        let half_planes = &self.half_planes;
+        self.bodies
+            .par_iter_mut()
+            .filter(|body| body.state().behaviour == BodyBehaviour::Dynamic)
+            .for_each(|body| {
+                let state = body.state_mut();
+                for plane in half_planes {
+                    let distance = plane.signed_distance(state.position);
+                    if distance < 0.0 {
+                        state.position -= plane.normal * distance;
+
+                        let normal_velocity = state.velocity.dot(plane.normal);
+                        if normal_velocity < 0.0 {
+                            state.velocity -= plane.normal * normal_velocity;
+                        }
+                    }
+                }
+            });
+    }
+
     fn move_bodies_by_velocity(&mut self, time_step: f32) {
+        let move_parts: Vec<u32> = self
+            .bodies
+            .iter()
+            .map(|body| self.move_parts(body, time_step))
+            .collect();
+
         self.bodies
             .par_iter_mut()
-            .for_each(|body| body.state_mut().move_by_velocity(time_step));
+            .zip(move_parts.par_iter())
+            .filter(|(_, parts)| **parts == 1)
+            .for_each(|(body, _)| body.state_mut().move_by_velocity(time_step));
+
+        for (index, parts) in move_parts.into_iter().enumerate() {
+            if parts > 1 {
+                self.move_body_in_parts(index, parts, time_step);
+            }
+        }
+    }
+
+    /// Into how many parts is the movement of the body split so that no part is longer than
+    /// `max_move_step`.
+    fn move_parts(&self, body: &RigidBody, time_step: f32) -> u32 {
+        if self.max_move_step <= 0.0 || body.state().behaviour != BodyBehaviour::Dynamic {
+            return 1;
+        }
+
+        let distance = body.state().velocity.length() * time_step;
+        ((distance / self.max_move_step).ceil() as u32).clamp(1, Self::MAX_MOVE_PARTS)
+    }
+
+    /// Moves the body at `index` in `parts` smaller moves. The movement stops at the first part
+    /// after which the body touches a body it was not touching before. The collision is then
+    /// resolved in the next step instead of the body passing through.
+    fn move_body_in_parts(&mut self, index: usize, parts: u32, time_step: f32) {
+        let collides_with = |body: &RigidBody, other_index: usize| {
+            other_index != index
+                && RigidBody::check_collision(body, &self.bodies[other_index], self.contact_offset)
+                    .is_some()
+        };
+
+        let mut body = self.bodies[index].clone();
+        let touching: Vec<bool> = (0..self.bodies.len())
+            .map(|other_index| collides_with(&body, other_index))
+            .collect();
+
+        let part_time_step = time_step / parts as f32;
+        for _ in 0..parts {
+            body.state_mut().move_by_velocity(part_time_step);
+            body.update_inner_values();
+
+            let hit_new_body = (0..self.bodies.len())
+                .any(|other_index| !touching[other_index] && collides_with(&body, other_index));
+            if hit_new_body {
+                break;
+            }
+        }
+
+        self.bodies[index] = body;
     }
 
-    /// Checks for possible collisions and returns a `LinkedList` of `BodyBodyCollision` where each
+    /// Checks for possible collisions and returns a `Vec` of `BodyBodyCollision` where each
     /// record represents a collison between 2 bodies.
-    fn check_collisions(&self) -> LinkedList<BodyBodyCollision> {
+    fn check_collisions(&self) -> Vec<BodyBodyCollision> {
+        // Pairs connected by a constraint, the larger index first
+        let connected: HashSet<(usize, usize)> = self
+            .constraints
+            .iter()
+            .filter_map(|c| match c.anchor {
+                Anchor::Body(other) => Some((c.index.max(other), c.index.min(other))),
+                Anchor::World(_) => None,
+            })
+            .collect();
+
         let mut index_pairs = LinkedList::new();
         for i in 1..self.bodies.len() {
             for j in 0..i {
-                index_pairs.push_back((i, j));
+                if !connected.contains(&(i, j)) {
+                    index_pairs.push_back((i, j));
+                }
             }
         }
 
         index_pairs
             .into_iter()
             .filter_map(|(index_a, index_b)| {
-                // Skip over pairs where both bodies are `Static`
-                if self.bodies[index_a].state().behaviour == BodyBehaviour::Static
-                    && self.bodies[index_b].state().behaviour == BodyBehaviour::Static
-                {
+                let (state_a, state_b) =
+                    (self.bodies[index_a].state(), self.bodies[index_b].state());
+                // Skip over pairs where both bodies are `Static` or one ignores the other bodies
+                let both_static = state_a.behaviour == BodyBehaviour::Static
+                    && state_b.behaviour == BodyBehaviour::Static;
+                if both_static || !state_a.collides_with_bodies || !state_b.collides_with_bodies {
                     None
-                } else if let Some(collision_data) =
-                    RigidBody::check_collision(&self.bodies[index_a], &self.bodies[index_b])
-                {
+                } else if let Some(mut collision_data) = RigidBody::check_collision(
+                    &self.bodies[index_a],
+                    &self.bodies[index_b],
+                    self.contact_offset,
+                ) {
+                    if self.single_contact_point && collision_data.collision_points.len() > 1 {
+                        let points = &collision_data.collision_points;
+                        let average =
+                            points.iter().copied().sum::<Vector2<f32>>() / points.len() as f32;
+                        collision_data.collision_points = vec![average];
+                    }
+
                     Some(BodyBodyCollision {
                         index_a,
                         index_b,
@@ -204,7 +767,7 @@ impl RbSimulator {
     }
 
     /// Applies appropriate forces to bodies in order to resolve all collisions.
-    fn resolve_collisions(&mut self, collisions: &LinkedList<BodyBodyCollision>) {
+    fn resolve_collisions(&mut self, collisions: &[BodyBodyCollision]) {
         let bodies = &mut self.bodies;
         for coll in collisions {
             let BodyBodyCollision {
@@ -226,6 +789,17 @@ impl RbSimulator {
                 penetration,
                 collision_points,
             } = collision_data;
+            // Velocity pushing the bodies apart, if the penetration is corrected through velocity.
+            // Snapped penetrations are removed by moving the bodies, so they would only gain energy.
+            let (velocity_weight, _) = self.correction_mode.weights();
+            let velocity_weight = if is_snapped(penetration, self.snap_penetration) {
+                0.0
+            } else {
+                velocity_weight
+            };
+            let correction =
+                velocity_weight * Self::CORRECTION_FACTOR * (penetration - Self::SLOP).max(0.0)
+                    / self.current_time_step;
 
             // Calculate needed values
             // Values of A
@@ -264,42 +838,95 @@ impl RbSimulator {
             let inv_masses = inverse_value(mass_a) + inverse_value(mass_b);
             // Apply impulse for each collision point weighted by the number of collision points
             let multiplier = 1.0 / collision_points.len() as f32;
-            let correction = Self::CORRECTION_FACTOR * (penetration - Self::SLOP).max(0.0)
-                / self.current_time_step;
-            for coll_point in collision_points {
-                let radius_a = coll_point - center_a;
-                let radius_b = coll_point - center_b;
+            let tangent = normal.normal();
 
-                // Relative velocity of the contact point from both bodies
-                let relative_velocity = (velocity_a
-                    + scalar_vector_cross(angular_velocity_a, radius_a))
-                    - (velocity_b + scalar_vector_cross(angular_velocity_b, radius_b));
+            let radii: Vec<(Vector2<f32>, Vector2<f32>)> = collision_points
+                .iter()
+                .map(|&point| (point - center_a, point - center_b))
+                .collect();
 
-                // Their are movign away from each other -> no need to do anything
-                if relative_velocity.dot(normal) < 0.0 {
-                    continue;
-                }
+            // Relative velocity of the contact point from both bodies
+            let relative_velocity_at = |radius_a: Vector2<f32>, radius_b: Vector2<f32>| {
+                (velocity_a + scalar_vector_cross(angular_velocity_a, radius_a))
+                    - (velocity_b + scalar_vector_cross(angular_velocity_b, radius_b))
+            };
+
+            // Formula for calculation of the effective mass in direction. The bottom term in
+            // the impulse calculation. For `i != j` it is how much an impulse at point `j`
+            // changes the velocity at point `i`.
+            let effective_mass_formula = |i: usize, j: usize, dir: Vector2<f32>| {
+                let (radius_a_i, radius_b_i) = radii[i];
+                let (radius_a_j, radius_b_j) = radii[j];
+                let inertia_term_a = radius_a_i.cross(dir) * radius_a_j.cross(dir) * inv_inertia_a;
+                let inertia_term_b = radius_b_i.cross(dir) * radius_b_j.cross(dir) * inv_inertia_b;
+
+                inv_masses + inertia_term_a + inertia_term_b
+            };
+
+            let normal_velocities: Vec<f32> = radii
+                .iter()
+                .map(|&(radius_a, radius_b)| relative_velocity_at(radius_a, radius_b).dot(normal))
+                .collect();
+            // The needed change of velocity along the normal at each point
+            let target_velocity = |i: usize| {
+                let elasticity = if normal_velocities[i] > self.restitution_threshold {
+                    shared_elasticity
+                } else {
+                    0.0
+                };
+                (1.0 + elasticity) * (normal_velocities[i] + correction)
+            };
 
-                // Formula for calculation of the effective mass in direction. The bottom term in
-                // the impulse calculation.
-                let effective_mass_formula = |dir: Vector2<f32>| {
-                    let inertia_term_a =
-                        scalar_vector_cross(radius_a.cross(dir), radius_a) * inv_inertia_a;
-                    let inertia_term_b =
-                        scalar_vector_cross(radius_b.cross(dir), radius_b) * inv_inertia_b;
+            // Two points sharing a normal (eg. two faces resting on each other) are solved
+            // together. Solving them one by one makes each ignore the rotation caused by the other
+            // which makes flat contacts rock.
+            let block_impulses = if radii.len() == 2 && normal_velocities.iter().any(|v| *v >= 0.0)
+            {
+                let effective_masses = Matrix::new([
+                    [
+                        effective_mass_formula(0, 0, normal),
+                        effective_mass_formula(0, 1, normal),
+                    ],
+                    [
+                        effective_mass_formula(1, 0, normal),
+                        effective_mass_formula(1, 1, normal),
+                    ],
+                ]);
+                solve_two_contacts(effective_masses, [target_velocity(0), target_velocity(1)])
+            } else {
+                None
+            };
 
-                    inv_masses + (inertia_term_a + inertia_term_b).dot(dir)
+            // Normal impulse of each point, `None` where the bodies are moving away from each other
+            let normal_impulses: Vec<Option<f32>> = match block_impulses {
+                Some(impulses) => impulses
+                    .into_iter()
+                    .map(|impulse| (impulse > 0.0).then_some(-impulse))
+                    .collect(),
+                None => (0..radii.len())
+                    .map(|i| {
+                        (normal_velocities[i] >= 0.0).then(|| {
+                            -target_velocity(i) / effective_mass_formula(i, i, normal) * multiplier
+                        })
+                    })
+                    .collect(),
+            };
+
+            for (i, impulse_normal) in normal_impulses.into_iter().enumerate() {
+                // Their are movign away from each other -> no need to do anything
+                let Some(impulse_normal) = impulse_normal else {
+                    continue;
                 };
+                let (radius_a, radius_b) = radii[i];
+                let relative_velocity = relative_velocity_at(radius_a, radius_b);
 
-                // Normal impulse
-                let top_term =
-                    -(1.0 + shared_elasticity) * (relative_velocity.dot(normal) + correction);
-                let impulse_normal = top_term / effective_mass_formula(normal) * multiplier;
+                self.contact_impulses[index_a] += impulse_normal.abs();
+                self.contact_impulses[index_b] += impulse_normal.abs();
 
                 // Tangent impulse - friction
-                let tangent = normal.normal();
-                let mut impulse_tangent =
-                    relative_velocity.dot(tangent) / effective_mass_formula(tangent) * multiplier;
+                let mut impulse_tangent = relative_velocity.dot(tangent)
+                    / effective_mass_formula(i, i, tangent)
+                    * multiplier;
                 if impulse_tangent.abs() > shared_static_friction * impulse_normal {
                     impulse_tangent *= shared_dynamic_friction;
                 }
@@ -364,9 +991,321 @@ fn inverse_value(value: f32) -> f32 {
     }
 }
 
+/// Solves `effective_masses * impulses = target_velocities` for the normal impulses of 2 contact
+/// points such that no impulse pulls the bodies together. Points which would separate on their own
+/// get zero impulse. Returns `None` if the system is ill conditioned, eg. when the points (almost)
+/// coincide.
+fn solve_two_contacts(
+    effective_masses: Matrix<f32, 2, 2>,
+    target_velocities: [f32; 2],
+) -> Option<[f32; 2]> {
+    const MAX_CONDITION: f32 = 1000.0;
+
+    let k = |row: usize, col: usize| *effective_masses.get(row, col);
+    let [b_0, b_1] = target_velocities;
+    if k(0, 0) * k(0, 0) >= MAX_CONDITION * effective_masses.determinant() {
+        return None;
+    }
+
+    // Both points pushing
+    let impulses = effective_masses.inverse()? * Matrix::new([[b_0], [b_1]]);
+    let (x_0, x_1) = (*impulses.get(0, 0), *impulses.get(1, 0));
+    if x_0 >= 0.0 && x_1 >= 0.0 {
+        return Some([x_0, x_1]);
+    }
+
+    // Only the first point pushing
+    let x_0 = b_0 / k(0, 0);
+    if x_0 >= 0.0 && k(1, 0) * x_0 >= b_1 {
+        return Some([x_0, 0.0]);
+    }
+
+    // Only the second point pushing
+    let x_1 = b_1 / k(1, 1);
+    if x_1 >= 0.0 && k(0, 1) * x_1 >= b_0 {
+        return Some([0.0, x_1]);
+    }
+
+    // Both separating
+    if b_0 <= 0.0 && b_1 <= 0.0 {
+        return Some([0.0, 0.0]);
+    }
+
+    None
+}
+
 /// As if: `(0, 0, scalar) x (v.x, v.y, 0)`
 fn scalar_vector_cross(scalar: f32, vector: Vector2<f32>) -> Vector2<f32> {
     let x = -scalar * vector.y;
     let y = scalar * vector.x;
     Vector2::new(x, y)
 }
+
+/// Returns true if the `penetration` is deep enough to be removed at once. A zero
+/// `snap_penetration` disables snapping.
+fn is_snapped(penetration: f32, snap_penetration: f32) -> bool {
+    snap_penetration > 0.0 && penetration >= snap_penetration
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Anchor, CorrectionMode, DistanceConstraint, RbSimulator, SharedProperty};
+    use crate::game::GameConfig;
+    use crate::math::{v2, Vector2};
+    use crate::physics::rigidbody::{BodyBehaviour, Rectangle, RigidBody};
+
+    /// Y coordinate of the top of the floor from `box_on_floor`.
+    const FLOOR_TOP: f32 = 490.0;
+    /// Side of the box from `box_on_floor`.
+    const BOX_SIZE: f32 = 50.0;
+
+    /// A static floor and a dynamic box above it with its center at `y`.
+    fn box_on_floor(y: f32) -> Vec<RigidBody> {
+        vec![
+            Rectangle!(v2!(400.0, 500.0); 800.0, 20.0; BodyBehaviour::Static),
+            Rectangle!(v2!(400.0, y); BOX_SIZE, BOX_SIZE; BodyBehaviour::Dynamic),
+        ]
+    }
+
+    #[test]
+    fn flat_contact_settles_without_rocking() {
+        let config = GameConfig::default();
+        let mut simulator = RbSimulator::new(config.gravity);
+        simulator.bodies = box_on_floor(460.0);
+        // No bouncing, so any rocking comes from how the contact points are solved
+        for body in &mut simulator.bodies {
+            body.state_mut().elasticity = SharedProperty::Value(0.0);
+        }
+        // Land slightly tilted so that one corner hits first
+        simulator.bodies[1].state_mut().orientation = 0.05;
+        simulator.bodies[1].update_inner_values();
+
+        // Let it settle
+        for _ in 0..500 {
+            simulator.step(&config, config.time_step);
+        }
+
+        for _ in 0..500 {
+            simulator.step(&config, config.time_step);
+
+            let state = simulator.bodies[1].state();
+            assert!(
+                state.angular_velocity.abs() < 0.001,
+                "{}",
+                state.angular_velocity
+            );
+            assert!(state.orientation.abs() < 0.01, "{}", state.orientation);
+        }
+    }
+
+    #[test]
+    fn half_plane_stops_falling_body() {
+        let config = GameConfig::default();
+        let mut simulator = RbSimulator::new(config.gravity);
+        simulator.bodies =
+            vec![Rectangle!(v2!(400.0, 300.0); BOX_SIZE, BOX_SIZE; BodyBehaviour::Dynamic)];
+        // Only the space above the line is allowed
+        let boundary = 400.0;
+        simulator.add_half_plane(v2!(0.0, boundary), v2!(0.0, -1.0));
+
+        for _ in 0..200 {
+            simulator.step(&config, config.time_step);
+
+            let state = simulator.bodies[0].state();
+            assert!(state.position.y <= boundary + 0.001, "{}", state.position.y);
+        }
+        let state = simulator.bodies[0].state();
+        // It reached the line and does not move into it
+        assert!(
+            (state.position.y - boundary).abs() < 0.001,
+            "{}",
+            state.position.y
+        );
+        assert!(state.velocity.y.abs() < 0.001, "{}", state.velocity.y);
+    }
+
+    #[test]
+    fn fast_body_does_not_pass_through_wall() {
+        let config = GameConfig {
+            gravity_enabled: false,
+            ..Default::default()
+        };
+        let mut simulator = RbSimulator::new(config.gravity);
+        simulator.bodies = vec![
+            Rectangle!(v2!(400.0, 300.0); 20.0, 400.0; BodyBehaviour::Static),
+            Rectangle!(v2!(335.0, 300.0); 10.0, 10.0; BodyBehaviour::Dynamic),
+        ];
+        // Moves 70 cm in a single step, which would jump over the wall
+        simulator.bodies[1].state_mut().velocity = v2!(7000.0, 0.0);
+
+        for _ in 0..20 {
+            simulator.step(&config, config.time_step);
+        }
+
+        assert!(simulator.bodies[1].state().position.x < 400.0);
+    }
+
+    #[test]
+    fn resting_body_keeps_contact_offset_gap() {
+        let mut config = GameConfig::default();
+        config.rb_config.contact_offset = 2.0;
+        let mut simulator = RbSimulator::new(config.gravity);
+        simulator.bodies = box_on_floor(460.0);
+
+        for _ in 0..500 {
+            simulator.step(&config, config.time_step);
+        }
+
+        let gap = FLOOR_TOP - (simulator.bodies[1].state().position.y + BOX_SIZE * 0.5);
+        let max_gap = config.rb_config.contact_offset * 2.0;
+        assert!(gap > max_gap - RbSimulator::SLOP - 0.1, "{gap}");
+        assert!(gap < max_gap + 0.1, "{gap}");
+    }
+
+    #[test]
+    fn every_correction_mode_keeps_box_on_floor() {
+        for mode in [
+            CorrectionMode::Baumgarte,
+            CorrectionMode::Positional,
+            CorrectionMode::Blend,
+        ] {
+            let mut config = GameConfig::default();
+            config.rb_config.correction_mode.select(mode);
+            let mut simulator = RbSimulator::new(config.gravity);
+            simulator.bodies = box_on_floor(400.0);
+
+            for _ in 0..500 {
+                simulator.step(&config, config.time_step);
+            }
+
+            let penetration = simulator.bodies[1].state().position.y + BOX_SIZE * 0.5 - FLOOR_TOP;
+            assert!(penetration < RbSimulator::SLOP + 0.5, "{penetration}");
+        }
+    }
+
+    #[test]
+    fn box_on_floor_is_resting() {
+        let config = GameConfig::default();
+        let mut simulator = RbSimulator::new(config.gravity);
+        simulator.bodies = box_on_floor(460.0);
+        simulator
+            .bodies
+            .push(Rectangle!(v2!(100.0, 100.0); 20.0, 20.0; BodyBehaviour::Dynamic));
+
+        for _ in 0..100 {
+            simulator.step(&config, config.time_step);
+        }
+
+        assert!(simulator.is_resting(1));
+        assert!(simulator.body_contact_count(1) > 0);
+        // The floor is below the box, so it does not rest on it
+        assert!(!simulator.is_resting(0));
+        // Still falling
+        assert!(!simulator.is_resting(2));
+        assert_eq!(simulator.body_contact_count(2), 0);
+        // The contacts go both ways
+        assert_eq!(simulator.contacts_of(0), vec![1]);
+        assert_eq!(simulator.contacts_of(1), vec![0]);
+        assert!(simulator.contacts_of(2).is_empty());
+    }
+
+    #[test]
+    fn single_touch_is_not_resting() {
+        let config = GameConfig::default();
+        let mut simulator = RbSimulator::new(config.gravity);
+        // Already touching the floor
+        simulator.bodies = box_on_floor(466.0);
+
+        simulator.step(&config, config.time_step);
+        assert!(simulator.body_contact_count(1) > 0);
+        assert!(!simulator.is_resting(1));
+
+        simulator.step(&config, config.time_step);
+        assert!(simulator.is_resting(1));
+    }
+
+    #[test]
+    fn single_contact_point_is_in_the_middle() {
+        let mut simulator = RbSimulator::new(v2!(0.0, 0.0));
+        simulator.bodies = box_on_floor(466.0);
+        assert_eq!(
+            simulator.check_collisions()[0]
+                .collision_data
+                .collision_points
+                .len(),
+            2
+        );
+
+        simulator.single_contact_point = true;
+        let collisions = simulator.check_collisions();
+
+        let points = &collisions[0].collision_data.collision_points;
+        assert_eq!(points.len(), 1);
+        assert!((points[0].x - 400.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn deep_penetration_is_snapped_apart() {
+        let penetration_after_step = |snap_penetration: f32| {
+            let mut config = GameConfig {
+                gravity_enabled: false,
+                ..Default::default()
+            };
+            config
+                .rb_config
+                .correction_mode
+                .select(CorrectionMode::Baumgarte);
+            config.rb_config.snap_penetration = snap_penetration;
+            let mut simulator = RbSimulator::new(config.gravity);
+            // Sunk 30 into the floor
+            simulator.bodies = box_on_floor(495.0);
+
+            simulator.step(&config, config.time_step);
+
+            simulator.bodies[1].state().position.y + BOX_SIZE * 0.5 - FLOOR_TOP
+        };
+
+        assert!(penetration_after_step(0.0) > 10.0);
+        let snapped = penetration_after_step(20.0);
+        assert!(snapped < RbSimulator::SLOP + 0.5, "{snapped}");
+    }
+
+    #[test]
+    fn pinned_chain_keeps_its_length() {
+        let config = GameConfig::default();
+        let mut simulator = RbSimulator::new(config.gravity);
+        // A horizontal chain pinned at its left end swings down under gravity
+        let length = 20.0;
+        for i in 0..5 {
+            let position = v2!(100.0 + i as f32 * length, 100.0);
+            let body = RigidBody::new_circle(position, 5.0, BodyBehaviour::Dynamic);
+            simulator.bodies.push(body);
+            if i > 0 {
+                simulator.constraints.push(DistanceConstraint {
+                    index: i,
+                    anchor: Anchor::Body(i - 1),
+                    length,
+                });
+            }
+        }
+        let pin = v2!(100.0, 100.0);
+        simulator.constraints.push(DistanceConstraint {
+            index: 0,
+            anchor: Anchor::World(pin),
+            length: 0.0,
+        });
+
+        for _ in 0..200 {
+            simulator.step(&config, config.time_step);
+        }
+
+        let position = |i: usize| simulator.bodies[i].state().position;
+        assert!((position(0) - pin).length() < 1.0);
+        for i in 1..5 {
+            let distance = (position(i) - position(i - 1)).length();
+            assert!((distance - length).abs() < 1.0, "{distance}");
+        }
+        // The chain is hanging down now
+        assert!(position(4).y > pin.y + 2.0 * length);
+    }
+}
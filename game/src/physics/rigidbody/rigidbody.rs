@@ -1,32 +1,46 @@
-use crate::math::Vector2;
+use crate::math::{v2, Vector2};
+use crate::physics::units::to_cm2;
 
 use super::{
     circle::CircleInner,
     collisions::{circle_circle_collision, polygon_circle_collision, polygon_polygon_collision},
+    local_point_to_global,
     polygon::PolygonInner,
     BodyBehaviour, BodyCollisionData, BodyState,
 };
 
+/// Number of vertices of the polygon approximating an ellipse.
+const ELLIPSE_SEGMENTS: usize = 24;
+
+#[derive(Clone)]
 pub enum RigidBody {
     Polygon(PolygonInner),
     Circle(CircleInner),
 }
 
 impl RigidBody {
-    pub fn check_collision(first: &RigidBody, second: &RigidBody) -> Option<BodyCollisionData> {
+    /// Checks if the 2 bodies collide. Each body is treated as if it was larger by
+    /// `contact_offset` in every direction, so resting bodies keep a small gap between them.
+    pub fn check_collision(
+        first: &RigidBody,
+        second: &RigidBody,
+        contact_offset: f32,
+    ) -> Option<BodyCollisionData> {
         match (first, second) {
             // Polygon - Polygon
             (Self::Polygon(first), Self::Polygon(second)) => {
-                polygon_polygon_collision(first, second)
+                polygon_polygon_collision(first, second, contact_offset)
             }
             // Circle - Circle
-            (Self::Circle(first), Self::Circle(second)) => circle_circle_collision(first, second),
+            (Self::Circle(first), Self::Circle(second)) => {
+                circle_circle_collision(first, second, contact_offset)
+            }
             // Polygon - Circle / Circle - Polygon
             (Self::Polygon(polygon), Self::Circle(circle)) => {
-                polygon_circle_collision(polygon, circle)
+                polygon_circle_collision(polygon, circle, contact_offset)
             }
             (Self::Circle(circle), Self::Polygon(polygon)) => {
-                let mut data = polygon_circle_collision(polygon, circle);
+                let mut data = polygon_circle_collision(polygon, circle, contact_offset);
                 // Flip the sign of the normal
                 if let Some(data) = &mut data {
                     data.normal *= -1.0;
@@ -69,6 +83,28 @@ impl RigidBody {
         RigidBody::Circle(circle)
     }
 
+    /// Creates an ellipse with semi-axes `a` (along the X axis) and `b` (along the Y axis).
+    /// The shape is approximated by a polygon but it has the moment of inertia of a true ellipse.
+    pub fn new_ellipse(
+        position: Vector2<f32>,
+        a: f32,
+        b: f32,
+        behaviour: BodyBehaviour,
+    ) -> RigidBody {
+        let points = (0..ELLIPSE_SEGMENTS)
+            .map(|i| {
+                let angle = i as f32 / ELLIPSE_SEGMENTS as f32 * std::f32::consts::TAU;
+                v2!(a * angle.cos(), b * angle.sin())
+            })
+            .collect();
+
+        let mut ellipse = RigidBody::new_polygon(position, points, behaviour);
+        let state = ellipse.state_mut();
+        state.moment_of_inertia = 0.25 * state.mass * (a * a + b * b);
+
+        ellipse
+    }
+
     pub fn state(&self) -> &BodyState {
         match self {
             Self::Polygon(inner) => &inner.state,
@@ -101,6 +137,22 @@ impl RigidBody {
         }
     }
 
+    /// Returns the distance from `point` to the outline of this body or zero if the point is inside.
+    pub fn distance_to_point(&self, point: Vector2<f32>) -> f32 {
+        if self.contains_point(point) {
+            return 0.0;
+        }
+
+        match self {
+            Self::Polygon(inner) => inner
+                .global_lines
+                .iter()
+                .map(|line| (line.closest_point(point) - point).length())
+                .fold(f32::INFINITY, f32::min),
+            Self::Circle(inner) => (point - inner.state.position).length() - inner.radius,
+        }
+    }
+
     pub fn update_inner_values(&mut self) {
         match self {
             Self::Polygon(inner) => inner.update_inner_values(),
@@ -108,10 +160,245 @@ impl RigidBody {
         }
     }
 
+    /// Returns a copy of this body placed between its previous (`alpha = 0`) and current
+    /// (`alpha = 1`) transform.
+    pub fn interpolated(&self, alpha: f32) -> RigidBody {
+        let mut body = self.clone();
+        let state = body.state_mut();
+        state.position =
+            state.previous_position + (state.position - state.previous_position) * alpha;
+        state.orientation =
+            state.previous_orientation + (state.orientation - state.previous_orientation) * alpha;
+        body.update_inner_values();
+
+        body
+    }
+
+    /// Returns the distance from `origin` along the unit `direction` at which the ray first hits
+    /// the outline of this body. A ray starting inside a circle hits it on the way out.
+    pub fn raycast(&self, origin: Vector2<f32>, direction: Vector2<f32>) -> Option<f32> {
+        match self {
+            Self::Polygon(inner) => inner
+                .global_lines
+                .iter()
+                .filter_map(|line| line.ray_intersection(origin, direction))
+                .min_by(f32::total_cmp),
+            Self::Circle(inner) => {
+                let to_origin = origin - inner.state.position;
+                let b = to_origin.dot(direction);
+                let c = to_origin.length_squared() - inner.radius * inner.radius;
+                let discriminant = b * b - c;
+                if discriminant < 0.0 {
+                    return None;
+                }
+
+                let root = discriminant.sqrt();
+                [-b - root, -b + root].into_iter().find(|t| *t >= 0.0)
+            }
+        }
+    }
+
+    /// Returns how far this body can move along the unit `direction` before it touches `obstacle`.
+    /// Polygons are approximated by rays from their vertices, circles by a ray from their center.
+    pub fn sweep_distance(&self, obstacle: &RigidBody, direction: Vector2<f32>) -> Option<f32> {
+        match self {
+            Self::Polygon(inner) => inner
+                .global_points
+                .iter()
+                .filter_map(|point| obstacle.raycast(*point, direction))
+                .min_by(f32::total_cmp),
+            Self::Circle(inner) => obstacle
+                .raycast(inner.state.position, direction)
+                .map(|distance| (distance - inner.radius).max(0.0)),
+        }
+    }
+
+    /// Shifts the local points of a polygon so that their centroid is at the origin and moves the
+    /// body by the same offset, so the shape stays where it was but rotates around its centroid.
+    /// Circles are always centered and are left as they are.
+    pub fn recenter(&mut self) {
+        let Self::Polygon(inner) = self else {
+            return;
+        };
+        let centroid = inner.local_centroid();
+        if centroid.is_zero() {
+            return;
+        }
+
+        let offset = local_point_to_global(&inner.state, centroid) - inner.state.position;
+        for point in &mut inner.points {
+            *point -= centroid;
+        }
+        inner.state.position += offset;
+        inner.state.previous_position += offset;
+        inner.state.moment_of_inertia =
+            PolygonInner::calculate_moment_of_inertia(&inner.points, inner.state.mass);
+        inner.update_inner_values();
+    }
+
+    /// Area of the body in world units squared.
+    pub fn area(&self) -> f32 {
+        match self {
+            // Shoelace formula
+            Self::Polygon(inner) => {
+                let points = &inner.global_points;
+                let twice_area: f32 = (0..points.len())
+                    .map(|i| points[i].cross(points[(i + 1) % points.len()]))
+                    .sum();
+                twice_area.abs() * 0.5
+            }
+            Self::Circle(inner) => std::f32::consts::PI * inner.radius * inner.radius,
+        }
+    }
+
+    /// Length of the outline of the body in world units.
+    pub fn perimeter(&self) -> f32 {
+        match self {
+            Self::Polygon(inner) => inner
+                .global_lines
+                .iter()
+                .map(|line| line.vector().length())
+                .sum(),
+            Self::Circle(inner) => std::f32::consts::TAU * inner.radius,
+        }
+    }
+
+    /// Sets the mass of the body so that it has the given `density` in g/cm^2.
+    pub fn set_density(&mut self, density: f32) {
+        let mass = density * to_cm2(self.area());
+        self.state_mut().set_mass(mass);
+    }
+
     pub fn center_of_mass(&self) -> Vector2<f32> {
         match self {
             Self::Polygon(inner) => inner.center_of_mass(),
             Self::Circle(inner) => inner.state.position,
         }
     }
+
+    /// Returns the minimum and maximum corner of the axis aligned box enclosing this body.
+    pub fn bounding_box(&self) -> (Vector2<f32>, Vector2<f32>) {
+        match self {
+            Self::Polygon(inner) => {
+                let first = inner.global_points[0];
+                inner
+                    .global_points
+                    .iter()
+                    .fold((first, first), |(min, max), p| (min.min(*p), max.max(*p)))
+            }
+            Self::Circle(inner) => {
+                let position = inner.state.position;
+                let radius = v2!(inner.radius, inner.radius);
+                (position - radius, position + radius)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RigidBody;
+    use crate::math::{v2, Vector2};
+    use crate::physics::rigidbody::{BodyBehaviour, Rectangle};
+
+    #[test]
+    fn cloned_polygon_has_same_global_lines() {
+        let mut body = Rectangle!(v2!(100.0, 50.0); 40.0, 20.0; BodyBehaviour::Dynamic);
+        body.state_mut().orientation = 0.5;
+        body.update_inner_values();
+
+        let cloned = body.clone();
+
+        match (&body, &cloned) {
+            (RigidBody::Polygon(original), RigidBody::Polygon(cloned)) => {
+                assert_eq!(original.global_lines.len(), cloned.global_lines.len());
+                for (a, b) in original.global_lines.iter().zip(&cloned.global_lines) {
+                    assert_eq!(a.start, b.start);
+                    assert_eq!(a.end, b.end);
+                }
+            }
+            _ => panic!("Clone of a polygon is not a polygon"),
+        }
+    }
+
+    #[test]
+    fn unit_square_area_and_perimeter() {
+        let mut body = Rectangle!(v2!(10.0, 10.0); 1.0, 1.0; BodyBehaviour::Dynamic);
+        body.state_mut().orientation = 0.3;
+        body.update_inner_values();
+
+        assert!((body.area() - 1.0).abs() < 1e-4);
+        assert!((body.perimeter() - 4.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn circle_area_and_perimeter() {
+        let body = RigidBody::new_circle(v2!(10.0, 10.0), 2.0, BodyBehaviour::Dynamic);
+
+        assert!((body.area() - 4.0 * std::f32::consts::PI).abs() < 1e-4);
+        assert!((body.perimeter() - 4.0 * std::f32::consts::PI).abs() < 1e-4);
+    }
+
+    #[test]
+    fn distance_to_point_is_zero_inside() {
+        let square = Rectangle!(v2!(50.0, 0.0); 20.0, 20.0; BodyBehaviour::Static);
+        let circle = RigidBody::new_circle(v2!(50.0, 0.0), 10.0, BodyBehaviour::Static);
+
+        for body in [square, circle] {
+            assert_eq!(body.distance_to_point(v2!(52.0, 1.0)), 0.0);
+            assert!((body.distance_to_point(v2!(65.0, 0.0)) - 5.0).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn raycast_hits_nearest_edge() {
+        let square = Rectangle!(v2!(50.0, 0.0); 20.0, 20.0; BodyBehaviour::Static);
+        let circle = RigidBody::new_circle(v2!(50.0, 0.0), 10.0, BodyBehaviour::Static);
+
+        for body in [square, circle] {
+            let hit = body.raycast(v2!(0.0, 0.0), v2!(1.0, 0.0));
+            assert!((hit.unwrap() - 40.0).abs() < 1e-4);
+            assert!(body.raycast(v2!(0.0, 0.0), v2!(-1.0, 0.0)).is_none());
+            assert!(body.raycast(v2!(0.0, 0.0), v2!(0.0, 1.0)).is_none());
+        }
+    }
+
+    #[test]
+    fn sweep_distance_stops_at_the_obstacle() {
+        let wall = Rectangle!(v2!(100.0, 0.0); 20.0, 200.0; BodyBehaviour::Static);
+        let square = Rectangle!(v2!(0.0, 0.0); 10.0, 10.0; BodyBehaviour::Dynamic);
+        let circle = RigidBody::new_circle(v2!(0.0, 0.0), 5.0, BodyBehaviour::Dynamic);
+
+        for body in [square, circle] {
+            let distance = body.sweep_distance(&wall, v2!(1.0, 0.0));
+            assert!((distance.unwrap() - 85.0).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn recenter_keeps_the_shape_in_place() {
+        let points = vec![
+            v2!(10.0, 10.0),
+            v2!(30.0, 10.0),
+            v2!(30.0, 20.0),
+            v2!(10.0, 20.0),
+        ];
+        let mut body = RigidBody::new_polygon(v2!(100.0, 100.0), points, BodyBehaviour::Dynamic);
+        body.state_mut().orientation = 0.7;
+        body.update_inner_values();
+        let RigidBody::Polygon(before) = body.clone() else {
+            unreachable!()
+        };
+
+        body.recenter();
+
+        let RigidBody::Polygon(after) = &body else {
+            unreachable!()
+        };
+        assert!(after.local_centroid().length() < 1e-4);
+        for (a, b) in before.global_points.iter().zip(&after.global_points) {
+            assert!((*a - *b).length() < 1e-3);
+        }
+        assert!((body.state().position - body.center_of_mass()).length() < 1e-3);
+    }
 }
@@ -0,0 +1,130 @@
+use crate::math::Vector2;
+use crate::physics::sph::Particle;
+use crate::shapes::Line;
+
+/// How long is the crossed mass accumulated before the rate is updated. In seconds.
+const RATE_WINDOW: f32 = 1.0;
+
+/// Measures how much fluid mass crosses a line per second.
+/// Crossing in the direction of the line normal counts as positive, the opposite as negative.
+pub struct FlowProbe {
+    pub line: Line,
+    crossed_mass: f32,
+    elapsed_time: f32,
+    rate: f32,
+}
+
+impl FlowProbe {
+    pub fn new(a: Vector2<f32>, b: Vector2<f32>) -> Self {
+        FlowProbe {
+            line: Line::new(a, b),
+            crossed_mass: 0.0,
+            elapsed_time: 0.0,
+            rate: 0.0,
+        }
+    }
+
+    /// Adds the mass of particles that crossed the line during the last step of length `dt`.
+    /// Moves longer than half of the `world_size` are ignored, as those are particles that
+    /// wrapped around the world and not ones that flowed through the line.
+    pub fn measure<'a>(
+        &mut self,
+        particles: impl IntoIterator<Item = &'a Particle>,
+        world_size: Vector2<f32>,
+        dt: f32,
+    ) {
+        let half_world = world_size * 0.5;
+        self.crossed_mass += particles
+            .into_iter()
+            .filter(|p| {
+                let movement = p.position - p.previous_position;
+                movement.x.abs() <= half_world.x && movement.y.abs() <= half_world.y
+            })
+            .filter_map(|p| {
+                let was_in_front = self.is_in_front(p.previous_position);
+                let is_in_front = self.is_in_front(p.position);
+                if was_in_front == is_in_front {
+                    return None;
+                }
+                let movement = Line::new(p.previous_position, p.position);
+                self.line
+                    .intersects(&movement)
+                    .then(|| if is_in_front { p.mass() } else { -p.mass() })
+            })
+            .sum::<f32>();
+
+        self.elapsed_time += dt;
+        if self.elapsed_time >= RATE_WINDOW {
+            self.rate = self.crossed_mass / self.elapsed_time;
+            self.crossed_mass = 0.0;
+            self.elapsed_time = 0.0;
+        }
+    }
+
+    /// Returns true if the point is on the side of the line the normal points to. Points exactly
+    /// on the line count as in front, so a particle that stops on the line is counted only once.
+    fn is_in_front(&self, point: Vector2<f32>) -> bool {
+        (point - self.line.start).dot(self.line.normal()) >= 0.0
+    }
+
+    /// Mass that crossed the line per second, averaged over the last finished window.
+    pub fn rate(&self) -> f32 {
+        self.rate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FlowProbe;
+    use crate::math::{v2, Vector2};
+    use crate::physics::sph::Particle;
+
+    const WORLD_SIZE: Vector2<f32> = v2!(1000.0, 1000.0);
+
+    fn moved_particle(from: Vector2<f32>, to: Vector2<f32>) -> Particle {
+        let mut particle = Particle::new(from).with_mass(2.0);
+        particle.position = to;
+        particle
+    }
+
+    #[test]
+    fn counts_crossings_by_direction() {
+        // Normal of this line points along the Y axis
+        let mut probe = FlowProbe::new(v2!(0.0, 0.0), v2!(10.0, 0.0));
+        let normal = probe.line.normal();
+        let forward = moved_particle(v2!(5.0, 0.0) - normal, v2!(5.0, 0.0) + normal);
+        let backward = moved_particle(v2!(2.0, 0.0) + normal, v2!(2.0, 0.0) - normal);
+        let missing = moved_particle(v2!(20.0, -1.0), v2!(20.0, 1.0));
+
+        probe.measure(
+            &[forward.clone(), forward, backward, missing],
+            WORLD_SIZE,
+            1.0,
+        );
+
+        assert_eq!(probe.rate(), 2.0);
+    }
+
+    #[test]
+    fn stopping_on_the_line_counts_once() {
+        let mut probe = FlowProbe::new(v2!(0.0, 0.0), v2!(10.0, 0.0));
+        let normal = probe.line.normal();
+        let onto = moved_particle(v2!(5.0, 0.0) - normal, v2!(5.0, 0.0));
+        let off = moved_particle(v2!(5.0, 0.0), v2!(5.0, 0.0) + normal);
+
+        probe.measure(&[onto], WORLD_SIZE, 0.5);
+        probe.measure(&[off], WORLD_SIZE, 0.5);
+
+        assert_eq!(probe.rate(), 2.0);
+    }
+
+    #[test]
+    fn ignores_wrapping_around_the_world() {
+        let mut probe = FlowProbe::new(v2!(0.0, 500.0), v2!(1000.0, 500.0));
+        let wrapped = moved_particle(v2!(300.0, 990.0), v2!(300.0, 10.0));
+
+        probe.measure(&[wrapped], WORLD_SIZE, 1.0);
+
+        assert_eq!(probe.rate(), 0.0);
+    }
+}
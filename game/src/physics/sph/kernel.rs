@@ -0,0 +1,41 @@
+/// The smoothing kernel used for density and pressure calculations.
+///
+/// All kernels are scaled so that they have the same value at zero distance. This keeps the
+/// `base_pressure` reasonable for each of them, but they still differ in stiffness and stability.
+#[derive(Clone, Copy, PartialEq)]
+pub enum KernelKind {
+    /// `(1 - d/r)^2`
+    Quadratic,
+    /// `(1 - d^2/r^2)^3` - smooth, but its gradient vanishes near the center.
+    Poly6,
+    /// `(1 - d/r)^3` - has a sharp gradient near the center which prevents clumping.
+    Spiky,
+}
+
+impl KernelKind {
+    pub fn value(&self, dist: f32, radius: f32) -> f32 {
+        if dist > radius {
+            return 0.0;
+        }
+
+        let shape = match self {
+            Self::Quadratic => (1.0 - dist / radius).max(0.0).powi(2),
+            Self::Poly6 => (1.0 - (dist / radius).powi(2)).max(0.0).powi(3),
+            Self::Spiky => (1.0 - dist / radius).max(0.0).powi(3),
+        };
+
+        shape * (3.0 / radius)
+    }
+
+    pub fn derivative(&self, dist: f32, radius: f32) -> f32 {
+        if dist > radius {
+            return 0.0;
+        }
+
+        match self {
+            Self::Quadratic => (6.0 * (dist - radius)) / radius.powi(2),
+            Self::Poly6 => -18.0 * dist * (1.0 - (dist / radius).powi(2)).powi(2) / radius.powi(2),
+            Self::Spiky => -9.0 * (1.0 - dist / radius).powi(2) / radius,
+        }
+    }
+}
@@ -1,4 +1,7 @@
+mod flow_probe;
+mod kernel;
 mod particle;
+mod particle_store;
 mod simulation;
 
-pub use {particle::Particle, simulation::Sph};
+pub use {flow_probe::FlowProbe, kernel::KernelKind, particle::Particle, simulation::Sph};
@@ -9,6 +9,8 @@ const MAX_SPEED_SQUARED: f32 = MAX_SPEED * MAX_SPEED;
 pub struct Particle {
     pub position: Vector2<f32>,
     pub predicted_position: Vector2<f32>,
+    /// Position before the last move by velocity.
+    pub previous_position: Vector2<f32>,
     pub velocity: Vector2<f32>,
     pub sph_density: f32,
     pub(crate) mass: f32,
@@ -19,6 +21,11 @@ pub struct Particle {
     pub(crate) body_collision_force_multiplier: f32,
     pub(crate) accumulated_force: Vector2<f32>,
     pub color: Color,
+    /// Particles with charges of the same sign repel each other, with opposite signs they attract.
+    pub charge: f32,
+    /// How strongly is the velocity of the particle pulled towards the velocity of its neighbors.
+    /// Zero is a perfectly runny fluid.
+    pub viscosity: f32,
     /// Should be set by the simulation when the particle is inserted
     pub(crate) id: u32,
 }
@@ -32,6 +39,7 @@ impl Particle {
         Particle {
             position,
             predicted_position: position,
+            previous_position: position,
             velocity,
             sph_density: 0.0,
             mass: 1.0,
@@ -40,6 +48,8 @@ impl Particle {
             body_collision_force_multiplier: 1.0,
             accumulated_force: Vector2::zero(),
             color: Color::rgb(0, 0, 255),
+            charge: 0.0,
+            viscosity: 0.0,
             id: 0,
         }
     }
@@ -54,6 +64,26 @@ impl Particle {
         self
     }
 
+    pub fn with_velocity(mut self, velocity: Vector2<f32>) -> Self {
+        self.velocity = velocity;
+        self
+    }
+
+    pub fn with_charge(mut self, charge: f32) -> Self {
+        self.charge = charge;
+        self
+    }
+
+    pub fn with_viscosity(mut self, viscosity: f32) -> Self {
+        self.viscosity = viscosity;
+        self
+    }
+
+    /// Identifier of the particle which does not change while it is in the simulation.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
     pub fn mass(&self) -> f32 {
         self.mass
     }
@@ -65,6 +95,29 @@ impl Particle {
         self.body_collision_force_multiplier = self.mass;
     }
 
+    /// Returns true if both particles belong to the same fluid.
+    pub fn is_same_fluid(&self, other: &Particle) -> bool {
+        self.color == other.color
+            && self.target_density == other.target_density
+            && self.viscosity == other.viscosity
+    }
+
+    /// Merges `other` into this particle. Mass, momentum and charge are conserved and the
+    /// particle is moved to the center of mass of the two.
+    /// The target density and pressure stay the same, so the fluid keeps behaving as before.
+    pub fn absorb(&mut self, other: &Particle) {
+        let mass = self.mass + other.mass;
+        let weighted = |a: Vector2<f32>, b: Vector2<f32>| (a * self.mass + b * other.mass) / mass;
+
+        self.position = weighted(self.position, other.position);
+        self.predicted_position = weighted(self.predicted_position, other.predicted_position);
+        self.previous_position = weighted(self.previous_position, other.previous_position);
+        self.velocity = weighted(self.velocity, other.velocity);
+        self.charge += other.charge;
+        self.mass = mass;
+        self.body_collision_force_multiplier = mass;
+    }
+
     /// Adds `force` to the accumulated force.
     pub fn add_force(&mut self, force: Vector2<f32>) {
         self.accumulated_force += force;
@@ -88,6 +141,7 @@ impl Particle {
             self.velocity = dir * MAX_SPEED;
         }
 
+        self.previous_position = self.position;
         self.position = runge_kutta(self.position, delta_time, self.velocity);
     }
 
@@ -95,6 +149,12 @@ impl Particle {
         self.predicted_position = runge_kutta(self.position, delta_time, self.velocity);
     }
 
+    /// How many layers of fluid are around this particle, estimated from the density relative to
+    /// the target density. Used to shade the fluid as if it was seen from above.
+    pub fn depth(&self) -> f32 {
+        self.sph_density / self.target_density
+    }
+
     pub fn pressure(&self) -> f32 {
         self.pressure_multiplier * (self.sph_density - self.target_density)
     }
@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+
+use rayon::iter::{
+    IndexedParallelIterator, IntoParallelRefIterator, IntoParallelRefMutIterator, ParallelIterator,
+};
+
+use super::Particle;
+
+/// Storage of particles in slots. A particle keeps its slot until it is removed, so removing
+/// particles does not move the others and the indexes stored in the lookup stay valid.
+/// Freed slots are reused only after `release_freed` is called, which has to happen once nothing
+/// refers to them anymore, eg. after the lookup is rebuilt. Until then a stale index finds no
+/// particle instead of a different one.
+#[derive(Default)]
+pub struct ParticleStore {
+    slots: Vec<Option<Particle>>,
+    /// Slots that can be reused by inserted particles.
+    free: Vec<usize>,
+    /// Slots freed since the last `release_freed`.
+    freed: Vec<usize>,
+    slot_of_id: HashMap<u32, usize>,
+}
+
+impl ParticleStore {
+    /// Number of particles, not of slots.
+    pub fn len(&self) -> usize {
+        self.slot_of_id.len()
+    }
+
+    /// Number of slots, including the empty ones. All slots are below this.
+    pub fn slot_count(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Stores the particle and returns its slot. The id of the particle has to be unique.
+    pub fn insert(&mut self, particle: Particle) -> usize {
+        let id = particle.id;
+        let slot = match self.free.pop() {
+            Some(slot) => {
+                self.slots[slot] = Some(particle);
+                slot
+            }
+            None => {
+                self.slots.push(Some(particle));
+                self.slots.len() - 1
+            }
+        };
+        self.slot_of_id.insert(id, slot);
+
+        slot
+    }
+
+    /// Removes the particle in the `slot` and returns it, if there is one.
+    pub fn remove(&mut self, slot: usize) -> Option<Particle> {
+        let particle = self.slots.get_mut(slot)?.take()?;
+        self.slot_of_id.remove(&particle.id);
+        self.freed.push(slot);
+
+        Some(particle)
+    }
+
+    /// Allows the slots freed until now to be reused.
+    pub fn release_freed(&mut self) {
+        self.free.append(&mut self.freed);
+    }
+
+    pub fn clear(&mut self) {
+        self.slots.clear();
+        self.free.clear();
+        self.freed.clear();
+        self.slot_of_id.clear();
+    }
+
+    pub fn get(&self, slot: usize) -> Option<&Particle> {
+        self.slots.get(slot)?.as_ref()
+    }
+
+    pub fn get_mut(&mut self, slot: usize) -> Option<&mut Particle> {
+        self.slots.get_mut(slot)?.as_mut()
+    }
+
+    pub fn by_id(&self, id: u32) -> Option<&Particle> {
+        self.get(*self.slot_of_id.get(&id)?)
+    }
+
+    /// All slots in their order, so that values computed for each slot can be indexed by it.
+    pub fn slots(&self) -> &[Option<Particle>] {
+        &self.slots
+    }
+
+    /// Iterates over the particles in the order of their slots.
+    pub fn iter(&self) -> impl Iterator<Item = &Particle> {
+        self.slots.iter().flatten()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Particle> {
+        self.slots.iter_mut().flatten()
+    }
+
+    /// Iterates over the particles together with their slots.
+    pub fn iter_slots(&self) -> impl Iterator<Item = (usize, &Particle)> {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(slot, p)| p.as_ref().map(|p| (slot, p)))
+    }
+
+    pub fn par_iter(&self) -> impl ParallelIterator<Item = &Particle> {
+        self.slots.par_iter().filter_map(Option::as_ref)
+    }
+
+    pub fn par_iter_mut(&mut self) -> impl ParallelIterator<Item = &mut Particle> {
+        self.slots.par_iter_mut().filter_map(Option::as_mut)
+    }
+
+    /// Iterates in parallel over the particles together with their slots.
+    pub fn par_iter_slots_mut(&mut self) -> impl ParallelIterator<Item = (usize, &mut Particle)> {
+        self.slots
+            .par_iter_mut()
+            .enumerate()
+            .filter_map(|(slot, p)| p.as_mut().map(|p| (slot, p)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ParticleStore;
+    use crate::math::{v2, Vector2};
+    use crate::physics::sph::Particle;
+
+    fn particle(id: u32) -> Particle {
+        let mut particle = Particle::new(v2!(id as f32, 0.0));
+        particle.id = id;
+        particle
+    }
+
+    #[test]
+    fn freed_slot_is_reused_only_after_release() {
+        let mut store = ParticleStore::default();
+        for id in 0..3 {
+            store.insert(particle(id));
+        }
+
+        let removed = store.remove(1).unwrap();
+        assert_eq!(removed.id, 1);
+        assert!(store.get(1).is_none());
+        assert!(store.by_id(1).is_none());
+        // The others keep their slots
+        assert_eq!(store.by_id(2).unwrap().position, v2!(2.0, 0.0));
+        assert_eq!(store.get(2).unwrap().id, 2);
+
+        assert_eq!(store.insert(particle(3)), 3);
+        store.release_freed();
+        assert_eq!(store.insert(particle(4)), 1);
+        assert_eq!(store.len(), 4);
+        assert_eq!(store.slot_count(), 4);
+    }
+}
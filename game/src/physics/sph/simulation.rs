@@ -1,34 +1,30 @@
 use std::collections::LinkedList;
 
-use rayon::iter::{
-    IndexedParallelIterator, IntoParallelRefIterator, IntoParallelRefMutIterator, ParallelIterator,
-};
+use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
 
 use crate::game::GameConfig;
 use crate::math::Vector2;
 use crate::physics::rigidbody::{BodyBehaviour, BodyForceAccumulation, RigidBody};
-use crate::{physics::sph::Particle, utility::LookUp};
+use crate::physics::units::{from_cm, EARTH_GRAVITY};
+use crate::{
+    physics::sph::{particle_store::ParticleStore, KernelKind, Particle},
+    utility::LookUp,
+};
 
 const PRESSURE_BASE: f32 = 100_000.0;
 const BODY_COLLISION_FORCE_BASE: f32 = 10_000.0;
 
 const PARTICLE_COLLIDER_RADIUS: f32 = 5.0;
-
-fn kernel(dist: f32, radius: f32) -> f32 {
-    if dist > radius {
-        return 0.0;
-    }
-
-    (1.0 - dist / radius).max(0.0).powi(2) * (3.0 / radius)
-}
-
-fn kernel_derivative(dist: f32, radius: f32) -> f32 {
-    if dist > radius {
-        return 0.0;
-    }
-
-    (6.0 * (dist - radius)) / radius.powi(2)
-}
+/// Multiplier of the force between 2 charged particles.
+const CHARGE_FORCE_BASE: f32 = 100_000.0;
+/// Charged particles closer than this are treated as if they were this far apart so that the
+/// force does not explode.
+const MIN_CHARGE_DISTANCE: f32 = 2.0;
+/// How many steps pass between two merges of close particles.
+const COALESCE_INTERVAL: u32 = 30;
+/// Relaxation added to the denominator of the density constraint so that particles with few
+/// neighbors are not pushed too far.
+const CONSTRAINT_RELAXATION: f32 = 0.1;
 
 /// This a helper structure which references fields from the `Particle` struct.
 /// Using this enables us to parallelize the calculation of densities.
@@ -45,38 +41,68 @@ struct DensityIntermediateReadOnly {
 /// More info at `[DensityIntermediateReadOnly]`
 struct PressureIntermediateReadOnly {
     predicted_position: Vector2<f32>,
+    velocity: Vector2<f32>,
     pressure: f32,
     mass: f32,
     sph_density: f32,
+    charge: f32,
+    viscosity: f32,
     id: u32,
 }
 
 pub struct Sph {
-    pub particles: Vec<Particle>,
+    particles: ParticleStore,
+    /// Slots of the particles by their position.
     pub lookup: LookUp<usize>,
     pub gravity: Vector2<f32>,
+    pub gravity_enabled: bool,
     pub smoothing_radius: f32,
+    pub kernel: KernelKind,
+    /// If true, the neighbors are sorted by their id before their forces are summed.
+    pub deterministic: bool,
+    /// If true, particles closer than `coalesce_distance` are periodically merged together.
+    pub coalesce: bool,
+    pub coalesce_distance: f32,
+    /// How many times per step is the density constraint solved. Zero turns it off.
+    pub incompressible_iterations: u8,
+    /// Radius in which neighbors are searched for in the density and pressure calculations.
+    pub neighbor_search_radius: f32,
+    /// Elasticity and friction of the collisions of particles with bodies.
+    pub particle_elasticity: f32,
+    pub particle_friction: f32,
     pressure_base: f32,
     body_collision_base: f32,
 
     // Inner helping stuff
     id_counter: u32,
-    density_intermediates: Vec<DensityIntermediateReadOnly>,
-    pressure_intermediates: Vec<PressureIntermediateReadOnly>,
+    steps_since_coalesce: u32,
+    /// Indexed by the slots of the particles. Empty slots have no intermediate.
+    density_intermediates: Vec<Option<DensityIntermediateReadOnly>>,
+    pressure_intermediates: Vec<Option<PressureIntermediateReadOnly>>,
 }
 
 impl Sph {
     pub fn new(width: f32, height: f32) -> Self {
         let smoothing_radius = 12.0;
         Sph {
-            particles: Vec::new(),
+            particles: ParticleStore::default(),
             lookup: LookUp::new(width, height, smoothing_radius * 2.0),
-            gravity: Vector2::new(0.0, 981.0),
+            gravity: Vector2::new(0.0, EARTH_GRAVITY),
+            gravity_enabled: true,
             smoothing_radius,
+            kernel: KernelKind::Quadratic,
+            deterministic: false,
+            coalesce: false,
+            coalesce_distance: 1.0,
+            incompressible_iterations: 0,
+            neighbor_search_radius: smoothing_radius * 2.0,
+            particle_elasticity: 0.3,
+            particle_friction: 0.0,
             pressure_base: PRESSURE_BASE,
             body_collision_base: BODY_COLLISION_FORCE_BASE,
 
             id_counter: 0,
+            steps_since_coalesce: 0,
             // 1000 chosen as a good starting capacity
             density_intermediates: Vec::with_capacity(1000),
             pressure_intermediates: Vec::with_capacity(1000),
@@ -87,19 +113,56 @@ impl Sph {
         self.particles.len()
     }
 
+    /// Sum of masses of all particles.
+    pub fn total_mass(&self) -> f32 {
+        self.particles.par_iter().map(|p| p.mass()).sum()
+    }
+
+    /// Sum of kinetic energies of all particles.
+    pub fn kinetic_energy(&self) -> f32 {
+        self.particles
+            .par_iter()
+            .map(|p| 0.5 * p.mass() * p.velocity.length_squared())
+            .sum()
+    }
+
+    /// Sum of `mass * velocity` of all particles.
+    pub fn total_momentum(&self) -> Vector2<f32> {
+        self.particles
+            .par_iter()
+            .map(|p| p.velocity * p.mass())
+            .sum()
+    }
+
+    /// Finds the particle with the given `id`, if it is still in the simulation.
+    pub fn particle_by_id(&self, id: u32) -> Option<&Particle> {
+        self.particles.by_id(id)
+    }
+
+    /// Iterates over all particles. The order is the same for simulations that did the same thing.
+    pub fn iter_particles(&self) -> impl Iterator<Item = &Particle> {
+        self.particles.iter()
+    }
+
+    pub fn iter_particles_mut(&mut self) -> impl Iterator<Item = &mut Particle> {
+        self.particles.iter_mut()
+    }
+
     pub fn add_particle(&mut self, mut particle: Particle) {
         let pos = particle.position;
 
         particle.id = self.id_counter;
-        self.particles.push(particle);
         self.id_counter += 1;
+        let slot = self.particles.insert(particle);
 
-        // Insert particles index into lookup
-        let index = self.particles.len() - 1;
-        self.lookup.insert(&pos, index);
+        self.lookup.insert(&pos, slot);
     }
 
     fn add_gravity_force(&mut self) {
+        if !self.gravity_enabled {
+            return;
+        }
+
         self.particles
             .par_iter_mut()
             .for_each(|p| p.add_force(self.gravity * p.mass));
@@ -108,28 +171,33 @@ impl Sph {
     fn calculate_densities(&mut self) {
         // Get readonly fields of the particles needed for density calculation.
         self.particles
+            .slots()
             .par_iter()
-            .map(|p| DensityIntermediateReadOnly {
-                predicted_position: p.predicted_position,
-                mass: p.mass(),
-                id: p.id,
+            .map(|slot| {
+                slot.as_ref().map(|p| DensityIntermediateReadOnly {
+                    predicted_position: p.predicted_position,
+                    mass: p.mass(),
+                    id: p.id,
+                })
             })
             .collect_into_vec(&mut self.density_intermediates);
 
         self.particles.par_iter_mut().for_each(|p| {
-            let neighbors = self.lookup.get_immediate_neighbors(&p.predicted_position);
+            let neighbors = self
+                .lookup
+                .get_neighbors_in_radius(&p.predicted_position, self.neighbor_search_radius);
 
             p.sph_density = neighbors
                 .iter()
-                .map(|index| {
-                    let other_inter = &self.density_intermediates[*index];
+                .filter_map(|index| self.density_intermediates[*index].as_ref())
+                .map(|other_inter| {
                     if p.id == other_inter.id {
                         0.0
                     } else {
                         let (other_pos, other_mass) =
                             (other_inter.predicted_position, other_inter.mass);
                         let dist = (p.predicted_position - other_pos).length();
-                        let density = other_mass * kernel(dist, self.smoothing_radius);
+                        let density = other_mass * self.kernel.value(dist, self.smoothing_radius);
                         density
                     }
                 })
@@ -139,13 +207,19 @@ impl Sph {
 
     fn apply_pressures(&mut self) {
         self.particles
+            .slots()
             .par_iter()
-            .map(|p| PressureIntermediateReadOnly {
-                predicted_position: p.predicted_position,
-                pressure: p.pressure() * self.pressure_base,
-                mass: p.mass(),
-                sph_density: p.sph_density,
-                id: p.id,
+            .map(|slot| {
+                slot.as_ref().map(|p| PressureIntermediateReadOnly {
+                    predicted_position: p.predicted_position,
+                    velocity: p.velocity,
+                    pressure: p.pressure() * self.pressure_base,
+                    mass: p.mass(),
+                    sph_density: p.sph_density,
+                    charge: p.charge,
+                    viscosity: p.viscosity,
+                    id: p.id,
+                })
             })
             .collect_into_vec(&mut self.pressure_intermediates);
 
@@ -153,31 +227,65 @@ impl Sph {
             let pos = p.predicted_position;
             let pressure = p.pressure() * self.pressure_base;
 
-            let neighbors = self.lookup.get_immediate_neighbors(&pos);
-            let pressure_force: Vector2<f32> = neighbors
-                .iter()
-                .map(|index| {
-                    let other_inter = &self.pressure_intermediates[*index];
-
-                    if other_inter.sph_density == 0.0 || p.id == other_inter.id {
+            let neighbors = self
+                .lookup
+                .get_neighbors_in_radius(&pos, self.neighbor_search_radius);
+            let force_from = |other_inter: &PressureIntermediateReadOnly| {
+                if other_inter.sph_density == 0.0 || p.id == other_inter.id {
+                    Vector2::zero()
+                } else {
+                    let other_pressure = other_inter.pressure;
+                    let pos_diff = other_inter.predicted_position - pos;
+
+                    let dir = if pos_diff.is_zero() {
+                        if self.deterministic {
+                            // Push the particles apart along the X axis based on their order
+                            if p.id < other_inter.id {
+                                Vector2::new(1.0, 0.0)
+                            } else {
+                                Vector2::new(-1.0, 0.0)
+                            }
+                        } else {
+                            Vector2::<f32>::random_unit()
+                        }
+                    } else {
+                        pos_diff.normalized()
+                    };
+                    let dist = pos_diff.length();
+                    let shared_pressure = (pressure + other_pressure)
+                        / (2.0 * other_inter.sph_density)
+                        * self.kernel.derivative(dist, self.smoothing_radius);
+                    let pressure_force = dir * other_inter.mass * shared_pressure;
+
+                    // Coulomb-like force - like charges push away from the other particle
+                    let charge_product = p.charge * other_inter.charge;
+                    let charge_force = if charge_product == 0.0 {
                         Vector2::zero()
                     } else {
-                        let other_pressure = other_inter.pressure;
-                        let pos_diff = other_inter.predicted_position - pos;
-
-                        let dir = if pos_diff.is_zero() {
-                            Vector2::<f32>::random_unit()
-                        } else {
-                            pos_diff.normalized()
-                        };
-                        let dist = pos_diff.length();
-                        let shared_pressure = (pressure + other_pressure)
-                            / (2.0 * other_inter.sph_density)
-                            * kernel_derivative(dist, self.smoothing_radius);
-                        dir * other_inter.mass * shared_pressure
-                    }
-                })
-                .sum();
+                        let dist = dist.max(MIN_CHARGE_DISTANCE);
+                        dir * (-CHARGE_FORCE_BASE * charge_product / (dist * dist))
+                    };
+
+                    // Viscosity pulls the velocities of the two particles together. Fluids of
+                    // different thickness meet in the middle.
+                    let viscosity = (p.viscosity + other_inter.viscosity) * 0.5;
+                    let viscosity_force = (other_inter.velocity - p.velocity)
+                        * (viscosity * p.mass * other_inter.mass / other_inter.sph_density
+                            * self.kernel.value(dist, self.smoothing_radius));
+
+                    pressure_force + charge_force + viscosity_force
+                }
+            };
+            let others = neighbors
+                .iter()
+                .filter_map(|index| self.pressure_intermediates[*index].as_ref());
+            let pressure_force: Vector2<f32> = if self.deterministic {
+                let mut sorted: Vec<&PressureIntermediateReadOnly> = others.collect();
+                sorted.sort_unstable_by_key(|other_inter| other_inter.id);
+                sorted.into_iter().map(force_from).sum()
+            } else {
+                others.map(force_from).sum()
+            };
 
             p.add_force(pressure_force);
         });
@@ -191,6 +299,10 @@ impl Sph {
     ) -> Vec<(usize, BodyForceAccumulation)> {
         let mut body_forces = Vec::with_capacity(bodies.len());
         for (index, body) in bodies.iter().enumerate() {
+            if !body.state().collides_with_fluid {
+                continue;
+            }
+
             let force_accumulation = self
                 .particles
                 .par_iter_mut()
@@ -201,13 +313,25 @@ impl Sph {
                         BodyBehaviour::Dynamic,
                     );
 
-                    if let Some(collision_data) = RigidBody::check_collision(body, &circle) {
-                        let elasticity = 0.3;
-                        let impulse = -(1.0 + elasticity) * p.velocity.dot(collision_data.normal);
+                    // Particles touch the bodies directly, without any gap
+                    if let Some(collision_data) = RigidBody::check_collision(body, &circle, 0.0) {
+                        let normal = collision_data.normal;
+                        let impulse = -(1.0 + self.particle_elasticity) * p.velocity.dot(normal);
                         let impulse = impulse / (1.0 / p.mass() + 1.0 / body.state().mass());
 
-                        p.velocity += collision_data.normal * (impulse / p.mass());
-                        p.position += collision_data.normal * collision_data.penetration;
+                        let normal_change = impulse / p.mass();
+                        p.velocity += normal * normal_change;
+                        p.position += normal * collision_data.penetration;
+
+                        // Friction slows the sliding along the surface by at most the friction
+                        // times the change of the velocity along the normal
+                        let tangent_velocity = p.velocity - normal * p.velocity.dot(normal);
+                        let tangent_speed = tangent_velocity.length();
+                        if tangent_speed > 0.0 {
+                            let slowdown =
+                                (self.particle_friction * normal_change.abs()).min(tangent_speed);
+                            p.velocity -= tangent_velocity * (slowdown / tangent_speed);
+                        }
 
                         // Calculate force on body only for non-static bodies
                         if body.state().behaviour != BodyBehaviour::Static {
@@ -243,10 +367,11 @@ impl Sph {
 
     fn setup_lookup(&mut self) {
         self.lookup.clear();
-        for index in 0..self.particles.len() {
-            self.lookup
-                .insert(&self.particles[index].predicted_position, index);
+        for (slot, p) in self.particles.iter_slots() {
+            self.lookup.insert(&p.predicted_position, slot);
         }
+        // Nothing refers to the removed particles anymore
+        self.particles.release_freed();
     }
 
     /// Performs a step of the fluid simulation.
@@ -260,9 +385,18 @@ impl Sph {
     ) -> Vec<(usize, BodyForceAccumulation)> {
         self.setup_lookup();
 
-        self.gravity = config.gravity;
+        self.gravity = from_cm(config.gravity);
+        self.gravity_enabled = config.gravity_enabled;
         self.pressure_base = config.sph_config.base_pressure;
         self.body_collision_base = config.sph_config.base_body_force;
+        self.kernel = *config.sph_config.kernel.get_value();
+        self.deterministic = config.sph_config.deterministic;
+        self.coalesce = config.sph_config.coalesce;
+        self.coalesce_distance = from_cm(config.sph_config.coalesce_distance);
+        self.incompressible_iterations = config.sph_config.incompressible_iterations;
+        self.neighbor_search_radius = from_cm(config.sph_config.neighbor_search_radius.get_value());
+        self.particle_elasticity = config.sph_config.particle_elasticity.get_value();
+        self.particle_friction = config.sph_config.particle_friction.get_value();
 
         self.particles
             .par_iter_mut()
@@ -276,9 +410,160 @@ impl Sph {
             p.apply_accumulated_force(dt);
             p.move_by_velocity(dt);
         });
+        if self.incompressible_iterations > 0 {
+            self.solve_density_constraints(dt);
+        }
 
         // Do collision detection and resolution
-        self.resolve_collisions(bodies)
+        let forces = self.resolve_collisions(bodies);
+
+        self.steps_since_coalesce += 1;
+        if self.coalesce && self.steps_since_coalesce >= COALESCE_INTERVAL {
+            self.coalesce(self.coalesce_distance);
+            self.steps_since_coalesce = 0;
+        }
+
+        forces
+    }
+
+    /// Moves the particles apart where the fluid is denser than its target density and updates their
+    /// velocities to match the movement. Based on Position Based Fluids by Macklin and Müller.
+    /// Only compression is corrected, so particles on the surface are not pulled together.
+    fn solve_density_constraints(&mut self, dt: f32) {
+        for _ in 0..self.incompressible_iterations {
+            // The lookup has to reflect the corrected positions
+            self.particles
+                .par_iter_mut()
+                .for_each(|p| p.predicted_position = p.position);
+            self.setup_lookup();
+
+            // Gradient of the kernel at `pos` caused by a particle at `other_pos`
+            let gradient = |pos: Vector2<f32>, other_pos: Vector2<f32>| {
+                let diff = pos - other_pos;
+                let dist = diff.length();
+                if dist == 0.0 {
+                    Vector2::zero()
+                } else {
+                    diff / dist * self.kernel.derivative(dist, self.smoothing_radius)
+                }
+            };
+
+            // Indexed by the slots of the particles
+            let lambdas: Vec<f32> = self
+                .particles
+                .slots()
+                .par_iter()
+                .map(|slot| {
+                    let Some(p) = slot else {
+                        return 0.0;
+                    };
+                    let mut density = 0.0;
+                    let mut own_gradient = Vector2::zero();
+                    let mut gradient_sum = 0.0;
+                    for index in self.lookup.get_immediate_neighbors(&p.position).iter() {
+                        let Some(other) = self.particles.get(*index) else {
+                            continue;
+                        };
+                        if other.id == p.id {
+                            continue;
+                        }
+
+                        let dist = (p.position - other.position).length();
+                        density += other.mass * self.kernel.value(dist, self.smoothing_radius);
+                        let other_gradient =
+                            gradient(p.position, other.position) * (other.mass / p.target_density);
+                        own_gradient += other_gradient;
+                        gradient_sum += other_gradient.length_squared();
+                    }
+
+                    let constraint = (density / p.target_density - 1.0).max(0.0);
+                    gradient_sum += own_gradient.length_squared();
+                    -constraint / (gradient_sum + CONSTRAINT_RELAXATION)
+                })
+                .collect();
+
+            let corrections: Vec<Vector2<f32>> = self
+                .particles
+                .slots()
+                .par_iter()
+                .enumerate()
+                .map(|(index, slot)| {
+                    let Some(p) = slot else {
+                        return Vector2::zero();
+                    };
+                    self.lookup
+                        .get_immediate_neighbors(&p.position)
+                        .iter()
+                        .filter(|other_index| **other_index != index)
+                        .filter_map(|other_index| {
+                            let other = self.particles.get(*other_index)?;
+                            Some(
+                                gradient(p.position, other.position)
+                                    * ((lambdas[index] + lambdas[*other_index]) * other.mass
+                                        / p.target_density),
+                            )
+                        })
+                        .sum()
+                })
+                .collect();
+
+            self.particles
+                .par_iter_slots_mut()
+                .for_each(|(slot, p)| p.position += corrections[slot]);
+        }
+
+        self.particles.par_iter_mut().for_each(|p| {
+            p.velocity = (p.position - p.previous_position) / dt;
+            p.predicted_position = p.position;
+        });
+        self.setup_lookup();
+    }
+
+    /// Merges pairs of particles of the same fluid that are closer than `min_dist` into one.
+    /// Mass and momentum of the fluid are conserved. Each particle is merged at most once per call.
+    pub fn coalesce(&mut self, min_dist: f32) {
+        self.lookup.clear();
+        for (slot, particle) in self.particles.iter_slots() {
+            self.lookup.insert(&particle.position, slot);
+        }
+
+        let min_dist_squared = min_dist * min_dist;
+        // Particles that already took part in a merge in this call
+        let mut is_used = vec![false; self.particles.slot_count()];
+        for index in 0..self.particles.slot_count() {
+            let Some(particle) = self.particles.get(index) else {
+                continue;
+            };
+            if is_used[index] {
+                continue;
+            }
+
+            let partner = self
+                .lookup
+                .get_neighbors_in_radius(&particle.position, min_dist)
+                .iter()
+                .copied()
+                .find(|&other_index| {
+                    let Some(other) = self.particles.get(other_index) else {
+                        return false;
+                    };
+                    other_index != index
+                        && !is_used[other_index]
+                        && particle.is_same_fluid(other)
+                        && (other.position - particle.position).length_squared() < min_dist_squared
+                });
+
+            if let Some(other_index) = partner {
+                is_used[index] = true;
+                is_used[other_index] = true;
+                // The merged particles keep their slots, so the lookup stays valid
+                if let Some(other) = self.particles.remove(other_index) {
+                    if let Some(particle) = self.particles.get_mut(index) {
+                        particle.absorb(&other);
+                    }
+                }
+            }
+        }
     }
 
     pub fn get_particles_around_position(
@@ -290,14 +575,278 @@ impl Sph {
 
         neighbors
             .iter()
-            .map(|index| &self.particles[*index])
+            .filter_map(|index| self.particles.get(*index))
+            .collect()
+    }
+
+    /// Returns the average velocity of the particles around `position`, weighted by the smoothing
+    /// kernel. Returns zero if there is no fluid nearby.
+    pub fn velocity_at(&self, position: Vector2<f32>) -> Vector2<f32> {
+        // A radius smaller than a cell would only search the cell of `position`. The kernel is zero
+        // for the particles outside of the smoothing radius.
+        let (velocity_sum, weight_sum) = self
+            .get_particles_around_position(position, self.lookup.cell_size)
+            .iter()
+            .fold((Vector2::zero(), 0.0), |(velocity_sum, weight_sum), p| {
+                let dist = (p.position - position).length();
+                let weight = self.kernel.value(dist, self.smoothing_radius);
+                (velocity_sum + p.velocity * weight, weight_sum + weight)
+            });
+
+        if weight_sum > 0.0 {
+            velocity_sum / weight_sum
+        } else {
+            Vector2::zero()
+        }
+    }
+
+    /// Pulls the velocity of particles within `radius` around `position` towards `velocity`.
+    /// Particles closer to the `position` are affected more.
+    pub fn stir(&mut self, position: Vector2<f32>, radius: f32, velocity: Vector2<f32>) {
+        let neighbors = self.lookup.get_neighbors_in_radius(&position, radius);
+
+        for index in neighbors.iter() {
+            let Some(particle) = self.particles.get_mut(*index) else {
+                continue;
+            };
+            let distance = (particle.position - position).length();
+            if distance > radius {
+                continue;
+            }
+
+            let proximity = 1.0 - distance / radius;
+            particle.velocity += (velocity - particle.velocity) * proximity;
+        }
+    }
+
+    /// Removes the particles inside the box given by its minimum and maximum corner and returns
+    /// them.
+    pub fn take_particles_in_region(
+        &mut self,
+        min: Vector2<f32>,
+        max: Vector2<f32>,
+    ) -> Vec<Particle> {
+        self.remove_particles_where(|p| {
+            let pos = p.position;
+            pos.x >= min.x && pos.x <= max.x && pos.y >= min.y && pos.y <= max.y
+        })
+    }
+
+    /// Removes all particles for which `should_remove` returns true and returns them.
+    /// `should_remove` is called once for each particle in order. The kept particles stay in their
+    /// slots, so the lookup does not have to be rebuilt.
+    pub fn remove_particles_where(
+        &mut self,
+        mut should_remove: impl FnMut(&Particle) -> bool,
+    ) -> Vec<Particle> {
+        let slots: Vec<usize> = self
+            .particles
+            .iter_slots()
+            .filter(|(_, p)| should_remove(p))
+            .map(|(slot, _)| slot)
+            .collect();
+
+        slots
+            .into_iter()
+            .filter_map(|slot| self.particles.remove(slot))
             .collect()
     }
 
+    /// Changes the size of the area in which the particles can find their neighbors. Should be
+    /// called whenever the world changes its size.
+    pub fn resize(&mut self, width: f32, height: f32) {
+        self.lookup.resize(width, height);
+        self.setup_lookup();
+    }
+
+    /// Replaces all particles with `particles` that were taken from this simulation earlier. Unlike
+    /// `add_particle` their ids are kept, so ids held elsewhere still find the same particles.
+    pub fn restore_particles(&mut self, particles: Vec<Particle>) {
+        self.clear_all_particles();
+        for particle in particles {
+            let pos = particle.position;
+            let slot = self.particles.insert(particle);
+            self.lookup.insert(&pos, slot);
+        }
+    }
+
     /// Clears all particles = deletes all fluid in simulation
     pub fn clear_all_particles(&mut self) {
         self.particles.clear();
         self.lookup.clear();
-        self.id_counter = 0;
+        // The ids are not reused, so that an id of a removed particle does not find a new one
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Sph;
+    use crate::game::GameConfig;
+    use crate::math::{v2, Vector2};
+    use crate::physics::rigidbody::{BodyBehaviour, RigidBody};
+    use crate::physics::sph::Particle;
+    use crate::rendering::Color;
+
+    #[test]
+    fn coalesce_conserves_mass_and_momentum() {
+        let mut sph = Sph::new(100.0, 100.0);
+        sph.add_particle(Particle::new(v2!(10.0, 10.0)).with_velocity(v2!(1.0, 0.0)));
+        sph.add_particle(Particle::new(v2!(10.5, 10.0)).with_velocity(v2!(-2.0, 4.0)));
+        // Close but of a different fluid
+        sph.add_particle(Particle::new(v2!(10.0, 10.3)).with_color(Color::rgb(255, 0, 0)));
+        // Too far away
+        sph.add_particle(Particle::new(v2!(50.0, 50.0)).with_velocity(v2!(0.0, 5.0)));
+        let mass = sph.total_mass();
+        let momentum = sph.total_momentum();
+
+        sph.coalesce(1.0);
+
+        assert_eq!(sph.particle_count(), 3);
+        assert_eq!(sph.total_mass(), mass);
+        assert!((sph.total_momentum() - momentum).length() < 1e-5);
+        let merged = sph.particle_by_id(0).unwrap();
+        assert_eq!(merged.mass(), 2.0);
+        assert!((merged.position - v2!(10.25, 10.0)).length() < 1e-5);
+    }
+
+    #[test]
+    fn removed_particles_keep_the_rest_findable() {
+        let mut sph = Sph::new(100.0, 100.0);
+        for i in 0..6 {
+            sph.add_particle(Particle::new(v2!(10.0 + 10.0 * i as f32, 50.0)));
+        }
+
+        let removed = sph.remove_particles_where(|p| p.id() % 2 == 0);
+
+        assert_eq!(removed.len(), 3);
+        assert!(sph.particle_by_id(2).is_none());
+        assert_eq!(sph.particle_by_id(3).unwrap().position, v2!(40.0, 50.0));
+        let around = sph.get_particles_around_position(v2!(60.0, 50.0), 1.0);
+        assert!(around.iter().all(|p| p.id() % 2 == 1));
+
+        // A particle added before the lookup is rebuilt is not found through a removed one
+        sph.add_particle(Particle::new(v2!(90.0, 90.0)));
+        let around = sph.get_particles_around_position(v2!(30.0, 50.0), 0.0);
+        assert!(around.iter().all(|p| p.id() != 6));
+        assert_eq!(sph.particle_by_id(6).unwrap().position, v2!(90.0, 90.0));
+        assert_eq!(sph.particle_count(), 4);
+    }
+
+    #[test]
+    fn restored_particles_keep_their_ids() {
+        let mut sph = Sph::new(100.0, 100.0);
+        for i in 0..4 {
+            sph.add_particle(Particle::new(v2!(10.0 + 10.0 * i as f32, 50.0)));
+        }
+        let snapshot: Vec<Particle> = sph.iter_particles().cloned().collect();
+        sph.remove_particles_where(|p| p.id() < 2);
+        sph.add_particle(Particle::new(v2!(90.0, 90.0)));
+
+        sph.restore_particles(snapshot);
+
+        assert_eq!(sph.particle_count(), 4);
+        assert_eq!(sph.particle_by_id(1).unwrap().position, v2!(20.0, 50.0));
+        assert!(sph.particle_by_id(4).is_none());
+        // New particles do not take the ids of the restored ones
+        sph.add_particle(Particle::new(v2!(90.0, 90.0)));
+        assert_eq!(sph.particle_by_id(5).unwrap().position, v2!(90.0, 90.0));
+    }
+
+    #[test]
+    fn velocity_at_averages_nearby_particles() {
+        let mut sph = Sph::new(100.0, 100.0);
+        sph.add_particle(Particle::new(v2!(45.0, 50.0)).with_velocity(v2!(10.0, 0.0)));
+        sph.add_particle(Particle::new(v2!(55.0, 50.0)).with_velocity(v2!(0.0, 10.0)));
+
+        // Both particles are equally far away
+        let velocity = sph.velocity_at(v2!(50.0, 50.0));
+        assert!((velocity - v2!(5.0, 5.0)).length() < 1e-5);
+        // There is no fluid
+        assert!(sph.velocity_at(v2!(10.0, 90.0)).is_zero());
+    }
+
+    /// Steps 2 particles sliding past each other once and returns their relative speed afterwards.
+    fn relative_speed_after_step(viscosity: f32) -> f32 {
+        let mut sph = Sph::new(100.0, 100.0);
+        sph.add_particle(
+            Particle::new(v2!(48.0, 50.0))
+                .with_velocity(v2!(0.0, 10.0))
+                .with_viscosity(viscosity),
+        );
+        sph.add_particle(Particle::new(v2!(52.0, 50.0)).with_velocity(v2!(0.0, -10.0)));
+        // Only the viscosity acts on the particles
+        let mut config = GameConfig {
+            gravity_enabled: false,
+            ..Default::default()
+        };
+        config.sph_config.base_pressure = 0.0;
+
+        sph.step(&vec![], &config, 0.005);
+
+        let velocity_y = |id: u32| sph.particle_by_id(id).unwrap().velocity.y;
+        (velocity_y(0) - velocity_y(1)).abs()
+    }
+
+    #[test]
+    fn viscosity_slows_sliding_particles() {
+        // Only one of the particles is viscous, but both are slowed by the average
+        assert!(relative_speed_after_step(20.0) < relative_speed_after_step(0.0));
+    }
+
+    /// Lets a particle hit a static floor at an angle and returns its velocity after the collision.
+    fn velocity_after_floor_hit(friction: f32) -> Vector2<f32> {
+        let mut sph = Sph::new(100.0, 100.0);
+        sph.particle_friction = friction;
+        sph.add_particle(Particle::new(v2!(50.0, 46.0)).with_velocity(v2!(10.0, 10.0)));
+        let floor = Rectangle!(v2!(50.0, 55.0); 100.0, 10.0; BodyBehaviour::Static);
+
+        sph.resolve_collisions(&vec![floor]);
+
+        sph.particle_by_id(0).unwrap().velocity
+    }
+
+    #[test]
+    fn friction_slows_fluid_sliding_on_bodies() {
+        let slippery = velocity_after_floor_hit(0.0);
+        let sticky = velocity_after_floor_hit(0.5);
+
+        assert!(slippery.y < 0.0 && sticky.y < 0.0);
+        assert!(sticky.x < slippery.x);
+        assert!(sticky.x >= 0.0);
+    }
+
+    /// Steps a tightly packed block of fluid once and returns the highest density in it afterwards.
+    fn max_density_after_step(incompressible_iterations: u8) -> f32 {
+        let mut sph = Sph::new(200.0, 200.0);
+        for i in 0..6 {
+            for j in 0..6 {
+                sph.add_particle(Particle::new(v2!(
+                    90.0 + i as f32 * 2.0,
+                    90.0 + j as f32 * 2.0
+                )));
+            }
+        }
+        // Only the density constraints push the particles apart
+        let mut config = GameConfig {
+            gravity_enabled: false,
+            ..Default::default()
+        };
+        config.sph_config.base_pressure = 0.0;
+        config.sph_config.incompressible_iterations = incompressible_iterations;
+
+        sph.step(&vec![], &config, 0.005);
+
+        sph.iter_particles_mut()
+            .for_each(|p| p.predicted_position = p.position);
+        sph.setup_lookup();
+        sph.calculate_densities();
+        sph.iter_particles()
+            .map(|p| p.sph_density)
+            .fold(0.0, f32::max)
+    }
+
+    #[test]
+    fn density_constraints_decompress_fluid() {
+        assert!(max_density_after_step(4) < max_density_after_step(0));
     }
 }
@@ -0,0 +1,33 @@
+//! The physical scale of the world. The simulations work in world units, one of which is drawn
+//! as one pixel of the gameview. Values shown to or entered by the user are in centimeters and
+//! are converted with the functions below.
+//!
+//! Changing `CM_PER_UNIT` does not rescale everything. The smoothing radius of the fluid is fixed
+//! in world units, so the fluid keeps its resolution in pixels and behaves differently at another
+//! scale.
+
+use std::ops::Mul;
+
+/// How many centimeters one world unit represents.
+pub const CM_PER_UNIT: f32 = 1.0;
+/// Gravitational acceleration on the surface of the Earth in cm/s^2.
+pub const EARTH_GRAVITY_CM: f32 = 981.0;
+/// Gravitational acceleration on the surface of the Earth in world units per second squared.
+pub const EARTH_GRAVITY: f32 = EARTH_GRAVITY_CM / CM_PER_UNIT;
+
+/// Converts a length, or a quantity proportional to it such as velocity, from world units to
+/// centimeters.
+pub fn to_cm<T: Mul<f32, Output = T>>(value: T) -> T {
+    value * CM_PER_UNIT
+}
+
+/// Converts a length, or a quantity proportional to it, from centimeters to world units.
+pub fn from_cm<T: Mul<f32, Output = T>>(value: T) -> T {
+    value * (1.0 / CM_PER_UNIT)
+}
+
+/// Converts a quantity proportional to a squared length, such as area or kinetic energy, from
+/// world units to centimeters.
+pub fn to_cm2<T: Mul<f32, Output = T>>(value: T) -> T {
+    value * (CM_PER_UNIT * CM_PER_UNIT)
+}
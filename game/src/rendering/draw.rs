@@ -1,20 +1,21 @@
-use macroquad::shapes::{draw_circle, draw_line, draw_triangle};
+use std::collections::VecDeque;
+
+use macroquad::shapes::{
+    draw_circle, draw_line, draw_poly, draw_poly_lines, draw_triangle, draw_triangle_lines,
+};
 
 use super::Color;
 use crate::{
+    math::{v2, Vector2},
     physics::rigidbody::RigidBody,
-    shapes::{Line, Triangle, Triangulation},
+    shapes::{Triangle, Triangulation},
     utility::AsMq,
 };
 
-/// Implementors of this trait have the ability to be drawn to the screen.
-pub trait Draw {
-    fn draw(&self);
-
-    fn draw_with_color(&self, color: Color);
-}
-
-const BLACK: Color = Color::rgb(0, 0, 0);
+/// How much darker the outline of a body is compared to its fill.
+const OUTLINE_DARKEN: f32 = 0.4;
+/// Fewer segments would not make a closed shape.
+const MIN_CIRCLE_SEGMENTS: u8 = 3;
 
 pub fn draw_triangulation(triangulation: &Triangulation, color: Color) {
     for Triangle { a, b, c } in triangulation {
@@ -22,44 +23,141 @@ pub fn draw_triangulation(triangulation: &Triangulation, color: Color) {
     }
 }
 
-impl Draw for Line {
-    fn draw(&self) {
-        self.draw_with_color(BLACK);
+/// Draws only the edges of each triangle so that missing or degenerate triangles are visible.
+pub fn draw_triangulation_edges(triangulation: &Triangulation, color: Color) {
+    for Triangle { a, b, c } in triangulation {
+        draw_triangle_lines(a.as_mq(), b.as_mq(), c.as_mq(), 1.0, color.as_mq());
     }
+}
 
-    fn draw_with_color(&self, color: Color) {
-        draw_line(
-            self.start.x,
-            self.start.y,
-            self.end.x,
-            self.end.y,
-            2.0,
-            color.as_mq(),
-        );
+/// How are rigidbodies drawn.
+#[derive(Clone, Copy, PartialEq)]
+pub enum RenderStyle {
+    Filled,
+    /// Only the outline, which makes overlapping bodies visible.
+    Wireframe,
+    FilledWithOutline,
+}
+
+/// Draws the body in the given `style`. `thickness` is the thickness of the outline and circles
+/// are drawn with `circle_segments` segments.
+pub fn draw_styled(body: &RigidBody, style: RenderStyle, thickness: f32, circle_segments: u8) {
+    draw_styled_with_color(body, body.state().color, style, thickness, circle_segments);
+}
+
+/// Same as `draw_styled` but with `color` instead of the color of the body.
+pub fn draw_styled_with_color(
+    body: &RigidBody,
+    color: Color,
+    style: RenderStyle,
+    thickness: f32,
+    circle_segments: u8,
+) {
+    match style {
+        RenderStyle::Filled => fill_body(body, color, circle_segments),
+        // The wireframe is the only thing drawn so it has to be visible
+        RenderStyle::Wireframe => {
+            draw_outline_with_color(body, color, thickness.max(1.0), circle_segments)
+        }
+        RenderStyle::FilledWithOutline => {
+            fill_body(body, color, circle_segments);
+            draw_outline(body, color, thickness, circle_segments);
+        }
     }
 }
 
-impl Draw for RigidBody {
-    fn draw(&self) {
-        match self {
-            Self::Polygon(inner) => {
-                draw_triangulation(inner.global_triangulation(), self.state().color)
-            }
-            Self::Circle(inner) => {
-                let position = self.state().position;
-                let color = self.state().color;
-                draw_circle(position.x, position.y, inner.radius, color.as_mq());
-            }
+/// Draws the inside of the body in the given `color`.
+fn fill_body(body: &RigidBody, color: Color, circle_segments: u8) {
+    match body {
+        RigidBody::Polygon(inner) => draw_triangulation(inner.global_triangulation(), color),
+        RigidBody::Circle(inner) => {
+            let position = body.state().position;
+            let sides = circle_segments.max(MIN_CIRCLE_SEGMENTS);
+            draw_poly(
+                position.x,
+                position.y,
+                sides,
+                inner.radius,
+                0.0,
+                color.as_mq(),
+            );
         }
     }
+}
+
+/// Draws the outline of the body in a darker shade of its fill `color`.
+/// Nothing is drawn if `thickness` is not positive.
+pub fn draw_outline(body: &RigidBody, color: Color, thickness: f32, circle_segments: u8) {
+    draw_outline_with_color(
+        body,
+        color.darker(OUTLINE_DARKEN),
+        thickness,
+        circle_segments,
+    );
+}
 
-    fn draw_with_color(&self, color: Color) {
-        match self {
-            Self::Polygon(inner) => draw_triangulation(inner.global_triangulation(), color),
-            Self::Circle(inner) => {
-                let position = self.state().position;
-                draw_circle(position.x, position.y, inner.radius, color.as_mq());
+/// Draws the outline of the body in exactly the `outline` color.
+/// Nothing is drawn if `thickness` is not positive.
+pub fn draw_outline_with_color(
+    body: &RigidBody,
+    outline: Color,
+    thickness: f32,
+    circle_segments: u8,
+) {
+    if thickness <= 0.0 {
+        return;
+    }
+
+    let outline = outline.as_mq();
+    match body {
+        RigidBody::Polygon(inner) => {
+            for line in inner.global_lines() {
+                draw_line(
+                    line.start.x,
+                    line.start.y,
+                    line.end.x,
+                    line.end.y,
+                    thickness,
+                    outline,
+                );
+                // Round the joints so thick outlines do not have notches in the corners
+                draw_circle(line.start.x, line.start.y, thickness * 0.5, outline);
             }
         }
+        RigidBody::Circle(inner) => {
+            let position = body.state().position;
+            let sides = circle_segments.max(MIN_CIRCLE_SEGMENTS);
+            draw_poly_lines(
+                position.x,
+                position.y,
+                sides,
+                inner.radius,
+                0.0,
+                thickness,
+                outline,
+            );
+        }
+    }
+}
+
+/// Draws a line from the center of the body in the direction of its orientation.
+pub fn draw_orientation_indicator(body: &RigidBody, length: f32) {
+    let state = body.state();
+    let direction = v2!(state.orientation.cos(), state.orientation.sin());
+    let start = body.center_of_mass();
+    let end = start + direction * length;
+    let color = state.color.darker(OUTLINE_DARKEN);
+
+    draw_line(start.x, start.y, end.x, end.y, 2.0, color.as_mq());
+    draw_circle(start.x, start.y, 3.0, color.as_mq());
+}
+
+/// Draws the points as a connected line which fades out towards the oldest (first) point.
+pub fn draw_trail(trail: &VecDeque<Vector2<f32>>, color: Color) {
+    let count = trail.len() as f32;
+    for (index, (start, end)) in trail.iter().zip(trail.iter().skip(1)).enumerate() {
+        let mut color = color;
+        color.a = (index + 1) as f32 / count;
+        draw_line(start.x, start.y, end.x, end.y, 2.0, color.as_mq());
     }
 }
@@ -9,7 +9,17 @@ use macroquad::prelude::*;
 use num_traits::Pow;
 
 use super::renderer::Renderer;
-use super::{Color, SamplePoint};
+use super::{Color, ColorMixMode, FluidColorMode, SamplePoint};
+
+/// Depth of the fluid at which it is drawn the darkest with depth shading on. See
+/// `Particle::depth`.
+const MAX_SHADED_DEPTH: f32 = 3.0;
+/// How much darker is the deepest fluid.
+const DEPTH_DARKEN: f32 = 0.6;
+/// How many fainter copies of a cell are drawn behind it with motion blur on.
+const MOTION_BLUR_COPIES: usize = 3;
+/// The motion blur of a cell reaches at most this many cells far.
+const MAX_MOTION_BLUR_CELLS: f32 = 3.0;
 
 /// Alias for a tuple of 2 Vector2.
 /// They represent the start and end of a line.
@@ -49,6 +59,8 @@ struct AppliedConfiguration {
 }
 
 pub struct MarchingSquaresRenderer {
+    screen_width: usize,
+    screen_height: usize,
     sample_field: Vec<SamplePoint>,
     field_width: usize,
     field_height: usize,
@@ -56,6 +68,19 @@ pub struct MarchingSquaresRenderer {
     influence_radius: f32,
     draw_threshold: f32,
     configurations: [Vec<Line<f32>>; 16],
+    color_mode: FluidColorMode,
+    mix_mode: ColorMixMode,
+    /// Color channels at or below this are not averaged when blending the colors of a cell.
+    blend_threshold: f32,
+    /// The average scalar value of a cell is raised to this to get its alpha. Higher values fade
+    /// the thin edges of the fluid more.
+    alpha_exponent: f32,
+    depth_shading: bool,
+    /// For how many seconds is the fluid exposed. The motion blur of a cell reaches as far as the
+    /// fluid in it moves in that time. Zero turns the blur off.
+    motion_blur: f32,
+    /// Minimum and maximum corner of the visible area.
+    visible_region: (Vector2<f32>, Vector2<f32>),
 }
 
 impl MarchingSquaresRenderer {
@@ -71,6 +96,8 @@ impl MarchingSquaresRenderer {
         let field_height = (screen_height as f32 / step_size) as usize + 1;
 
         Ok(MarchingSquaresRenderer {
+            screen_width,
+            screen_height,
             sample_field: vec![SamplePoint::default(); field_width * field_height],
             field_width,
             field_height,
@@ -78,6 +105,16 @@ impl MarchingSquaresRenderer {
             influence_radius,
             draw_threshold,
             configurations: configurations(),
+            color_mode: FluidColorMode::Particle,
+            mix_mode: ColorMixMode::Average,
+            blend_threshold: 0.2,
+            alpha_exponent: 1.0,
+            depth_shading: false,
+            motion_blur: 0.0,
+            visible_region: (
+                Vector2::zero(),
+                v2!(screen_width as f32, screen_height as f32),
+            ),
         })
     }
 
@@ -165,58 +202,285 @@ impl MarchingSquaresRenderer {
     }
 
     fn get_color_from_corner(&self, i: usize) -> Color {
-        let (top_left, tl_value) = (
+        let (top_left, tl_value, tl_depth) = (
             self.sample_field[i].color,
             self.sample_field[i].scalar_value,
+            self.sample_field[i].depth,
         );
         // We try the rest and always choose the previouse one if it is out of bounds
-        let (top_right, tr_value) = self
+        let (top_right, tr_value, tr_depth) = self
             .sample_field
             .get(i + 1)
-            .map(|s| (s.color, s.scalar_value))
-            .unwrap_or((top_left, tl_value));
-        let (bottom_left, bl_value) = self
+            .map(|s| (s.color, s.scalar_value, s.depth))
+            .unwrap_or((top_left, tl_value, tl_depth));
+        let (bottom_left, bl_value, bl_depth) = self
             .sample_field
             .get(i + self.field_width)
-            .map(|s| (s.color, s.scalar_value))
-            .unwrap_or((top_right, tr_value));
-        let (bottom_right, br_value) = self
+            .map(|s| (s.color, s.scalar_value, s.depth))
+            .unwrap_or((top_right, tr_value, tr_depth));
+        let (bottom_right, br_value, br_depth) = self
             .sample_field
             .get(i + self.field_width + 1)
-            .map(|s| (s.color, s.scalar_value))
-            .unwrap_or((bottom_left, bl_value));
+            .map(|s| (s.color, s.scalar_value, s.depth))
+            .unwrap_or((bottom_left, bl_value, bl_depth));
 
         let average = (tl_value + tr_value + bl_value + br_value) * 0.25;
-        // Average the colors in each corner
-        let r = non_zero_average(
-            &[top_left.r, top_right.r, bottom_left.r, bottom_right.r],
-            0.2,
-        );
-        let g = non_zero_average(
-            &[top_left.g, top_right.g, bottom_left.g, bottom_right.g],
-            0.2,
-        );
-        let b = non_zero_average(
-            &[top_left.b, top_right.b, bottom_left.b, bottom_right.b],
-            0.2,
-        );
-        let a = non_zero_average(
-            &[top_left.a, top_right.a, bottom_left.a, bottom_right.a],
-            0.2,
-        ) * average;
+        let corners = [
+            (top_left, tl_value),
+            (top_right, tr_value),
+            (bottom_left, bl_value),
+            (bottom_right, br_value),
+        ];
+        let channels = |channel: fn(&Color) -> f32| corners.map(|(color, _)| channel(&color));
+        let mix = |channel: fn(&Color) -> f32| match self.mix_mode {
+            // Average the colors in each corner
+            ColorMixMode::Average => non_zero_average(&channels(channel), self.blend_threshold),
+            ColorMixMode::Dominant => {
+                let (dominant, _) = corners
+                    .iter()
+                    .max_by(|a, b| a.1.total_cmp(&b.1))
+                    .expect("There are always 4 corners");
+                channel(dominant)
+            }
+            ColorMixMode::Additive => channels(channel).into_iter().fold(0.0, f32::max),
+        };
+
+        let a = non_zero_average(&channels(|c| c.a), self.blend_threshold)
+            * average.powf(self.alpha_exponent);
+        let color = Color::new(mix(|c| c.r), mix(|c| c.g), mix(|c| c.b), a);
+
+        if self.depth_shading {
+            let depth = (tl_depth + tr_depth + bl_depth + br_depth) * 0.25;
+            let shade = (depth / MAX_SHADED_DEPTH).clamp(0.0, 1.0);
+            // Deeper fluid lets less of what is below it through
+            let a = a + (1.0 - a) * shade;
+            Color { a, ..color }.darker(shade * DEPTH_DARKEN)
+        } else {
+            color
+        }
+    }
 
-        Color::new(r, g, b, a)
+    /// Overwrites the color of each sample point by the magnitude of its pressure relative to the
+    /// highest one in the field.
+    fn color_by_pressure(&mut self) {
+        const LOW: Color = Color::rgb(0, 0, 255);
+        const HIGH: Color = Color::rgb(255, 0, 0);
+
+        let max_pressure = self
+            .sample_field
+            .iter()
+            .fold(0.0f32, |acc, s| acc.max(s.pressure.abs()));
+
+        for sample in &mut self.sample_field {
+            let t = if max_pressure > 0.0 {
+                sample.pressure.abs() / max_pressure
+            } else {
+                0.0
+            };
+            sample.color = LOW.lerp(HIGH, t);
+        }
+    }
+
+    /// Returns true if the cell with top-left corner at `position` overlaps the visible region.
+    fn is_cell_visible(&self, position: Vector2<f32>) -> bool {
+        let (min, max) = self.visible_region;
+        position.x + self.step_size >= min.x
+            && position.x <= max.x
+            && position.y + self.step_size >= min.y
+            && position.y <= max.y
     }
 
     fn local_point(&self, base: Vector2<f32>, offset: Vector2<f32>) -> Vector2<f32> {
         base + offset * self.step_size
     }
+
+    /// How far behind the cell with top-left corner at index `i` does its motion blur reach.
+    fn motion_blur_offset(&self, i: usize) -> Vector2<f32> {
+        let offset = self.sample_field[i].velocity * self.motion_blur;
+        let max_length = self.step_size * MAX_MOTION_BLUR_CELLS;
+        if offset.length_squared() > max_length * max_length {
+            offset.normalized() * max_length
+        } else {
+            offset
+        }
+    }
+
+    /// Draws the cell described by `conf` with its top-left corner at `pos`.
+    fn draw_cell(&self, pos: Vector2<f32>, conf: &AppliedConfiguration, color: Color) {
+        // Special cases - if matched then this will return early
+        // Those are: Empty, Full, Opossite corners
+        match conf.configuration_id {
+            // Empty - draw nothing
+            0b0000 => return,
+            // Full - draw a rectangle
+            0b1111 => {
+                draw_rectangle(pos.x, pos.y, self.step_size, self.step_size, color.as_mq());
+
+                return;
+            }
+            // TL and BR corners
+            0b1010 => {
+                // Top triangle
+                draw_triangle(
+                    pos.as_mq(),
+                    self.local_point(pos, conf.lines[0].0).as_mq(),
+                    self.local_point(pos, conf.lines[0].1).as_mq(),
+                    color.as_mq(),
+                );
+                // Bottom triangle
+                draw_triangle(
+                    self.local_point(pos, v2!(1.0, 1.0)).as_mq(),
+                    self.local_point(pos, conf.lines[1].0).as_mq(),
+                    self.local_point(pos, conf.lines[1].1).as_mq(),
+                    color.as_mq(),
+                );
+
+                return;
+            }
+            // TR and BL corners
+            0b0101 => {
+                // Top triangle
+                draw_triangle(
+                    self.local_point(pos, v2!(1.0, 0.0)).as_mq(),
+                    self.local_point(pos, conf.lines[0].0).as_mq(),
+                    self.local_point(pos, conf.lines[0].1).as_mq(),
+                    color.as_mq(),
+                );
+                // Bottom triangle
+                draw_triangle(
+                    self.local_point(pos, v2!(0.0, 1.0)).as_mq(),
+                    self.local_point(pos, conf.lines[1].0).as_mq(),
+                    self.local_point(pos, conf.lines[1].1).as_mq(),
+                    color.as_mq(),
+                );
+
+                return;
+            }
+            // Single corner active
+            0b1000 => {
+                draw_triangle(
+                    pos.as_mq(),
+                    self.local_point(pos, conf.lines[0].0).as_mq(),
+                    self.local_point(pos, conf.lines[0].1).as_mq(),
+                    color.as_mq(),
+                );
+
+                return;
+            }
+            0b0100 => {
+                draw_triangle(
+                    self.local_point(pos, v2!(1.0, 0.0)).as_mq(),
+                    self.local_point(pos, conf.lines[0].0).as_mq(),
+                    self.local_point(pos, conf.lines[0].1).as_mq(),
+                    color.as_mq(),
+                );
+
+                return;
+            }
+            0b0010 => {
+                draw_triangle(
+                    self.local_point(pos, v2!(1.0, 1.0)).as_mq(),
+                    self.local_point(pos, conf.lines[0].0).as_mq(),
+                    self.local_point(pos, conf.lines[0].1).as_mq(),
+                    color.as_mq(),
+                );
+
+                return;
+            }
+            0b0001 => {
+                draw_triangle(
+                    self.local_point(pos, v2!(0.0, 1.0)).as_mq(),
+                    self.local_point(pos, conf.lines[0].0).as_mq(),
+                    self.local_point(pos, conf.lines[0].1).as_mq(),
+                    color.as_mq(),
+                );
+
+                return;
+            }
+            _ => {}
+        }
+
+        // Other cases - get the points for these and then triangulate
+        let points: &[Vector2<f32>] = match conf.configuration_id {
+            // 2 corners active - horizontal or vertical line
+            0b1100 => &[
+                v2!(0.0, 0.0),
+                v2!(1.0, 0.0),
+                conf.lines[0].1,
+                conf.lines[0].0,
+            ],
+            0b0110 => &[
+                v2!(1.0, 0.0),
+                v2!(1.0, 1.0),
+                conf.lines[0].1,
+                conf.lines[0].0,
+            ],
+            0b0011 => &[
+                v2!(1.0, 1.0),
+                v2!(0.0, 1.0),
+                conf.lines[0].0,
+                conf.lines[0].1,
+            ],
+            0b1001 => &[
+                v2!(0.0, 1.0),
+                v2!(0.0, 0.0),
+                conf.lines[0].0,
+                conf.lines[0].1,
+            ],
+            // 3 corners active
+            0b0111 => &[
+                conf.lines[0].0,
+                conf.lines[0].1,
+                v2!(1.0, 0.0),
+                v2!(1.0, 1.0),
+                v2!(0.0, 1.0),
+            ],
+            0b1011 => &[
+                v2!(0.0, 0.0),
+                conf.lines[0].1,
+                conf.lines[0].0,
+                v2!(1.0, 1.0),
+                v2!(0.0, 1.0),
+            ],
+            // TODO: Maybe try switching the points in line in configuration? for the 2 bellow
+            0b1101 => &[
+                v2!(0.0, 0.0),
+                v2!(1.0, 0.0),
+                conf.lines[0].1,
+                conf.lines[0].0,
+                v2!(0.0, 1.0),
+            ],
+            0b1110 => &[
+                v2!(0.0, 0.0),
+                v2!(1.0, 0.0),
+                v2!(1.0, 1.0),
+                conf.lines[0].1,
+                conf.lines[0].0,
+            ],
+            _ => {
+                panic!("The renderer does not draw all possible configurations! This is a bug!")
+            }
+        };
+
+        // Draw the triangulation
+        for Triangle { a, b, c } in triangulate_convex_polygon(points) {
+            draw_triangle(
+                self.local_point(pos, a).as_mq(),
+                self.local_point(pos, b).as_mq(),
+                self.local_point(pos, c).as_mq(),
+                color.as_mq(),
+            );
+        }
+    }
 }
 
 impl Renderer for MarchingSquaresRenderer {
     fn setup(&mut self, sph: &Sph) {
         let half_step = self.step_size * 0.5;
         for i in 0..(self.field_width * self.field_height) {
+            if !self.is_cell_visible(self.index_to_position(i)) {
+                continue;
+            }
             let pos = self.index_to_position(i) + v2!(half_step, half_step);
 
             let particles = sph.get_particles_around_position(pos, self.influence_radius);
@@ -230,16 +494,22 @@ impl Renderer for MarchingSquaresRenderer {
                     } else {
                         self.influence_radius / dist
                     };
-                    (influence, p.color)
+                    (influence, p.color, p.pressure(), p.depth(), p.velocity)
                 })
-                .fold(SamplePoint::default(), |mut acc, (value, color)| {
-                    acc.scalar_value += value;
-                    acc.color.r += color.r * value;
-                    acc.color.g += color.g * value;
-                    acc.color.b += color.b * value;
-
-                    acc
-                });
+                .fold(
+                    SamplePoint::default(),
+                    |mut acc, (value, color, pressure, depth, velocity)| {
+                        acc.scalar_value += value;
+                        acc.color.r += color.r * value;
+                        acc.color.g += color.g * value;
+                        acc.color.b += color.b * value;
+                        acc.pressure += pressure * value;
+                        acc.depth += depth * value;
+                        acc.velocity += velocity * value;
+
+                        acc
+                    },
+                );
 
             // Get weighted average of the color
             let color = Color::new(
@@ -252,184 +522,89 @@ impl Renderer for MarchingSquaresRenderer {
             self.sample_field[i].color = color;
             self.sample_field[i].scalar_value =
                 (self.sample_field[i].scalar_value + sample.scalar_value) * 0.5;
+            self.sample_field[i].pressure = if sample.scalar_value > 0.0 {
+                sample.pressure / sample.scalar_value
+            } else {
+                0.0
+            };
+            self.sample_field[i].depth = if sample.scalar_value > 0.0 {
+                sample.depth / sample.scalar_value
+            } else {
+                0.0
+            };
+            self.sample_field[i].velocity = if sample.scalar_value > 0.0 {
+                sample.velocity / sample.scalar_value
+            } else {
+                Vector2::zero()
+            };
+        }
+
+        if self.color_mode == FluidColorMode::Pressure {
+            self.color_by_pressure();
+        }
+    }
+
+    fn set_visible_region(&mut self, min: Vector2<f32>, max: Vector2<f32>) {
+        self.visible_region = (min, max);
+    }
+
+    fn set_color_mode(&mut self, color_mode: FluidColorMode) {
+        self.color_mode = color_mode;
+    }
+
+    fn set_color_blending(&mut self, threshold: f32, alpha_exponent: f32) {
+        self.blend_threshold = threshold;
+        self.alpha_exponent = alpha_exponent;
+    }
+
+    fn set_color_mix_mode(&mut self, mix_mode: ColorMixMode) {
+        self.mix_mode = mix_mode;
+    }
+
+    fn set_depth_shading(&mut self, depth_shading: bool) {
+        self.depth_shading = depth_shading;
+    }
+
+    fn set_motion_blur(&mut self, exposure: f32) {
+        self.motion_blur = exposure;
+    }
+
+    /// Reallocates the sample field for the new `step_size`. The influence radius is scaled along
+    /// so the fluid keeps its look.
+    fn set_step_size(&mut self, step_size: f32) {
+        if step_size == self.step_size || step_size <= 0.0 {
+            return;
         }
+
+        self.influence_radius *= step_size / self.step_size;
+        self.step_size = step_size;
+        self.field_width = (self.screen_width as f32 / step_size) as usize + 1;
+        self.field_height = (self.screen_height as f32 / step_size) as usize + 1;
+        self.sample_field = vec![SamplePoint::default(); self.field_width * self.field_height];
     }
 
     fn draw(&self) {
         for i in 0..(self.field_width * self.field_height) {
             let pos = self.index_to_position(i);
-            let conf = self.configuration_from_corner(i);
-
-            // Special cases - if matched then this will jump to next loop iteration
-            // Those are: Empty, Full, Opossite corners
-            match conf.configuration_id {
-                // Empty - draw nothing
-                0b0000 => continue,
-                // Full - draw a rectangle
-                0b1111 => {
-                    draw_rectangle(
-                        pos.x,
-                        pos.y,
-                        self.step_size,
-                        self.step_size,
-                        conf.color.as_mq(),
-                    );
-
-                    continue;
-                }
-                // TL and BR corners
-                0b1010 => {
-                    // Top triangle
-                    draw_triangle(
-                        pos.as_mq(),
-                        self.local_point(pos, conf.lines[0].0).as_mq(),
-                        self.local_point(pos, conf.lines[0].1).as_mq(),
-                        conf.color.as_mq(),
-                    );
-                    // Bottom triangle
-                    draw_triangle(
-                        self.local_point(pos, v2!(1.0, 1.0)).as_mq(),
-                        self.local_point(pos, conf.lines[1].0).as_mq(),
-                        self.local_point(pos, conf.lines[1].1).as_mq(),
-                        conf.color.as_mq(),
-                    );
-
-                    continue;
-                }
-                // TR and BL corners
-                0b0101 => {
-                    // Top triangle
-                    draw_triangle(
-                        self.local_point(pos, v2!(1.0, 0.0)).as_mq(),
-                        self.local_point(pos, conf.lines[0].0).as_mq(),
-                        self.local_point(pos, conf.lines[0].1).as_mq(),
-                        conf.color.as_mq(),
-                    );
-                    // Bottom triangle
-                    draw_triangle(
-                        self.local_point(pos, v2!(0.0, 1.0)).as_mq(),
-                        self.local_point(pos, conf.lines[1].0).as_mq(),
-                        self.local_point(pos, conf.lines[1].1).as_mq(),
-                        conf.color.as_mq(),
-                    );
-
-                    continue;
-                }
-                // Single corner active
-                0b1000 => {
-                    draw_triangle(
-                        pos.as_mq(),
-                        self.local_point(pos, conf.lines[0].0).as_mq(),
-                        self.local_point(pos, conf.lines[0].1).as_mq(),
-                        conf.color.as_mq(),
-                    );
-
-                    continue;
-                }
-                0b0100 => {
-                    draw_triangle(
-                        self.local_point(pos, v2!(1.0, 0.0)).as_mq(),
-                        self.local_point(pos, conf.lines[0].0).as_mq(),
-                        self.local_point(pos, conf.lines[0].1).as_mq(),
-                        conf.color.as_mq(),
-                    );
-
-                    continue;
-                }
-                0b0010 => {
-                    draw_triangle(
-                        self.local_point(pos, v2!(1.0, 1.0)).as_mq(),
-                        self.local_point(pos, conf.lines[0].0).as_mq(),
-                        self.local_point(pos, conf.lines[0].1).as_mq(),
-                        conf.color.as_mq(),
-                    );
-
-                    continue;
-                }
-                0b0001 => {
-                    draw_triangle(
-                        self.local_point(pos, v2!(0.0, 1.0)).as_mq(),
-                        self.local_point(pos, conf.lines[0].0).as_mq(),
-                        self.local_point(pos, conf.lines[0].1).as_mq(),
-                        conf.color.as_mq(),
-                    );
-
-                    continue;
-                }
-                _ => {}
+            if !self.is_cell_visible(pos) {
+                continue;
             }
+            let conf = self.configuration_from_corner(i);
 
-            // Other cases - get the points for these and then triangulate
-            let points: &[Vector2<f32>] = match conf.configuration_id {
-                // 2 corners active - horizontal or vertical line
-                0b1100 => &[
-                    v2!(0.0, 0.0),
-                    v2!(1.0, 0.0),
-                    conf.lines[0].1,
-                    conf.lines[0].0,
-                ],
-                0b0110 => &[
-                    v2!(1.0, 0.0),
-                    v2!(1.0, 1.0),
-                    conf.lines[0].1,
-                    conf.lines[0].0,
-                ],
-                0b0011 => &[
-                    v2!(1.0, 1.0),
-                    v2!(0.0, 1.0),
-                    conf.lines[0].0,
-                    conf.lines[0].1,
-                ],
-                0b1001 => &[
-                    v2!(0.0, 1.0),
-                    v2!(0.0, 0.0),
-                    conf.lines[0].0,
-                    conf.lines[0].1,
-                ],
-                // 3 corners active
-                0b0111 => &[
-                    conf.lines[0].0,
-                    conf.lines[0].1,
-                    v2!(1.0, 0.0),
-                    v2!(1.0, 1.0),
-                    v2!(0.0, 1.0),
-                ],
-                0b1011 => &[
-                    v2!(0.0, 0.0),
-                    conf.lines[0].1,
-                    conf.lines[0].0,
-                    v2!(1.0, 1.0),
-                    v2!(0.0, 1.0),
-                ],
-                // TODO: Maybe try switching the points in line in configuration? for the 2 bellow
-                0b1101 => &[
-                    v2!(0.0, 0.0),
-                    v2!(1.0, 0.0),
-                    conf.lines[0].1,
-                    conf.lines[0].0,
-                    v2!(0.0, 1.0),
-                ],
-                0b1110 => &[
-                    v2!(0.0, 0.0),
-                    v2!(1.0, 0.0),
-                    v2!(1.0, 1.0),
-                    conf.lines[0].1,
-                    conf.lines[0].0,
-                ],
-                _ => {
-                    panic!("The renderer does not draw all possible configurations! This is a bug!")
+            // Fainter copies of the cell trail behind it along the flow
+            let blur = self.motion_blur_offset(i);
+            if blur.length_squared() > 0.0 {
+                for copy in (1..=MOTION_BLUR_COPIES).rev() {
+                    let t = copy as f32 / MOTION_BLUR_COPIES as f32;
+                    let fade = copy as f32 / (MOTION_BLUR_COPIES + 1) as f32;
+                    let color = Color {
+                        a: conf.color.a * (1.0 - fade),
+                        ..conf.color
+                    };
+                    self.draw_cell(pos - blur * t, &conf, color);
                 }
-            };
-
-            // Draw the triangulation
-            for Triangle { a, b, c } in triangulate_convex_polygon(points) {
-                draw_triangle(
-                    self.local_point(pos, a).as_mq(),
-                    self.local_point(pos, b).as_mq(),
-                    self.local_point(pos, c).as_mq(),
-                    conf.color.as_mq(),
-                );
             }
+            self.draw_cell(pos, &conf, conf.color);
         }
     }
 }
@@ -4,6 +4,8 @@ mod renderer;
 
 use serde_derive::{Deserialize, Serialize};
 
+use crate::math::Vector2;
+
 pub use draw::*;
 pub use marching_squares_render::MarchingSquaresRenderer;
 pub use renderer::Renderer;
@@ -12,6 +14,32 @@ pub use renderer::Renderer;
 struct SamplePoint {
     scalar_value: f32,
     color: Color,
+    /// Average pressure of the particles around this point weighted by their influence.
+    pressure: f32,
+    /// Average depth of the particles around this point weighted by their influence.
+    depth: f32,
+    /// Average velocity of the particles around this point weighted by their influence.
+    velocity: Vector2<f32>,
+}
+
+/// What is the fluid colored by.
+#[derive(Clone, Copy, PartialEq)]
+pub enum FluidColorMode {
+    /// The colors of the particles.
+    Particle,
+    /// The magnitude of pressure from blue (lowest) to red (highest).
+    Pressure,
+}
+
+/// How are the colors of neighboring fluid samples mixed together.
+#[derive(Clone, Copy, PartialEq)]
+pub enum ColorMixMode {
+    /// The average of the colors.
+    Average,
+    /// The color of the sample with the most fluid. Keeps the boundaries between fluids sharp.
+    Dominant,
+    /// Like mixing light, each channel takes its strongest value, so red and blue make magenta.
+    Additive,
 }
 
 /// Representation of a RGBA color.
@@ -48,4 +76,21 @@ impl Color {
     pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
         Self::rgba(r, g, b, 255)
     }
+
+    /// Linearly interpolates between this color (`t = 0`) and `other` (`t = 1`).
+    pub fn lerp(&self, other: Color, t: f32) -> Color {
+        let t = t.clamp(0.0, 1.0);
+        Color::new(
+            self.r + (other.r - self.r) * t,
+            self.g + (other.g - self.g) * t,
+            self.b + (other.b - self.b) * t,
+            self.a + (other.a - self.a) * t,
+        )
+    }
+
+    /// Returns this color with its RGB channels scaled down by `amount` (in [0..1] range). Alpha is kept.
+    pub fn darker(&self, amount: f32) -> Color {
+        let factor = 1.0 - amount.clamp(0.0, 1.0);
+        Color::new(self.r * factor, self.g * factor, self.b * factor, self.a)
+    }
 }
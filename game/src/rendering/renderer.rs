@@ -1,4 +1,6 @@
-use crate::Sph;
+use crate::{math::Vector2, Sph};
+
+use super::{ColorMixMode, FluidColorMode};
 
 /// Structs that implement this trait are used for rendering to the game screen.
 /// They need to be setup in each iteration and then can draw to screen in their own style.
@@ -9,4 +11,28 @@ pub trait Renderer {
 
     /// Draws to the screen.
     fn draw(&self);
+
+    /// Changes the size of the cells in which the fluid is sampled.
+    fn set_step_size(&mut self, step_size: f32);
+
+    /// Sets the minimum and maximum corner of the visible area. Fluid outside of it is not drawn.
+    fn set_visible_region(&mut self, min: Vector2<f32>, max: Vector2<f32>);
+
+    /// Changes what the fluid is colored by.
+    fn set_color_mode(&mut self, color_mode: FluidColorMode);
+
+    /// Changes how the colors of neighboring samples are blended. Color channels at or below
+    /// `threshold` are left out of the average and the coverage of a cell is raised to
+    /// `alpha_exponent` before it is used as its alpha.
+    fn set_color_blending(&mut self, threshold: f32, alpha_exponent: f32);
+
+    /// Changes how the colors of different fluids are mixed where they meet.
+    fn set_color_mix_mode(&mut self, mix_mode: ColorMixMode);
+
+    /// If true, deeper fluid is drawn darker and more opaque, as if the water was seen from above.
+    fn set_depth_shading(&mut self, depth_shading: bool);
+
+    /// Smears the fluid along its flow as if it was exposed for `exposure` seconds. Zero turns the
+    /// blur off.
+    fn set_motion_blur(&mut self, exposure: f32);
 }
@@ -0,0 +1,103 @@
+use crate::{
+    math::Vector2,
+    physics::rigidbody::{Anchor, DistanceConstraint},
+};
+use serde_derive::{Deserialize, Serialize};
+
+use super::SerializationForm;
+
+#[derive(Serialize, Deserialize)]
+pub enum AnchorSerializedForm {
+    Body(usize),
+    World(Vector2<f32>),
+}
+
+/// A line that the centers of dynamic bodies can not cross, see `RbSimulator::add_half_plane`.
+#[derive(Serialize, Deserialize)]
+pub struct HalfPlaneSerializedForm {
+    pub point: Vector2<f32>,
+    pub normal: Vector2<f32>,
+}
+
+/// The bodies are referenced by their index in the saved list of bodies.
+#[derive(Serialize, Deserialize)]
+pub struct DistanceConstraintSerializedForm {
+    pub index: usize,
+    pub anchor: AnchorSerializedForm,
+    pub length: f32,
+}
+
+impl SerializationForm for DistanceConstraint {
+    type Original = DistanceConstraint;
+
+    type SerializedForm = DistanceConstraintSerializedForm;
+
+    fn to_serialized_form(&self) -> Self::SerializedForm {
+        let anchor = match self.anchor {
+            Anchor::Body(index) => AnchorSerializedForm::Body(index),
+            Anchor::World(point) => AnchorSerializedForm::World(point),
+        };
+
+        DistanceConstraintSerializedForm {
+            index: self.index,
+            anchor,
+            length: self.length,
+        }
+    }
+
+    fn from_serialized_form(serialized_form: Self::SerializedForm) -> Self::Original {
+        let DistanceConstraintSerializedForm {
+            index,
+            anchor,
+            length,
+        } = serialized_form;
+        let anchor = match anchor {
+            AnchorSerializedForm::Body(index) => Anchor::Body(index),
+            AnchorSerializedForm::World(point) => Anchor::World(point),
+        };
+
+        DistanceConstraint {
+            index,
+            anchor,
+            length,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DistanceConstraintSerializedForm;
+    use crate::math::{v2, Vector2};
+    use crate::physics::rigidbody::{Anchor, DistanceConstraint};
+    use crate::serialization::SerializationForm;
+
+    #[test]
+    fn constraint_round_trip() {
+        let constraints = [
+            DistanceConstraint {
+                index: 5,
+                anchor: Anchor::Body(4),
+                length: 15.0,
+            },
+            DistanceConstraint {
+                index: 4,
+                anchor: Anchor::World(v2!(100.0, 50.0)),
+                length: 0.0,
+            },
+        ];
+
+        for constraint in constraints {
+            let json = serde_json::to_string(&constraint.to_serialized_form()).unwrap();
+            let serialized: DistanceConstraintSerializedForm = serde_json::from_str(&json).unwrap();
+            let loaded = DistanceConstraint::from_serialized_form(serialized);
+
+            assert_eq!(loaded.index, constraint.index);
+            assert_eq!(loaded.length, constraint.length);
+            match (loaded.anchor, constraint.anchor) {
+                (Anchor::Body(a), Anchor::Body(b)) => assert_eq!(a, b),
+                (Anchor::World(a), Anchor::World(b)) => assert_eq!(a, b),
+                _ => panic!("The anchor changed its kind"),
+            }
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use std::fs;
+
+use crate::{
+    math::{v2, Vector2},
+    physics::sph::{Particle, Sph},
+    rendering::Color,
+};
+
+impl Sph {
+    /// Replaces all particles with those read from a CSV file at `path`.
+    /// Each row should have the form of `x,y,vx,vy,mass,color` where `color` is a hex string such as
+    /// `#1a2b3c`. Rows that can not be parsed (eg. a header) or that lie outside of the simulated
+    /// area are skipped.
+    ///
+    /// Returns the number of imported particles.
+    pub fn import_csv(&mut self, path: &str) -> std::io::Result<usize> {
+        let content = fs::read_to_string(path)?;
+
+        self.clear_all_particles();
+        let (width, height) = (self.lookup.width, self.lookup.height);
+        let mut count = 0;
+        for line in content.lines() {
+            let Some(particle) = particle_from_csv_row(line) else {
+                continue;
+            };
+
+            let pos = particle.position;
+            if pos.x < 0.0 || pos.x > width || pos.y < 0.0 || pos.y > height {
+                continue;
+            }
+
+            self.add_particle(particle);
+            count += 1;
+        }
+
+        Ok(count)
+    }
+}
+
+fn particle_from_csv_row(row: &str) -> Option<Particle> {
+    let columns: Vec<&str> = row.split(',').map(|c| c.trim()).collect();
+    if columns.len() != 6 {
+        return None;
+    }
+
+    let mut numbers = [0.0; 5];
+    for (number, column) in numbers.iter_mut().zip(&columns) {
+        *number = column.parse::<f32>().ok()?;
+    }
+    let [x, y, vx, vy, mass] = numbers;
+    if mass <= 0.0 {
+        return None;
+    }
+    let color = color_from_hex(columns[5])?;
+
+    let particle = Particle::new_with_velocity(v2!(x, y), v2!(vx, vy))
+        .with_mass(mass)
+        .with_color(color);
+    Some(particle)
+}
+
+/// Parses colors in the `#rrggbb` or `rrggbb` format.
+fn color_from_hex(hex: &str) -> Option<Color> {
+    let hex = hex.trim_start_matches('#');
+    if hex.len() != 6 {
+        return None;
+    }
+
+    let r = u8::from_str_radix(hex.get(0..2)?, 16).ok()?;
+    let g = u8::from_str_radix(hex.get(2..4)?, 16).ok()?;
+    let b = u8::from_str_radix(hex.get(4..6)?, 16).ok()?;
+    Some(Color::rgb(r, g, b))
+}
@@ -0,0 +1,51 @@
+use crate::{math::Vector2, physics::sph::FlowProbe};
+use serde_derive::{Deserialize, Serialize};
+
+use super::SerializationForm;
+
+/// Only the placement of the probe is stored, the measured rate starts from zero after loading.
+#[derive(Serialize, Deserialize)]
+pub struct FlowProbeSerializedForm {
+    pub start: Vector2<f32>,
+    pub end: Vector2<f32>,
+}
+
+impl SerializationForm for FlowProbe {
+    type Original = FlowProbe;
+
+    type SerializedForm = FlowProbeSerializedForm;
+
+    fn to_serialized_form(&self) -> Self::SerializedForm {
+        FlowProbeSerializedForm {
+            start: self.line.start,
+            end: self.line.end,
+        }
+    }
+
+    fn from_serialized_form(serialized_form: Self::SerializedForm) -> Self::Original {
+        let FlowProbeSerializedForm { start, end } = serialized_form;
+
+        FlowProbe::new(start, end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FlowProbeSerializedForm;
+    use crate::math::{v2, Vector2};
+    use crate::physics::sph::FlowProbe;
+    use crate::serialization::SerializationForm;
+
+    #[test]
+    fn flow_probe_round_trip() {
+        let probe = FlowProbe::new(v2!(10.0, 20.0), v2!(30.0, -5.0));
+
+        let json = serde_json::to_string(&probe.to_serialized_form()).unwrap();
+        let serialized: FlowProbeSerializedForm = serde_json::from_str(&json).unwrap();
+        let loaded = FlowProbe::from_serialized_form(serialized);
+
+        assert_eq!(loaded.line.start, probe.line.start);
+        assert_eq!(loaded.line.end, probe.line.end);
+        assert_eq!(loaded.rate(), 0.0);
+    }
+}
@@ -1,13 +1,25 @@
+mod constraint;
+mod csv;
+mod flow_probe;
 mod rigidbody;
 mod sph;
+mod stats;
 
 use crate::{
     game::Game,
-    physics::{rigidbody::RigidBody, sph::Sph},
-    serialization::sph::SphSerializedForm,
+    physics::{
+        rigidbody::{DistanceConstraint, RigidBody},
+        sph::{FlowProbe, Sph},
+    },
+    serialization::{
+        constraint::{DistanceConstraintSerializedForm, HalfPlaneSerializedForm},
+        flow_probe::FlowProbeSerializedForm,
+        sph::SphSerializedForm,
+    },
 };
 pub use rigidbody::{BodySerializationForm, BodySerializedForm};
 use serde_derive::{Deserialize, Serialize};
+pub use stats::{Bounds, SceneStats};
 
 pub trait SerializationForm {
     type Original;
@@ -27,11 +39,22 @@ pub struct GameSerializedForm {
     pub height: f32,
     pub rb: RbSerializedForm,
     pub sph: SphSerializedForm,
+    /// Templates of the body hotbar slots.
+    #[serde(default)]
+    pub hotbar: Vec<Option<BodySerializedForm>>,
+    /// Objects placed in the world besides bodies and fluid. Each is optional so that older saves
+    /// load without them.
+    #[serde(default)]
+    pub flow_probe: Option<FlowProbeSerializedForm>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct RbSerializedForm {
     pub bodies: Vec<BodySerializedForm>,
+    #[serde(default)]
+    pub constraints: Vec<DistanceConstraintSerializedForm>,
+    #[serde(default)]
+    pub half_planes: Vec<HalfPlaneSerializedForm>,
 }
 
 impl SerializationForm for Game {
@@ -56,6 +79,30 @@ impl SerializationForm for Game {
             .iter()
             .map(|body| body.to_serialized_form())
             .collect();
+        let constraints = self
+            .rb_simulator
+            .constraints
+            .iter()
+            .map(|constraint| constraint.to_serialized_form())
+            .collect();
+        let half_planes = self
+            .rb_simulator
+            .half_planes
+            .iter()
+            .map(|plane| HalfPlaneSerializedForm {
+                point: plane.point,
+                normal: plane.normal,
+            })
+            .collect();
+        let hotbar = self
+            .hotbar
+            .iter()
+            .map(|slot| slot.as_ref().map(|body| body.to_serialized_form()))
+            .collect();
+        let flow_probe = self
+            .flow_probe
+            .as_ref()
+            .map(|probe| probe.to_serialized_form());
 
         GameSerializedForm {
             save_name: self.save_name.clone(),
@@ -64,7 +111,13 @@ impl SerializationForm for Game {
             width,
             height,
             sph,
-            rb: RbSerializedForm { bodies },
+            rb: RbSerializedForm {
+                bodies,
+                constraints,
+                half_planes,
+            },
+            hotbar,
+            flow_probe,
         }
     }
 
@@ -77,6 +130,8 @@ impl SerializationForm for Game {
             height,
             sph,
             rb,
+            hotbar,
+            flow_probe,
         } = serialized_form;
 
         let sph = Sph::from_serialized_form(sph);
@@ -85,14 +140,115 @@ impl SerializationForm for Game {
             .into_iter()
             .map(RigidBody::from_serialized_form)
             .collect();
+        let constraints = rb
+            .constraints
+            .into_iter()
+            .map(DistanceConstraint::from_serialized_form)
+            .collect();
 
         let mut game = Game::new(width as usize, height as usize);
         game.fluid_system = sph;
+        // The fluid stores its own size, which must cover the whole world for the neighbor search
+        game.fluid_system.resize(width, height);
         game.rb_simulator.bodies = bodies;
+        game.rb_simulator.constraints = constraints;
+        // An edited save could reference bodies that do not exist
+        game.rb_simulator.remove_invalid_constraints();
+        for HalfPlaneSerializedForm { point, normal } in rb.half_planes {
+            game.rb_simulator.add_half_plane(point, normal);
+        }
         game.name = name;
         game.set_description(description);
         game.save_name = save_name;
+        for (slot, template) in game.hotbar.iter_mut().zip(hotbar) {
+            *slot = template.map(RigidBody::from_serialized_form);
+        }
+        game.flow_probe = flow_probe.map(FlowProbe::from_serialized_form);
 
         game
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{GameSerializedForm, SerializationForm};
+    use crate::game::Game;
+    use crate::math::{v2, Vector2};
+    use crate::physics::rigidbody::{
+        Anchor, BodyBehaviour, DistanceConstraint, Rectangle, RigidBody,
+    };
+    use crate::physics::sph::FlowProbe;
+
+    #[test]
+    fn game_round_trip() {
+        let mut game = Game::new(800, 600);
+        let simulator = &mut game.rb_simulator;
+        // After the walls
+        let first = simulator.bodies.len();
+        simulator
+            .bodies
+            .push(Rectangle!(v2!(200.0, 300.0); 40.0, 20.0; BodyBehaviour::Dynamic));
+        simulator
+            .bodies
+            .push(Rectangle!(v2!(260.0, 300.0); 30.0, 30.0; BodyBehaviour::Static));
+        simulator.constraints.push(DistanceConstraint {
+            index: first,
+            anchor: Anchor::Body(first + 1),
+            length: 60.0,
+        });
+        simulator.constraints.push(DistanceConstraint {
+            index: first,
+            anchor: Anchor::World(v2!(200.0, 100.0)),
+            length: 200.0,
+        });
+        simulator.add_half_plane(v2!(0.0, 500.0), v2!(0.0, -1.0));
+        game.hotbar[2] = Some(Rectangle!(v2!(0.0, 0.0); 10.0, 50.0; BodyBehaviour::Dynamic));
+        game.flow_probe = Some(FlowProbe::new(v2!(100.0, 50.0), v2!(100.0, 250.0)));
+
+        let json = serde_json::to_string(&game.to_serialized_form()).unwrap();
+        let serialized: GameSerializedForm = serde_json::from_str(&json).unwrap();
+        let loaded = Game::from_serialized_form(serialized);
+
+        let same_body = |a: &RigidBody, b: &RigidBody| {
+            let (a, b) = (a.state(), b.state());
+            a.position == b.position && a.orientation == b.orientation && a.behaviour == b.behaviour
+        };
+        let (original, restored) = (&game.rb_simulator, &loaded.rb_simulator);
+        assert_eq!(original.bodies.len(), restored.bodies.len());
+        for (a, b) in original.bodies.iter().zip(&restored.bodies) {
+            assert!(same_body(a, b));
+        }
+
+        assert_eq!(original.constraints.len(), restored.constraints.len());
+        for (a, b) in original.constraints.iter().zip(&restored.constraints) {
+            assert_eq!(a.index, b.index);
+            assert_eq!(a.length, b.length);
+            match (a.anchor, b.anchor) {
+                (Anchor::Body(a), Anchor::Body(b)) => assert_eq!(a, b),
+                (Anchor::World(a), Anchor::World(b)) => assert_eq!(a, b),
+                _ => panic!("The anchor changed its kind"),
+            }
+        }
+
+        assert_eq!(original.half_planes.len(), restored.half_planes.len());
+        for (a, b) in original.half_planes.iter().zip(&restored.half_planes) {
+            assert_eq!(a.point, b.point);
+            assert_eq!(a.normal, b.normal);
+        }
+
+        for (a, b) in game.hotbar.iter().zip(&loaded.hotbar) {
+            match (a, b) {
+                (Some(a), Some(b)) => assert!(same_body(a, b)),
+                (None, None) => {}
+                _ => panic!("A hotbar slot was not restored"),
+            }
+        }
+
+        let (original, restored) = (
+            game.flow_probe.as_ref().unwrap(),
+            loaded.flow_probe.as_ref().unwrap(),
+        );
+        assert_eq!(original.line.start, restored.line.start);
+        assert_eq!(original.line.end, restored.line.end);
+    }
+}
@@ -26,6 +26,12 @@ pub struct BodyStateSerializedForm {
     pub orientation: f32,
     #[serde(default)]
     pub lock_rotation: bool,
+    #[serde(default = "default_true")]
+    pub collides_with_fluid: bool,
+    #[serde(default = "default_true")]
+    pub collides_with_bodies: bool,
+    #[serde(default)]
+    pub draw_over_fluid: bool,
 
     pub behaviour: BodyBehaviour,
     pub mass: f32,
@@ -35,6 +41,12 @@ pub struct BodyStateSerializedForm {
     pub dynamic_friction: SharedProperty<f32>,
 
     pub color: Color,
+    #[serde(default)]
+    pub tag: Option<String>,
+}
+
+fn default_true() -> bool {
+    true
 }
 
 impl From<BodyState> for BodyStateSerializedForm {
@@ -43,6 +55,9 @@ impl From<BodyState> for BodyStateSerializedForm {
             position,
             orientation,
             lock_rotation,
+            collides_with_fluid,
+            collides_with_bodies,
+            draw_over_fluid,
             behaviour,
             mass,
             moment_of_inertia,
@@ -50,6 +65,7 @@ impl From<BodyState> for BodyStateSerializedForm {
             static_friction,
             dynamic_friction,
             color,
+            tag,
             ..
         } = body_state;
 
@@ -57,6 +73,9 @@ impl From<BodyState> for BodyStateSerializedForm {
             position,
             orientation,
             lock_rotation,
+            collides_with_fluid,
+            collides_with_bodies,
+            draw_over_fluid,
             behaviour,
             mass,
             moment_of_inertia,
@@ -64,6 +83,7 @@ impl From<BodyState> for BodyStateSerializedForm {
             static_friction,
             dynamic_friction,
             color,
+            tag,
         }
     }
 }
@@ -74,6 +94,9 @@ impl From<BodyStateSerializedForm> for BodyState {
             position,
             orientation,
             lock_rotation,
+            collides_with_fluid,
+            collides_with_bodies,
+            draw_over_fluid,
             behaviour,
             mass,
             moment_of_inertia,
@@ -81,12 +104,16 @@ impl From<BodyStateSerializedForm> for BodyState {
             static_friction,
             dynamic_friction,
             color,
+            tag,
         } = serialized_from;
 
         BodyState {
             position,
             orientation,
             lock_rotation,
+            collides_with_fluid,
+            collides_with_bodies,
+            draw_over_fluid,
             behaviour,
             mass,
             moment_of_inertia,
@@ -94,6 +121,9 @@ impl From<BodyStateSerializedForm> for BodyState {
             static_friction,
             dynamic_friction,
             color,
+            tag,
+            previous_position: position,
+            previous_orientation: orientation,
             ..Default::default()
         }
     }
@@ -154,3 +184,31 @@ impl BodySerializationForm for RigidBody {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{BodySerializationForm, BodySerializedForm};
+    use crate::math::{v2, Vector2};
+    use crate::physics::rigidbody::{BodyBehaviour, Rectangle, RigidBody};
+
+    #[test]
+    fn loaded_bodies_keep_collision_flags() {
+        let mut ghost = RigidBody::new_circle(v2!(10.0, 10.0), 5.0, BodyBehaviour::Dynamic);
+        ghost.state_mut().collides_with_bodies = false;
+        let solid = Rectangle!(v2!(50.0, 50.0); 10.0, 20.0; BodyBehaviour::Static);
+
+        for body in [ghost, solid] {
+            let json = serde_json::to_string(&body.to_serialized_form()).unwrap();
+            let serialized: BodySerializedForm = serde_json::from_str(&json).unwrap();
+            let loaded = RigidBody::from_serialized_form(serialized);
+
+            let (state, loaded_state) = (body.state(), loaded.state());
+            assert_eq!(loaded_state.position, state.position);
+            assert_eq!(
+                loaded_state.collides_with_bodies,
+                state.collides_with_bodies
+            );
+            assert_eq!(loaded_state.collides_with_fluid, state.collides_with_fluid);
+        }
+    }
+}
@@ -21,8 +21,7 @@ impl SerializationForm for Sph {
 
     fn to_serialized_form(&self) -> Self::SerializedForm {
         let ser_form_particles: Vec<ParticleSerializedForm> = self
-            .particles
-            .iter()
+            .iter_particles()
             .map(|p| p.to_serialized_form())
             .collect();
 
@@ -64,6 +63,10 @@ pub struct ParticleSerializedForm {
     /// ammount of fluid hitting the object instead of only a few particles.
     pub body_collision_force_multiplier: f32,
     pub color: Color,
+    #[serde(default)]
+    pub charge: f32,
+    #[serde(default)]
+    pub viscosity: f32,
 }
 
 impl SerializationForm for Particle {
@@ -79,6 +82,8 @@ impl SerializationForm for Particle {
             pressure_multiplier,
             body_collision_force_multiplier,
             color,
+            charge,
+            viscosity,
             ..
         } = *self;
 
@@ -89,6 +94,8 @@ impl SerializationForm for Particle {
             pressure_multiplier,
             body_collision_force_multiplier,
             color,
+            charge,
+            viscosity,
         }
     }
 
@@ -100,6 +107,8 @@ impl SerializationForm for Particle {
             pressure_multiplier,
             body_collision_force_multiplier,
             color,
+            charge,
+            viscosity,
         } = serialized_form;
 
         Particle {
@@ -109,6 +118,9 @@ impl SerializationForm for Particle {
             pressure_multiplier,
             body_collision_force_multiplier,
             color,
+            charge,
+            viscosity,
+            previous_position: position,
             ..Default::default()
         }
     }
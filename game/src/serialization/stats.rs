@@ -0,0 +1,34 @@
+use serde_derive::Serialize;
+
+use crate::{game::GameConfig, math::Vector2};
+
+/// A summary of the current scene meant for collecting results of experiments.
+///
+/// The JSON schema follows the field names below. New fields may be added but existing ones will
+/// not be renamed or removed.
+#[derive(Serialize)]
+pub struct SceneStats {
+    /// Number of rigidbodies including the 4 walls.
+    pub body_count: usize,
+    /// Number of fluid particles.
+    pub particle_count: usize,
+    /// Sum of translational and rotational kinetic energies of all rigidbodies.
+    pub body_kinetic_energy: f32,
+    /// Sum of kinetic energies of all fluid particles.
+    pub fluid_kinetic_energy: f32,
+    /// Sum of masses of all fluid particles.
+    pub fluid_mass: f32,
+    /// The axis aligned box enclosing all bodies except the walls. `null` if there are no such bodies.
+    pub bounds: Option<Bounds>,
+    /// The axis aligned box enclosing all bodies except the walls and all fluid particles. The
+    /// whole world if there is nothing.
+    pub scene_bounds: Bounds,
+    /// The configuration the simulation is running with.
+    pub config: GameConfig,
+}
+
+#[derive(Serialize)]
+pub struct Bounds {
+    pub min: Vector2<f32>,
+    pub max: Vector2<f32>,
+}
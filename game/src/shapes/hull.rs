@@ -0,0 +1,94 @@
+use crate::math::Vector2;
+
+/// Returns the convex hull of `points` using Andrew's monotone chain algorithm.
+/// The hull goes around in a circle without repeating the first point and without collinear
+/// points, so it can be used directly as the vertices of a polygon.
+pub fn convex_hull(points: &[Vector2<f32>]) -> Vec<Vector2<f32>> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    sorted.dedup();
+    if sorted.len() < 3 {
+        return sorted;
+    }
+
+    // Adds the points to the chain while removing the points that do not make a turn
+    let build_chain = |points: &mut dyn Iterator<Item = &Vector2<f32>>| {
+        let mut chain: Vec<Vector2<f32>> = Vec::new();
+        for &point in points {
+            while chain.len() >= 2 {
+                let last = chain[chain.len() - 1];
+                let before_last = chain[chain.len() - 2];
+                if (last - before_last).cross(point - before_last) > 0.0 {
+                    break;
+                }
+                chain.pop();
+            }
+            chain.push(point);
+        }
+        // The last point is the first point of the other chain
+        chain.pop();
+        chain
+    };
+
+    let mut hull = build_chain(&mut sorted.iter());
+    hull.extend(build_chain(&mut sorted.iter().rev()));
+
+    hull
+}
+
+#[cfg(test)]
+mod tests {
+    use super::convex_hull;
+    use crate::math::{v2, Vector2};
+    use crate::shapes::triangulate_convex_polygon;
+
+    fn contains_same_points(hull: &[Vector2<f32>], expected: &[Vector2<f32>]) -> bool {
+        hull.len() == expected.len() && expected.iter().all(|p| hull.contains(p))
+    }
+
+    #[test]
+    fn square_with_interior_point() {
+        let corners = [
+            v2!(0.0, 0.0),
+            v2!(10.0, 0.0),
+            v2!(10.0, 10.0),
+            v2!(0.0, 10.0),
+        ];
+        let mut points = corners.to_vec();
+        points.insert(2, v2!(5.0, 5.0));
+
+        let hull = convex_hull(&points);
+
+        assert!(contains_same_points(&hull, &corners), "{hull:?}");
+        // The points are in order so the triangles cover the square exactly
+        let area: f32 = triangulate_convex_polygon(&hull)
+            .iter()
+            .map(|t| t.area())
+            .sum();
+        assert_eq!(area, 100.0);
+    }
+
+    #[test]
+    fn collinear_points_are_skipped() {
+        let corners = [
+            v2!(0.0, 0.0),
+            v2!(10.0, 0.0),
+            v2!(10.0, 10.0),
+            v2!(0.0, 10.0),
+        ];
+        // Middles of the edges
+        let mut points = corners.to_vec();
+        points.extend([v2!(5.0, 0.0), v2!(10.0, 5.0), v2!(5.0, 10.0), v2!(0.0, 5.0)]);
+
+        let hull = convex_hull(&points);
+        assert!(contains_same_points(&hull, &corners), "{hull:?}");
+
+        // Points on a single line only keep the ends
+        let line = [v2!(0.0, 0.0), v2!(1.0, 1.0), v2!(2.0, 2.0)];
+        let hull = convex_hull(&line);
+        assert!(
+            contains_same_points(&hull, &[v2!(0.0, 0.0), v2!(2.0, 2.0)]),
+            "{hull:?}"
+        );
+    }
+}
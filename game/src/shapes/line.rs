@@ -39,4 +39,32 @@ impl Line {
     pub fn middle(&self) -> Vector2<f32> {
         (self.start + self.end) * 0.5
     }
+
+    /// Returns true if this line segment crosses or touches the `other` line segment.
+    /// Parallel segments never intersect.
+    pub fn intersects(&self, other: &Line) -> bool {
+        let denominator = self.vector.cross(other.vector);
+        if denominator == 0.0 {
+            return false;
+        }
+
+        let start_diff = other.start - self.start;
+        let t = start_diff.cross(other.vector) / denominator;
+        let u = start_diff.cross(self.vector) / denominator;
+        (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u)
+    }
+
+    /// Returns the distance from `origin` along the unit `direction` at which the ray hits this
+    /// line segment. Rays parallel to the segment never hit it.
+    pub fn ray_intersection(&self, origin: Vector2<f32>, direction: Vector2<f32>) -> Option<f32> {
+        let denominator = direction.cross(self.vector);
+        if denominator == 0.0 {
+            return None;
+        }
+
+        let start_diff = self.start - origin;
+        let t = start_diff.cross(self.vector) / denominator;
+        let u = start_diff.cross(direction) / denominator;
+        (t >= 0.0 && (0.0..=1.0).contains(&u)).then_some(t)
+    }
 }
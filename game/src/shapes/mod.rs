@@ -1,5 +1,7 @@
+mod hull;
 mod line;
 mod triangle;
 
+pub use hull::*;
 pub use line::*;
 pub use triangle::*;
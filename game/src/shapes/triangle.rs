@@ -22,6 +22,7 @@ pub fn triangulate_convex_polygon(points: &[Vector2<f32>]) -> Triangulation {
     triangles
 }
 
+#[derive(Clone)]
 pub struct Triangle {
     pub a: Vector2<f32>,
     pub b: Vector2<f32>,
@@ -48,4 +49,33 @@ impl Triangle {
 
         v >= 0.0 && w >= 0.0 && u >= 0.0
     }
+
+    pub fn area(&self) -> f32 {
+        0.5 * (self.b - self.a).cross(self.c - self.a).abs()
+    }
+
+    pub fn centroid(&self) -> Vector2<f32> {
+        (self.a + self.b + self.c) / 3.0
+    }
+
+    /// Splits the triangle into 2 halves by connecting the middle of its longest edge with the
+    /// opposite vertex. The order of vertices (winding) is kept.
+    pub fn split(&self) -> (Triangle, Triangle) {
+        let Triangle { a, b, c } = self.clone();
+        let ab = (b - a).length_squared();
+        let bc = (c - b).length_squared();
+        let ca = (a - c).length_squared();
+
+        // Rotate the vertices so that the longest edge is `a -> b`
+        let (a, b, c) = if ab >= bc && ab >= ca {
+            (a, b, c)
+        } else if bc >= ca {
+            (b, c, a)
+        } else {
+            (c, a, b)
+        };
+        let middle = (a + b) * 0.5;
+
+        (Triangle { a, b: middle, c }, Triangle { a: middle, b, c })
+    }
 }
@@ -43,6 +43,16 @@ where
 {
     /// Cell size should be equal to smoothing radius
     pub fn new(width: f32, height: f32, cell_size: f32) -> Self {
+        LookUp {
+            cells: Self::empty_cells(width, height, cell_size),
+            width,
+            height,
+            cell_size,
+        }
+    }
+
+    /// Returns a grid of empty cells covering the given area.
+    fn empty_cells(width: f32, height: f32, cell_size: f32) -> Vec<Vec<Cell<T>>> {
         let mut cols_count = (width / cell_size) as usize;
         let mut rows_count = (height / cell_size) as usize;
         if width % cell_size > 0.0 {
@@ -52,12 +62,16 @@ where
             rows_count += 1;
         }
 
-        LookUp {
-            cells: vec![vec![Cell::empty(); cols_count]; rows_count],
-            width,
-            height,
-            cell_size,
-        }
+        vec![vec![Cell::empty(); cols_count]; rows_count]
+    }
+
+    /// Changes the covered area while keeping the cell size. Positions outside of the area are
+    /// neither inserted nor found, so this has to be called whenever the world changes its size.
+    /// All items are removed and have to be inserted again.
+    pub fn resize(&mut self, width: f32, height: f32) {
+        self.cells = Self::empty_cells(width, height, self.cell_size);
+        self.width = width;
+        self.height = height;
     }
 
     pub fn clear(&mut self) {
@@ -122,3 +136,22 @@ where
         neighbors
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::LookUp;
+    use crate::math::{v2, Vector2};
+
+    #[test]
+    fn resized_lookup_finds_items_in_the_new_area() {
+        let mut lookup = LookUp::new(100.0, 100.0, 10.0);
+        let position = v2!(150.0, 50.0);
+        lookup.insert(&position, 1);
+        assert_eq!(lookup.get_immediate_neighbors(&position).iter().count(), 0);
+
+        lookup.resize(200.0, 100.0);
+        lookup.insert(&position, 1);
+
+        assert_eq!(lookup.get_immediate_neighbors(&position).iter().count(), 1);
+    }
+}
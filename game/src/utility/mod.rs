@@ -2,8 +2,12 @@ mod connectors;
 mod linked_linked_list;
 mod lookup;
 mod numerical;
+mod state_hasher;
+mod union_find;
 
 pub use connectors::*;
 pub use linked_linked_list::LinkedLinkedList;
 pub use lookup::LookUp;
 pub use numerical::*;
+pub use state_hasher::StateHasher;
+pub use union_find::UnionFind;
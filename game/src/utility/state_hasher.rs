@@ -0,0 +1,125 @@
+use crate::math::Vector2;
+use crate::physics::{rigidbody::RigidBody, sph::Particle};
+
+/// Values are rounded to multiples of `1 / PRECISION` before hashing.
+const PRECISION: f32 = 1000.0;
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Hashes simulation values into a number that stays the same between runs, platforms and Rust
+/// versions, unlike the hasher of the standard library. Floats are quantized first, so tiny
+/// differences do not change the hash. Values that land right at a rounding boundary still can.
+pub struct StateHasher {
+    hash: u64,
+}
+
+impl Default for StateHasher {
+    fn default() -> Self {
+        StateHasher {
+            hash: FNV_OFFSET_BASIS,
+        }
+    }
+}
+
+impl StateHasher {
+    pub fn write_f32(&mut self, value: f32) {
+        let quantized = (value * PRECISION).round() as i64;
+        // FNV-1a
+        for byte in quantized.to_le_bytes() {
+            self.hash ^= byte as u64;
+            self.hash = self.hash.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    pub fn write_vector(&mut self, vector: Vector2<f32>) {
+        self.write_f32(vector.x);
+        self.write_f32(vector.y);
+    }
+
+    /// Writes the positions and orientations of the bodies in their order.
+    pub fn write_bodies(&mut self, bodies: &[RigidBody]) {
+        for body in bodies {
+            self.write_vector(body.state().position);
+            self.write_f32(body.state().orientation);
+        }
+    }
+
+    /// Writes the positions of the particles in their order.
+    pub fn write_particles<'a>(&mut self, particles: impl IntoIterator<Item = &'a Particle>) {
+        for particle in particles {
+            self.write_vector(particle.position);
+        }
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StateHasher;
+    use crate::game::GameConfig;
+    use crate::math::{v2, Vector2};
+    use crate::physics::rigidbody::{BodyBehaviour, RbSimulator, Rectangle, RigidBody};
+    use crate::physics::sph::{Particle, Sph};
+    use crate::physics::units::from_cm;
+
+    fn hash_of(vectors: &[Vector2<f32>]) -> u64 {
+        let mut hasher = StateHasher::default();
+        for vector in vectors {
+            hasher.write_vector(*vector);
+        }
+        hasher.finish()
+    }
+
+    #[test]
+    fn ignores_noise_but_not_order() {
+        let a = v2!(1.0, 2.0);
+        let b = v2!(3.5, -4.0);
+
+        assert_eq!(hash_of(&[a, b]), hash_of(&[a + v2!(0.00001, 0.0), b]));
+        assert_ne!(hash_of(&[a, b]), hash_of(&[b, a]));
+        assert_ne!(hash_of(&[a, b]), hash_of(&[a, b + v2!(0.01, 0.0)]));
+    }
+
+    #[test]
+    fn stepped_scene_has_known_hash() {
+        let mut config = GameConfig::default();
+        config.sph_config.deterministic = true;
+        let mut simulator = RbSimulator::new(from_cm(config.gravity));
+        simulator.bodies = vec![
+            Rectangle!(v2!(100.0, 190.0); 200.0, 20.0; BodyBehaviour::Static),
+            Rectangle!(v2!(60.0, 100.0); 30.0, 30.0; BodyBehaviour::Dynamic),
+            RigidBody::new_circle(v2!(140.0, 60.0), 10.0, BodyBehaviour::Dynamic),
+        ];
+        simulator.bodies[1].state_mut().orientation = 0.3;
+        simulator.bodies[1].update_inner_values();
+        let mut sph = Sph::new(200.0, 200.0);
+        for i in 0..100 {
+            let position = v2!(80.0 + (i % 10) as f32 * 4.0, 120.0 + (i / 10) as f32 * 4.0);
+            sph.add_particle(Particle::new(position));
+        }
+
+        for _ in 0..100 {
+            let forces = sph.step(&simulator.bodies, &config, config.time_step);
+            for (index, force_accumulation) in forces {
+                let state = simulator.bodies[index].state_mut();
+                state.add_force_accumulation(force_accumulation);
+                state.apply_accumulated_forces(config.time_step);
+            }
+            simulator.step(&config, config.time_step);
+        }
+
+        let mut hasher = StateHasher::default();
+        hasher.write_bodies(&simulator.bodies);
+        hasher.write_particles(sph.iter_particles());
+        // Changes when the simulation changes. Update it only if that was intended.
+        assert_eq!(
+            hasher.finish(),
+            0xc064_9bc0_e1fb_30dc,
+            "{:#x}",
+            hasher.finish()
+        );
+    }
+}
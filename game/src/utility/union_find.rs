@@ -0,0 +1,66 @@
+/// Disjoint-set structure over the indexes `0..size`.
+pub struct UnionFind {
+    parents: Vec<usize>,
+    set_count: usize,
+}
+
+impl UnionFind {
+    /// Creates `size` sets each containing only its own index.
+    pub fn new(size: usize) -> Self {
+        UnionFind {
+            parents: (0..size).collect(),
+            set_count: size,
+        }
+    }
+
+    /// Returns the representative index of the set containing `index`.
+    pub fn find(&mut self, index: usize) -> usize {
+        let mut root = index;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+
+        // Compress the path so following lookups are faster
+        let mut current = index;
+        while self.parents[current] != root {
+            let next = self.parents[current];
+            self.parents[current] = root;
+            current = next;
+        }
+
+        root
+    }
+
+    /// Merges the sets containing `a` and `b`.
+    pub fn union(&mut self, a: usize, b: usize) {
+        let root_a = self.find(a);
+        let root_b = self.find(b);
+        if root_a != root_b {
+            self.parents[root_b] = root_a;
+            self.set_count -= 1;
+        }
+    }
+
+    /// Number of distinct sets.
+    pub fn set_count(&self) -> usize {
+        self.set_count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UnionFind;
+
+    #[test]
+    fn union_merges_sets() {
+        let mut sets = UnionFind::new(5);
+        sets.union(0, 1);
+        sets.union(3, 4);
+        sets.union(1, 0);
+
+        assert_eq!(sets.set_count(), 3);
+        assert_eq!(sets.find(0), sets.find(1));
+        assert_eq!(sets.find(3), sets.find(4));
+        assert_ne!(sets.find(1), sets.find(2));
+    }
+}